@@ -17,6 +17,7 @@ pub struct Options {
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
     flag_message_format: MessageFormat,
+    flag_profile: Option<String>,
     flag_lib: bool,
     flag_bin: Vec<String>,
     flag_example: Vec<String>,
@@ -24,6 +25,7 @@ pub struct Options {
     flag_bench: Vec<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
     arg_args: Vec<String>,
 }
 
@@ -46,6 +48,7 @@ Options:
     --features FEATURES          Space-separated list of features to also build
     --all-features               Build all available features
     --no-default-features        Do not build the `default` feature
+    --profile NAME               Build artifacts with the specified profile
     --target TRIPLE              Build for the target triple
     --manifest-path PATH         Path to the manifest to build benchmarks for
     -v, --verbose ...            Use verbose output (-vv very verbose/build.rs output)
@@ -54,6 +57,7 @@ Options:
     --message-format FMT         Error format: human, json [default: human]
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --ignore-rust-version        Ignore `rust-version` specification in packages
 
 All of the trailing arguments are passed to the benchmark binaries generated
 for filtering benchmarks and generally providing options configuring how they
@@ -67,7 +71,8 @@ see the `cargo help pkgid` command.
 The --jobs argument affects the building of the benchmark executable but does
 not affect how many jobs are used when running the benchmarks.
 
-Compilation can be customized with the `bench` profile in the manifest.
+Compilation can be customized with the `bench` profile in the manifest, or a
+custom profile selected with the --profile flag.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult {
@@ -90,6 +95,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
             no_default_features: options.flag_no_default_features,
             spec: ops::Packages::Packages(&options.flag_package),
             release: true,
+            profile: options.flag_profile.as_ref().map(|s| &s[..]),
+            ignore_rust_version: options.flag_ignore_rust_version,
+            build_plan: false,
             mode: ops::CompileMode::Bench,
             filter: ops::CompileFilter::new(options.flag_lib,
                                             &options.flag_bin,
@@ -3,7 +3,7 @@ use std::env;
 use cargo::core::Workspace;
 use cargo::ops::{self, CompileOptions, MessageFormat, Packages};
 use cargo::util::important_paths::{find_root_manifest_for_wd};
-use cargo::util::{CliResult, Config};
+use cargo::util::{CliResult, Config, human};
 
 #[derive(RustcDecodable)]
 pub struct Options {
@@ -19,12 +19,15 @@ pub struct Options {
     flag_color: Option<String>,
     flag_message_format: MessageFormat,
     flag_release: bool,
+    flag_profile: Option<String>,
     flag_lib: bool,
     flag_bin: Vec<String>,
     flag_example: Vec<String>,
     flag_test: Vec<String>,
     flag_bench: Vec<String>,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
+    flag_build_plan: bool,
     flag_frozen: bool,
     flag_all: bool,
 }
@@ -46,6 +49,7 @@ Options:
     --test NAME                  Build only the specified test target
     --bench NAME                 Build only the specified benchmark target
     --release                    Build artifacts in release mode, with optimizations
+    --profile NAME               Build artifacts with the specified profile
     --features FEATURES          Space-separated list of features to also build
     --all-features               Build all available features
     --no-default-features        Do not build the `default` feature
@@ -57,6 +61,9 @@ Options:
     --message-format FMT         Error format: human, json [default: human]
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --ignore-rust-version        Ignore `rust-version` specification in packages
+    --build-plan                 Print the commands which would be run as JSON
+                                 instead of building anything
 
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package should be built. If it is not given, then the
@@ -68,7 +75,13 @@ All packages in the workspace are built if the `--all` flag is supplied. The
 
 Compilation can be configured via the use of profiles which are configured in
 the manifest. The default profile for this command is `dev`, but passing
-the --release flag will use the `release` profile instead.
+the --release flag will use the `release` profile instead. Custom profiles
+defined in the manifest can be selected with the --profile flag.
+
+With --build-plan nothing is compiled. Instead the invocations of rustc and of
+build scripts are printed in the order they have to run in. Build scripts are
+not run, so the flags and environment variables they would add to later
+invocations are left for the consumer of the plan to fill in.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult {
@@ -82,6 +95,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
 
     let root = find_root_manifest_for_wd(options.flag_manifest_path, config.cwd())?;
 
+    if options.flag_release && options.flag_profile.is_some() {
+        return Err(human("the `--release` flag cannot be combined with \
+                          `--profile`").into())
+    }
+
     let spec = if options.flag_all {
         Packages::All
     } else {
@@ -98,6 +116,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         spec: spec,
         mode: ops::CompileMode::Build,
         release: options.flag_release,
+        profile: options.flag_profile.as_ref().map(|s| &s[..]),
+        ignore_rust_version: options.flag_ignore_rust_version,
+        build_plan: options.flag_build_plan,
         filter: ops::CompileFilter::new(options.flag_lib,
                                         &options.flag_bin,
                                         &options.flag_test,
@@ -124,6 +124,7 @@ macro_rules! each_subcommand{
         $mac!(rustdoc);
         $mac!(search);
         $mac!(test);
+        $mac!(tree);
         $mac!(uninstall);
         $mac!(update);
         $mac!(verify_project);
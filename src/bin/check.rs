@@ -33,6 +33,7 @@ Options:
     --message-format FMT         Error format: human, json [default: human]
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --ignore-rust-version        Ignore `rust-version` specification in packages
 
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package should be built. If it is not given, then the
@@ -64,6 +65,7 @@ pub struct Options {
     flag_test: Vec<String>,
     flag_bench: Vec<String>,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
     flag_frozen: bool,
     flag_all: bool,
 }
@@ -97,6 +99,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         spec: spec,
         mode: ops::CompileMode::Check,
         release: options.flag_release,
+        profile: None,
+        ignore_rust_version: options.flag_ignore_rust_version,
+        build_plan: false,
         filter: ops::CompileFilter::new(options.flag_lib,
                                         &options.flag_bin,
                                         &options.flag_test,
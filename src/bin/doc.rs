@@ -23,6 +23,7 @@ pub struct Options {
     flag_bin: Vec<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
     flag_all: bool,
 }
 
@@ -53,6 +54,7 @@ Options:
     --message-format FMT         Error format: human, json [default: human]
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --ignore-rust-version        Ignore `rust-version` specification in packages
 
 By default the documentation for the local package and all dependencies is
 built. The output is all placed in `target/doc` in rustdoc's usual format.
@@ -99,6 +101,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
                                             &empty),
             message_format: options.flag_message_format,
             release: options.flag_release,
+            profile: None,
+            ignore_rust_version: options.flag_ignore_rust_version,
+            build_plan: false,
             mode: ops::CompileMode::Doc {
                 deps: !options.flag_no_deps,
             },
@@ -13,6 +13,7 @@ pub struct Options {
     flag_color: Option<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_minimal_versions: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -29,6 +30,7 @@ Options:
     --color WHEN             Coloring: auto, always, never
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --minimal-versions       Resolve dependencies to their lowest allowed versions
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult {
@@ -38,6 +40,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
                      &options.flag_color,
                      options.flag_frozen,
                      options.flag_locked)?;
+    config.set_minimal_versions(options.flag_minimal_versions);
     let root = find_root_manifest_for_wd(options.flag_manifest_path, config.cwd())?;
 
     let ws = Workspace::new(&root, config)?;
@@ -9,6 +9,7 @@ pub struct Options {
     flag_all_features: bool,
     flag_no_default_features: bool,
     flag_debug: bool,
+    flag_profile: Option<String>,
     flag_bin: Vec<String>,
     flag_example: Vec<String>,
     flag_verbose: u32,
@@ -19,6 +20,7 @@ pub struct Options {
     flag_force: bool,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
 
     arg_crate: Option<String>,
     flag_vers: Option<String>,
@@ -54,6 +56,7 @@ Build and install options:
     --all-features            Build all available features
     --no-default-features     Do not build the `default` feature
     --debug                   Build in debug mode instead of release mode
+    --profile NAME            Build with the specified profile
     --bin NAME                Only install the binary NAME
     --example EXAMPLE         Install the example EXAMPLE instead of binaries
     --root DIR                Directory to install packages into
@@ -62,6 +65,7 @@ Build and install options:
     --color WHEN              Coloring: auto, always, never
     --frozen                  Require Cargo.lock and cache are up to date
     --locked                  Require Cargo.lock is up to date
+    --ignore-rust-version     Ignore `rust-version` specification in packages
 
 This command manages Cargo's local set of installed binary crates. Only packages
 which have [[bin]] targets can be installed, and all binaries are installed into
@@ -111,6 +115,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         spec: ops::Packages::Packages(&[]),
         mode: ops::CompileMode::Build,
         release: !options.flag_debug,
+        profile: options.flag_profile.as_ref().map(|s| &s[..]),
+        ignore_rust_version: options.flag_ignore_rust_version,
+        build_plan: false,
         filter: ops::CompileFilter::new(false, &options.flag_bin, &[],
                                         &options.flag_example, &[]),
         message_format: ops::MessageFormat::Human,
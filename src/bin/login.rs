@@ -15,6 +15,7 @@ pub struct Options {
     flag_color: Option<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_registry: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -31,6 +32,7 @@ Options:
     --color WHEN             Coloring: auto, always, never
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --registry REGISTRY      Registry to use
 
 ";
 
@@ -43,7 +45,10 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
     let token = match options.arg_token.clone() {
         Some(token) => token,
         None => {
-            let src = SourceId::crates_io(config)?;
+            let src = match options.flag_registry {
+                Some(ref registry) => SourceId::alt_registry(config, registry)?,
+                None => SourceId::crates_io(config)?,
+            };
             let mut src = RegistrySource::remote(&src, config);
             src.update()?;
             let config = src.config()?.unwrap();
@@ -59,7 +64,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
     };
 
     let token = token.trim().to_string();
-    ops::registry_login(config, token)?;
+    ops::registry_login(config, token, options.flag_registry)?;
     Ok(())
 }
 
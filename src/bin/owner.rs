@@ -14,6 +14,7 @@ pub struct Options {
     flag_list: bool,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_registry: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -34,6 +35,7 @@ Options:
     --color WHEN             Coloring: auto, always, never
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --registry REGISTRY      Registry to use
 
 This command will modify the owners for a package on the specified registry (or
 default). Note that owners of a package can upload new versions, yank old
@@ -57,6 +59,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         to_add: options.flag_add,
         to_remove: options.flag_remove,
         list: options.flag_list,
+        registry: options.flag_registry,
     };
     ops::modify_owners(config, &opts)?;
     Ok(())
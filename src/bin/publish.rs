@@ -17,6 +17,7 @@ pub struct Options {
     flag_dry_run: bool,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_registry: Option<String>,
 }
 
 pub const USAGE: &'static str = "
@@ -39,6 +40,7 @@ Options:
     --color WHEN             Coloring: auto, always, never
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --registry REGISTRY      Registry to publish to
 
 ";
 
@@ -56,6 +58,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         flag_allow_dirty: allow_dirty,
         flag_jobs: jobs,
         flag_dry_run: dry_run,
+        flag_registry: registry,
         ..
     } = options;
 
@@ -69,6 +72,7 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         allow_dirty: allow_dirty,
         jobs: jobs,
         dry_run: dry_run,
+        registry: registry,
     })?;
     Ok(())
 }
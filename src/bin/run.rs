@@ -23,6 +23,7 @@ pub struct Options {
     flag_release: bool,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
     arg_args: Vec<String>,
 }
 
@@ -50,6 +51,7 @@ Options:
     --message-format FMT         Error format: human, json [default: human]
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --ignore-rust-version        Ignore `rust-version` specification in packages
 
 If neither `--bin` nor `--example` are given, then if the project only has one
 bin target it will be run. Otherwise `--bin` specifies the bin target to run,
@@ -90,6 +92,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         no_default_features: options.flag_no_default_features,
         spec: spec,
         release: options.flag_release,
+        profile: None,
+        ignore_rust_version: options.flag_ignore_rust_version,
+        build_plan: false,
         mode: ops::CompileMode::Build,
         filter: if examples.is_empty() && bins.is_empty() {
             ops::CompileFilter::Everything { required_features_filterable: false, }
@@ -28,6 +28,7 @@ pub struct Options {
     flag_profile: Option<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -58,6 +59,7 @@ Options:
     --message-format FMT     Error format: human, json [default: human]
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --ignore-rust-version    Ignore `rust-version` specification in packages
 
 The specified target for the current package (or package specified by SPEC if
 provided) will be compiled along with all of its dependencies. The specified
@@ -108,6 +110,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         spec: Packages::Packages(&spec),
         mode: mode,
         release: options.flag_release,
+        profile: None,
+        ignore_rust_version: options.flag_ignore_rust_version,
+        build_plan: false,
         filter: ops::CompileFilter::new(options.flag_lib,
                                         &options.flag_bin,
                                         &options.flag_test,
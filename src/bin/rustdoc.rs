@@ -26,6 +26,7 @@ pub struct Options {
     flag_bench: Vec<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -56,6 +57,7 @@ Options:
     --message-format FMT     Error format: human, json [default: human]
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --ignore-rust-version    Ignore `rust-version` specification in packages
 
 The specified target for the current package (or package specified by SPEC if
 provided) will be documented with the specified <opts>... being passed to the
@@ -93,6 +95,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
             no_default_features: options.flag_no_default_features,
             spec: Packages::Packages(&spec),
             release: options.flag_release,
+            profile: None,
+            ignore_rust_version: options.flag_ignore_rust_version,
+            build_plan: false,
             filter: ops::CompileFilter::new(options.flag_lib,
                                             &options.flag_bin,
                                             &options.flag_test,
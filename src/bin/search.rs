@@ -12,6 +12,7 @@ pub struct Options {
     flag_limit: Option<u32>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_registry: Option<String>,
     arg_query: Vec<String>,
 }
 
@@ -31,6 +32,7 @@ Options:
     --limit LIMIT            Limit the number of results (default: 10, max: 100)
     --frozen                 Require Cargo.lock and cache are up to date
     --locked                 Require Cargo.lock is up to date
+    --registry REGISTRY      Registry to search in
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult {
@@ -43,9 +45,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
         flag_host: host,
         flag_limit: limit,
         arg_query: query,
+        flag_registry: registry,
         ..
     } = options;
 
-    ops::search(&query.join("+"), config, host, cmp::min(100, limit.unwrap_or(10)) as u8)?;
+    ops::search(&query.join("+"), config, host,
+                cmp::min(100, limit.unwrap_or(10)) as u8, registry)?;
     Ok(())
 }
@@ -25,9 +25,11 @@ pub struct Options {
     flag_color: Option<String>,
     flag_message_format: MessageFormat,
     flag_release: bool,
+    flag_profile: Option<String>,
     flag_no_fail_fast: bool,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_ignore_rust_version: bool,
     flag_all: bool,
 }
 
@@ -50,6 +52,7 @@ Options:
     --all                        Test all packages in the workspace
     -j N, --jobs N               Number of parallel jobs, defaults to # of CPUs
     --release                    Build artifacts in release mode, with optimizations
+    --profile NAME               Build artifacts with the specified profile
     --features FEATURES          Space-separated list of features to also build
     --all-features               Build all available features
     --no-default-features        Do not build the `default` feature
@@ -62,6 +65,7 @@ Options:
     --no-fail-fast               Run all tests regardless of failure
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --ignore-rust-version        Ignore `rust-version` specification in packages
 
 All of the trailing arguments are passed to the test binaries generated for
 filtering tests and generally providing options configuring how they run. For
@@ -80,7 +84,8 @@ All packages in the workspace are tested if the `--all` flag is supplied. The
 The --jobs argument affects the building of the test executable but does
 not affect how many jobs are used when running the tests.
 
-Compilation can be configured via the `test` profile in the manifest.
+Compilation can be configured via the `test` profile in the manifest, or a
+custom profile selected with the --profile flag.
 
 By default the rust test harness hides output from test execution to
 keep results readable. Test output can be recovered (e.g. for debugging)
@@ -102,6 +107,11 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
 
     let root = find_root_manifest_for_wd(options.flag_manifest_path, config.cwd())?;
 
+    if options.flag_release && options.flag_profile.is_some() {
+        return Err(human("the `--release` flag cannot be combined with \
+                          `--profile`").into())
+    }
+
     let empty = Vec::new();
     let (mode, filter);
     if options.flag_doc {
@@ -135,6 +145,9 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
             no_default_features: options.flag_no_default_features,
             spec: spec,
             release: options.flag_release,
+            profile: options.flag_profile.as_ref().map(|s| &s[..]),
+            ignore_rust_version: options.flag_ignore_rust_version,
+            build_plan: false,
             mode: mode,
             filter: filter,
             message_format: options.flag_message_format,
@@ -0,0 +1,88 @@
+use cargo::core::Workspace;
+use cargo::ops::{self, TreeOptions, TreeTarget, EdgeKinds, Prefix};
+use cargo::util::{CliResult, Config};
+use cargo::util::important_paths::find_root_manifest_for_wd;
+
+#[derive(RustcDecodable)]
+pub struct Options {
+    flag_package: Vec<String>,
+    flag_features: Vec<String>,
+    flag_all_features: bool,
+    flag_no_default_features: bool,
+    flag_target: Option<String>,
+    flag_invert: Option<String>,
+    flag_duplicates: bool,
+    flag_edges: Option<String>,
+    flag_prefix: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_verbose: u32,
+    flag_quiet: Option<bool>,
+    flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+}
+
+pub const USAGE: &'static str = "
+Display a tree visualization of the resolved dependency graph
+
+Usage:
+    cargo tree [options]
+
+Options:
+    -h, --help                   Print this message
+    -p SPEC, --package SPEC ...  Package to be used as the root of the tree
+    --features FEATURES          Space-separated list of features to activate
+    --all-features               Activate all available features
+    --no-default-features        Do not activate the `default` feature
+    --target TRIPLE              Only show dependencies for the target TRIPLE,
+                                 or for all targets with `all`
+    -i SPEC, --invert SPEC       Show the packages which depend on SPEC
+    -d, --duplicates             Show the packages which are resolved to
+                                 multiple versions, and what depends on them
+    -e KINDS, --edges KINDS      Comma-separated kinds of dependencies to
+                                 show: normal, build, dev, all, and features
+                                 to show the features enabled on each edge
+    --prefix PREFIX              Display each line with an indentation (indent),
+                                 the depth as a number (depth) or nothing (none)
+    --manifest-path PATH         Path to the manifest of the package
+    -v, --verbose ...            Use verbose output (-vv very verbose/build.rs output)
+    -q, --quiet                  No output printed to stdout
+    --color WHEN                 Coloring: auto, always, never
+    --frozen                     Require Cargo.lock and cache are up to date
+    --locked                     Require Cargo.lock is up to date
+
+By default the tree of the current package is shown, or of every member when
+run in a virtual workspace, and only dependencies which are built for the host
+platform are included. Packages which were already shown are marked with `(*)`
+and their dependencies are not repeated.
+
+The `depth` and `none` prefixes print one package per line without any
+decoration, which is suited for diffing the output of two runs.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult {
+    config.configure(options.flag_verbose,
+                     options.flag_quiet,
+                     &options.flag_color,
+                     options.flag_frozen,
+                     options.flag_locked)?;
+    let root = find_root_manifest_for_wd(options.flag_manifest_path, config.cwd())?;
+
+    let edges = options.flag_edges.as_ref().map(|s| &s[..]).unwrap_or("all");
+    let prefix = options.flag_prefix.as_ref().map(|s| &s[..]).unwrap_or("indent");
+    let opts = TreeOptions {
+        features: options.flag_features,
+        all_features: options.flag_all_features,
+        no_default_features: options.flag_no_default_features,
+        packages: options.flag_package,
+        target: TreeTarget::parse(options.flag_target.as_ref().map(|s| &s[..])),
+        invert: options.flag_invert,
+        duplicates: options.flag_duplicates,
+        edges: EdgeKinds::parse(edges)?,
+        prefix: Prefix::parse(prefix)?,
+    };
+
+    let ws = Workspace::new(&root, config)?;
+    ops::tree(&ws, &opts)?;
+    Ok(())
+}
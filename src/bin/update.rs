@@ -1,7 +1,7 @@
 use std::env;
 
 use cargo::core::Workspace;
-use cargo::ops;
+use cargo::ops::{self, MessageFormat};
 use cargo::util::{CliResult, Config};
 use cargo::util::important_paths::find_root_manifest_for_wd;
 
@@ -10,12 +10,16 @@ pub struct Options {
     flag_package: Vec<String>,
     flag_aggressive: bool,
     flag_precise: Option<String>,
+    flag_yanked: bool,
     flag_manifest_path: Option<String>,
     flag_verbose: u32,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_minimal_versions: bool,
+    flag_dry_run: bool,
+    flag_message_format: MessageFormat,
 }
 
 pub const USAGE: &'static str = "
@@ -29,12 +33,16 @@ Options:
     -p SPEC, --package SPEC ...  Package to update
     --aggressive                 Force updating all dependencies of <name> as well
     --precise PRECISE            Update a single dependency to exactly PRECISE
+    --yanked                     Only update dependencies locked to yanked versions
     --manifest-path PATH         Path to the crate's manifest
     -v, --verbose ...            Use verbose output (-vv very verbose/build.rs output)
     -q, --quiet                  No output printed to stdout
     --color WHEN                 Coloring: auto, always, never
     --frozen                     Require Cargo.lock and cache are up to date
     --locked                     Require Cargo.lock is up to date
+    --minimal-versions           Resolve dependencies to their lowest allowed versions
+    --dry-run                    Show what would change without writing the lock file
+    --message-format FMT         Output format: human, json [default: human]
 
 This command requires that a `Cargo.lock` already exists as generated by
 `cargo build` or related commands.
@@ -54,6 +62,14 @@ be updated to.
 If SPEC is not given, then all dependencies will be re-resolved and
 updated.
 
+With --yanked only the packages locked to a version which has since been yanked
+are updated, each to the closest semver-compatible version that isn't yanked,
+preferring newer versions over older ones.
+
+Every package which is added, removed, upgraded or downgraded is listed. With
+`--message-format json` the same list is also printed to stdout as a
+`lockfile-diff` message.
+
 For more information about package id specifications, see `cargo help pkgid`.
 ";
 
@@ -64,13 +80,17 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
                      &options.flag_color,
                      options.flag_frozen,
                      options.flag_locked)?;
+    config.set_minimal_versions(options.flag_minimal_versions);
     let root = find_root_manifest_for_wd(options.flag_manifest_path, config.cwd())?;
 
     let update_opts = ops::UpdateOptions {
         aggressive: options.flag_aggressive,
         precise: options.flag_precise.as_ref().map(|s| &s[..]),
+        yanked: options.flag_yanked,
         to_update: &options.flag_package,
         config: config,
+        dry_run: options.flag_dry_run,
+        message_format: options.flag_message_format,
     };
 
     let ws = Workspace::new(&root, config)?;
@@ -13,6 +13,7 @@ pub struct Options {
     flag_undo: bool,
     flag_frozen: bool,
     flag_locked: bool,
+    flag_registry: Option<String>,
 }
 
 pub static USAGE: &'static str = "
@@ -32,14 +33,16 @@ Options:
     --color WHEN        Coloring: auto, always, never
     --frozen            Require Cargo.lock and cache are up to date
     --locked            Require Cargo.lock is up to date
+    --registry REGISTRY Registry to use
 
 The yank command removes a previously pushed crate's version from the server's
 index. This command does not delete any data, and the crate will still be
 available for download via the registry's download link.
 
 Note that existing crates locked to a yanked version will still be able to
-download the yanked version to use it. Cargo will, however, not allow any new
-crates to be locked to any yanked version.
+download the yanked version to use it, though Cargo warns about it and
+`cargo update --yanked` moves them to a version which isn't yanked. Cargo will,
+however, not allow any new crates to be locked to any yanked version.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult {
@@ -53,7 +56,8 @@ pub fn execute(options: Options, config: &Config) -> CliResult {
               options.flag_vers,
               options.flag_token,
               options.flag_index,
-              options.flag_undo)?;
+              options.flag_undo,
+              options.flag_registry)?;
     Ok(())
 }
 
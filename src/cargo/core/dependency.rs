@@ -20,6 +20,9 @@ pub struct Dependency {
 #[derive(PartialEq, Clone, Debug)]
 pub struct DependencyInner {
     name: String,
+    // The name this dependency was given in the manifest, if it differs from
+    // the name of the package it refers to (`foo = { package = "bar" }`).
+    rename: Option<String>,
     source_id: SourceId,
     req: VersionReq,
     specified_req: bool,
@@ -27,12 +30,30 @@ pub struct DependencyInner {
     only_match_name: bool,
 
     optional: bool,
+    // Whether the dependency is part of the public API of the package
+    // depending on it, so that its dependents see it as well.
+    public: bool,
     default_features: bool,
     features: Vec<String>,
 
     // This dependency should be used only for this platform.
     // `None` means *all platforms*.
     platform: Option<Platform>,
+
+    // If set, an artifact of the dependency is built instead of linking its
+    // library, optionally for a different target than the dependent.
+    artifact: Option<ArtifactKind>,
+    artifact_target: Option<String>,
+}
+
+/// The kind of artifact requested by an artifact dependency
+/// (`artifact = "bin"`).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ArtifactKind {
+    /// All binary targets of the package
+    Bin,
+    /// The library of the package, built as a `cdylib`
+    Cdylib,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -44,11 +65,14 @@ pub enum Platform {
 #[derive(Serialize)]
 struct SerializedDependency<'a> {
     name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rename: Option<&'a str>,
     source: &'a SourceId,
     req: String,
     kind: Kind,
 
     optional: bool,
+    public: bool,
     uses_default_features: bool,
     features: &'a [String],
     target: Option<&'a Platform>,
@@ -60,10 +84,12 @@ impl ser::Serialize for Dependency {
     {
         SerializedDependency {
             name: self.name(),
+            rename: self.rename(),
             source: &self.source_id(),
             req: self.version_req().to_string(),
             kind: self.kind(),
             optional: self.is_optional(),
+            public: self.is_public(),
             uses_default_features: self.uses_default_features(),
             features: self.features(),
             target: self.platform(),
@@ -155,20 +181,33 @@ this warning.
     pub fn new_override(name: &str, source_id: &SourceId) -> DependencyInner {
         DependencyInner {
             name: name.to_string(),
+            rename: None,
             source_id: source_id.clone(),
             req: VersionReq::any(),
             kind: Kind::Normal,
             only_match_name: true,
             optional: false,
+            public: false,
             features: Vec::new(),
             default_features: true,
             specified_req: false,
             platform: None,
+            artifact: None,
+            artifact_target: None,
         }
     }
 
     pub fn version_req(&self) -> &VersionReq { &self.req }
     pub fn name(&self) -> &str { &self.name }
+    pub fn rename(&self) -> Option<&str> {
+        self.rename.as_ref().map(|s| &s[..])
+    }
+
+    /// The name this dependency is known by in the manifest of the package
+    /// depending on it, which is also the name of the crate it's linked as.
+    pub fn name_in_toml(&self) -> &str {
+        self.rename().unwrap_or(&self.name)
+    }
     pub fn source_id(&self) -> &SourceId { &self.source_id }
     pub fn kind(&self) -> Kind { self.kind }
     pub fn specified_req(&self) -> bool { self.specified_req }
@@ -202,6 +241,19 @@ this warning.
         self
     }
 
+    /// Sets whether the dependency is part of the package's public API.
+    pub fn set_public(mut self, public: bool) -> DependencyInner {
+        self.public = public;
+        self
+    }
+
+    /// Sets the name this dependency is known by in the manifest, if it's
+    /// different from the name of the package.
+    pub fn set_rename(mut self, rename: Option<String>) -> DependencyInner {
+        self.rename = rename;
+        self
+    }
+
     /// Set the source id for this dependency
     pub fn set_source_id(mut self, id: SourceId) -> DependencyInner {
         self.source_id = id;
@@ -220,6 +272,16 @@ this warning.
         self
     }
 
+    /// Sets the artifact to build for this dependency, and the target triple
+    /// to build it for if it differs from the dependent's.
+    pub fn set_artifact(mut self,
+                        artifact: Option<ArtifactKind>,
+                        target: Option<String>) -> DependencyInner {
+        self.artifact = artifact;
+        self.artifact_target = target;
+        self
+    }
+
     /// Lock this dependency to depending on the specified package id
     pub fn lock_to(self, id: &PackageId) -> DependencyInner {
         assert_eq!(self.source_id, *id.source_id());
@@ -239,6 +301,11 @@ this warning.
         match self.kind { Kind::Build => true, _ => false }
     }
     pub fn is_optional(&self) -> bool { self.optional }
+    pub fn is_public(&self) -> bool { self.public }
+    pub fn artifact(&self) -> Option<ArtifactKind> { self.artifact }
+    pub fn artifact_target(&self) -> Option<&str> {
+        self.artifact_target.as_ref().map(|s| &s[..])
+    }
     /// Returns true if the default features of the dependency are requested.
     pub fn uses_default_features(&self) -> bool { self.default_features }
     /// Returns the list of features that are requested by the dependency.
@@ -256,6 +323,19 @@ this warning.
                                       &self.source_id == id.source_id()))
     }
 
+    /// Returns true if the package (`sum`) can fulfill this dependency request
+    /// when the source it comes from is not taken into account.
+    pub fn matches_ignoring_source(&self, sum: &Summary) -> bool {
+        self.name == sum.package_id().name() &&
+            self.req.matches(sum.package_id().version())
+    }
+
+    /// Returns true if this dependency is locked to a precise version.
+    pub fn is_locked(&self) -> bool {
+        // Kind of a hack to figure this out, but it works!
+        self.req.to_string().starts_with("=")
+    }
+
     pub fn into_dependency(self) -> Dependency {
         Dependency {inner: Rc::new(self)}
     }
@@ -291,10 +371,15 @@ impl Dependency {
 
     pub fn version_req(&self) -> &VersionReq { self.inner.version_req() }
     pub fn name(&self) -> &str { self.inner.name() }
+    pub fn rename(&self) -> Option<&str> { self.inner.rename() }
     pub fn source_id(&self) -> &SourceId { self.inner.source_id() }
     pub fn kind(&self) -> Kind { self.inner.kind() }
     pub fn specified_req(&self) -> bool { self.inner.specified_req() }
 
+    /// The name this dependency is known by in the manifest of the package
+    /// depending on it, which is also the name of the crate it's linked as.
+    pub fn name_in_toml(&self) -> &str { self.inner.name_in_toml() }
+
     /// If none, this dependencies must be built for all platforms.
     /// If some, it must only be built for the specified platform.
     pub fn platform(&self) -> Option<&Platform> {
@@ -310,6 +395,18 @@ impl Dependency {
     pub fn is_transitive(&self) -> bool { self.inner.is_transitive() }
     pub fn is_build(&self) -> bool { self.inner.is_build() }
     pub fn is_optional(&self) -> bool { self.inner.is_optional() }
+    /// Returns true if the dependency is re-exported as part of the public
+    /// API of the package depending on it.
+    pub fn is_public(&self) -> bool { self.inner.is_public() }
+
+    /// Returns the artifact to build if this is an artifact dependency, in
+    /// which case the library of the dependency is not linked.
+    pub fn artifact(&self) -> Option<ArtifactKind> { self.inner.artifact() }
+    /// The target triple to build the artifact for, or `"target"` for the
+    /// `--target` triple; `None` builds it like the dependent.
+    pub fn artifact_target(&self) -> Option<&str> {
+        self.inner.artifact_target()
+    }
 
     /// Returns true if the default features of the dependency are requested.
     pub fn uses_default_features(&self) -> bool {
@@ -326,6 +423,15 @@ impl Dependency {
         self.inner.matches_id(id)
     }
 
+    /// Returns true if the package (`sum`) can fulfill this dependency request
+    /// when the source it comes from is not taken into account.
+    pub fn matches_ignoring_source(&self, sum: &Summary) -> bool {
+        self.inner.matches_ignoring_source(sum)
+    }
+
+    /// Returns true if this dependency is locked to a precise version.
+    pub fn is_locked(&self) -> bool { self.inner.is_locked() }
+
     pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
                       -> Dependency {
         if self.source_id() != to_replace {
@@ -4,9 +4,12 @@ use std::path::{PathBuf, Path};
 
 use semver::Version;
 use serde::ser;
+use url::Url;
 
 use core::{Dependency, PackageId, Summary, SourceId, PackageIdSpec};
 use core::WorkspaceConfig;
+use core::resolver::ResolveBehavior;
+use util::CargoResult;
 
 pub enum EitherManifest {
     Real(Manifest),
@@ -25,15 +28,22 @@ pub struct Manifest {
     metadata: ManifestMetadata,
     profiles: Profiles,
     publish: bool,
+    rust_version: Option<String>,
+    default_run: Option<String>,
+    lints: Vec<String>,
     replace: Vec<(PackageIdSpec, Dependency)>,
+    patch: HashMap<Url, Vec<Dependency>>,
     workspace: WorkspaceConfig,
+    resolve_behavior: Option<ResolveBehavior>,
 }
 
 #[derive(Clone, Debug)]
 pub struct VirtualManifest {
     replace: Vec<(PackageIdSpec, Dependency)>,
+    patch: HashMap<Url, Vec<Dependency>>,
     workspace: WorkspaceConfig,
     profiles: Profiles,
+    resolve_behavior: Option<ResolveBehavior>,
 }
 
 /// General metadata about a package which is just blindly uploaded to the
@@ -171,6 +181,15 @@ pub struct Profiles {
     pub custom_build: Profile,
     pub check: Profile,
     pub doctest: Profile,
+
+    /// Profiles used for packages matching a `[profile.*.package.<spec>]`
+    /// section, in the order they were found.
+    pub package_overrides: Vec<(PackageIdSpec, Profiles)>,
+    /// Profiles used for build scripts, procedural macros, plugins and their
+    /// dependencies, as configured by `[profile.*.build-override]`.
+    pub build_override: Option<Box<Profiles>>,
+    /// Custom profiles defined with `inherits`, selected with `--profile`.
+    pub named: HashMap<String, Profiles>,
 }
 
 /// Information about a binary, a library, an example, etc. that is part of the
@@ -221,8 +240,13 @@ impl Manifest {
                metadata: ManifestMetadata,
                profiles: Profiles,
                publish: bool,
+               rust_version: Option<String>,
+               default_run: Option<String>,
+               lints: Vec<String>,
                replace: Vec<(PackageIdSpec, Dependency)>,
-               workspace: WorkspaceConfig) -> Manifest {
+               patch: HashMap<Url, Vec<Dependency>>,
+               workspace: WorkspaceConfig,
+               resolve_behavior: Option<ResolveBehavior>) -> Manifest {
         Manifest {
             summary: summary,
             targets: targets,
@@ -233,8 +257,13 @@ impl Manifest {
             metadata: metadata,
             profiles: profiles,
             publish: publish,
+            rust_version: rust_version,
+            default_run: default_run,
+            lints: lints,
             replace: replace,
+            patch: patch,
             workspace: workspace,
+            resolve_behavior: resolve_behavior,
         }
     }
 
@@ -250,7 +279,16 @@ impl Manifest {
     pub fn warnings(&self) -> &[String] { &self.warnings }
     pub fn profiles(&self) -> &Profiles { &self.profiles }
     pub fn publish(&self) -> bool { self.publish }
+    pub fn rust_version(&self) -> Option<&str> {
+        self.rust_version.as_ref().map(|s| &s[..])
+    }
+    pub fn default_run(&self) -> Option<&str> {
+        self.default_run.as_ref().map(|s| &s[..])
+    }
+    /// Flags passed to rustc for the lint levels of the `[lints]` table
+    pub fn lints(&self) -> &[String] { &self.lints }
     pub fn replace(&self) -> &[(PackageIdSpec, Dependency)] { &self.replace }
+    pub fn patch(&self) -> &HashMap<Url, Vec<Dependency>> { &self.patch }
     pub fn links(&self) -> Option<&str> {
         self.links.as_ref().map(|s| &s[..])
     }
@@ -259,6 +297,12 @@ impl Manifest {
         &self.workspace
     }
 
+    /// The `resolver` setting of this package or of its `[workspace]`
+    /// section, if any.
+    pub fn resolve_behavior(&self) -> Option<ResolveBehavior> {
+        self.resolve_behavior
+    }
+
     pub fn add_warning(&mut self, s: String) {
         self.warnings.push(s)
     }
@@ -278,12 +322,16 @@ impl Manifest {
 
 impl VirtualManifest {
     pub fn new(replace: Vec<(PackageIdSpec, Dependency)>,
+               patch: HashMap<Url, Vec<Dependency>>,
                workspace: WorkspaceConfig,
-               profiles: Profiles) -> VirtualManifest {
+               profiles: Profiles,
+               resolve_behavior: Option<ResolveBehavior>) -> VirtualManifest {
         VirtualManifest {
             replace: replace,
+            patch: patch,
             workspace: workspace,
             profiles: profiles,
+            resolve_behavior: resolve_behavior,
         }
     }
 
@@ -291,6 +339,10 @@ impl VirtualManifest {
         &self.replace
     }
 
+    pub fn patch(&self) -> &HashMap<Url, Vec<Dependency>> {
+        &self.patch
+    }
+
     pub fn workspace_config(&self) -> &WorkspaceConfig {
         &self.workspace
     }
@@ -298,6 +350,10 @@ impl VirtualManifest {
     pub fn profiles(&self) -> &Profiles {
         &self.profiles
     }
+
+    pub fn resolve_behavior(&self) -> Option<ResolveBehavior> {
+        self.resolve_behavior
+    }
 }
 
 impl Target {
@@ -435,6 +491,13 @@ impl Target {
         }
     }
 
+    pub fn is_cdylib(&self) -> bool {
+        match self.kind {
+            TargetKind::Lib(ref libs) => libs.iter().any(|l| l.crate_type() == "cdylib"),
+            _ => false
+        }
+    }
+
     pub fn linkable(&self) -> bool {
         match self.kind {
             TargetKind::Lib(ref kinds) => {
@@ -586,6 +649,33 @@ impl Profile {
     }
 }
 
+impl Profiles {
+    /// Returns the set of profiles that `pkg` should be built with, taking
+    /// per-package overrides into account. Overrides for a specific package
+    /// take precedence over `build-override`, which applies when `for_host`
+    /// is set.
+    pub fn for_package(&self, pkg: &PackageId, for_host: bool) -> &Profiles {
+        let overridden = self.package_overrides.iter().find(|&&(ref spec, _)| {
+            spec.matches(pkg)
+        });
+        if let Some(&(_, ref profiles)) = overridden {
+            return profiles
+        }
+        match self.build_override {
+            Some(ref profiles) if for_host => profiles,
+            _ => self,
+        }
+    }
+
+    /// Returns the set of profiles for the custom profile `name`.
+    pub fn named(&self, name: &str) -> CargoResult<&Profiles> {
+        match self.named.get(name) {
+            Some(profiles) => Ok(profiles),
+            None => bail!("profile `{}` is not defined", name),
+        }
+    }
+}
+
 impl Default for Profile {
     fn default() -> Profile {
         Profile {
@@ -7,7 +7,7 @@ pub use self::package_id_spec::PackageIdSpec;
 pub use self::registry::Registry;
 pub use self::resolver::Resolve;
 pub use self::shell::{Shell, MultiShell, ShellConfig, Verbosity, ColorConfig};
-pub use self::source::{Source, SourceId, SourceMap, GitReference};
+pub use self::source::{Source, SourceId, SourceMap, GitReference, Yanked};
 pub use self::summary::Summary;
 pub use self::workspace::{Workspace, WorkspaceConfig};
 
@@ -37,6 +37,10 @@ struct SerializedPackage<'a> {
     targets: &'a [Target],
     features: &'a HashMap<String, Vec<String>>,
     manifest_path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rust_version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_run: Option<&'a str>,
 }
 
 impl ser::Serialize for Package {
@@ -62,6 +66,8 @@ impl ser::Serialize for Package {
             targets: &self.manifest.targets(),
             features: summary.features(),
             manifest_path: &self.manifest_path.display().to_string(),
+            rust_version: self.manifest.rust_version(),
+            default_run: self.manifest.default_run(),
         }.serialize(s)
     }
 }
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
+use semver::VersionReq;
+use url::Url;
+
 use core::{Source, SourceId, SourceMap, Summary, Dependency, PackageId, Package};
-use core::PackageSet;
+use core::{PackageSet, Yanked};
 use util::{CargoResult, ChainError, Config, human, profile};
 use sources::config::SourceConfigMap;
 
@@ -80,6 +83,11 @@ pub struct PackageRegistry<'cfg> {
 
     locked: HashMap<SourceId, HashMap<String, Vec<(PackageId, Vec<PackageId>)>>>,
     source_config: SourceConfigMap<'cfg>,
+
+    // Extra summaries registered through `[patch]`, keyed by the url of the
+    // source that they're patching. Summaries listed here take precedence over
+    // the versions of the same name found in the original source.
+    patches: HashMap<Url, Vec<Summary>>,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -98,6 +106,7 @@ impl<'cfg> PackageRegistry<'cfg> {
             overrides: Vec::new(),
             source_config: source_config,
             locked: HashMap::new(),
+            patches: HashMap::new(),
         })
     }
 
@@ -176,6 +185,52 @@ impl<'cfg> PackageRegistry<'cfg> {
         sub_vec.push((id, deps));
     }
 
+    /// Inserts a list of dependencies that are used to `[patch]` a source.
+    ///
+    /// Each dependency is resolved to exactly one summary here, and that
+    /// summary is then returned alongside the summaries of `url` whenever a
+    /// dependency on `url` is queried for.
+    pub fn patch(&mut self, url: &Url, deps: &[Dependency]) -> CargoResult<()> {
+        let deps = deps.iter().map(|dep| {
+            let mut summaries = self.query(dep)?.into_iter();
+            let summary = match summaries.next() {
+                Some(summary) => summary,
+                None => {
+                    bail!("patch for `{}` in `{}` did not resolve to any crates",
+                          dep.name(), url)
+                }
+            };
+            if summaries.next().is_some() {
+                bail!("patch for `{}` in `{}` resolved to more than one candidate",
+                      dep.name(), url)
+            }
+            if summary.package_id().source_id().url() == url {
+                bail!("patch for `{}` in `{}` points to the same source, but \
+                       patches must point to different sources",
+                      dep.name(), url);
+            }
+            Ok(summary)
+        }).collect::<CargoResult<Vec<_>>>().chain_error(|| {
+            human(format!("failed to resolve patches for `{}`", url))
+        })?;
+
+        self.patches.insert(url.clone(), deps);
+        Ok(())
+    }
+
+    pub fn patches(&self) -> &HashMap<Url, Vec<Summary>> {
+        &self.patches
+    }
+
+    /// Returns whether `id` has been yanked from its source, which needs to
+    /// have been loaded already.
+    pub fn yanked(&mut self, id: &PackageId) -> CargoResult<Option<Yanked>> {
+        match self.sources.get_mut(id.source_id()) {
+            Some(source) => source.yanked(id),
+            None => Ok(None),
+        }
+    }
+
     fn load(&mut self, source_id: &SourceId, kind: Kind) -> CargoResult<()> {
         (|| {
             let source = self.source_config.load(source_id)?;
@@ -275,16 +330,34 @@ impl<'cfg> PackageRegistry<'cfg> {
             }).and_then(|vec| {
                 vec.iter().find(|&&(ref id, _)| dep.matches_id(id))
             });
-            match v {
-                Some(&(ref id, _)) => {
-                    trace!("\tsecond hit on {}", id);
-                    dep.lock_to(id)
-                }
-                None => {
-                    trace!("\tremaining unlocked");
-                    dep
+            if let Some(&(ref id, _)) = v {
+                trace!("\tsecond hit on {}", id);
+                return dep.lock_to(id)
+            }
+
+            // Finally we check to see if any registered patches correspond to
+            // this dependency. If a patch was previously locked then we lock
+            // the dependency to the patch's version, leaving the source alone
+            // as the patch will be picked up when the source is queried.
+            let patch = self.patches.get(dep.source_id().url()).and_then(|list| {
+                list.iter().find(|s| dep.matches_ignoring_source(s))
+            });
+            if let Some(patch) = patch {
+                let patch_locked = self.locked.get(patch.source_id()).and_then(|map| {
+                    map.get(patch.name())
+                }).map(|vec| {
+                    vec.iter().any(|&(ref id, _)| id == patch.package_id())
+                }).unwrap_or(false);
+
+                if patch_locked {
+                    trace!("\tthird hit on {}", patch.package_id());
+                    let req = VersionReq::exact(patch.version());
+                    return dep.clone_inner().set_version_req(req).into_dependency()
                 }
             }
+
+            trace!("\tremaining unlocked");
+            dep
         })
     }
 
@@ -335,6 +408,21 @@ http://doc.crates.io/specifying-dependencies.html#overriding-dependencies
 
 impl<'cfg> Registry for PackageRegistry<'cfg> {
     fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        // First up we need to check if any summary in the patch tables
+        // matches what we're looking for.
+        let patches = self.patches.get(dep.source_id().url()).map(|list| {
+            list.iter().filter(|s| dep.matches_ignoring_source(s))
+                .cloned()
+                .collect::<Vec<_>>()
+        }).unwrap_or(Vec::new());
+
+        // A locked dependency has a requirement of `=a.b.c`, and as patches
+        // take priority over the original source there's no need to query the
+        // source at all if a patch already satisfies it.
+        if patches.len() == 1 && dep.is_locked() {
+            return Ok(patches.into_iter().map(|s| self.lock(s)).collect())
+        }
+
         // Ensure the requested source_id is loaded
         self.ensure_loaded(dep.source_id(), Kind::Normal).chain_error(|| {
             human(format!("failed to load source for a dependency \
@@ -356,8 +444,18 @@ impl<'cfg> Registry for PackageRegistry<'cfg> {
                 vec![candidate]
             }
             (Some(_), None) => bail!("override found but no real ones"),
-            (None, Some(summaries)) => summaries,
-            (None, None) => Vec::new(),
+
+            // Sources never return two summaries with the same version, but a
+            // `[patch]` may be providing a version that's also in the original
+            // source. In that case the patch wins and the original is skipped.
+            (None, Some(summaries)) => {
+                let mut ret = summaries.into_iter().filter(|s| {
+                    !patches.iter().any(|p| p.version() == s.version())
+                }).collect::<Vec<_>>();
+                ret.extend(patches);
+                ret
+            }
+            (None, None) => patches,
         };
 
         // post-process all returned summaries to ensure that we lock all
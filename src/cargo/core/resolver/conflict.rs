@@ -0,0 +1,157 @@
+//! Reporting of resolution failures.
+//!
+//! When no candidate of a dependency can be activated the resolver records,
+//! for every rejected candidate, which previously activated package it
+//! conflicted with and why. That information is collected into a
+//! `ConflictTree` which renders both sides of the conflict as chains of
+//! dependents leading back to the workspace roots.
+
+use std::error::Error;
+use std::fmt;
+
+use core::PackageId;
+use util::CargoError;
+
+/// Why a candidate version of a dependency could not be activated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConflictReason {
+    /// A different, semver-compatible version of the same package has already
+    /// been activated.
+    Semver,
+    /// The candidate links to a native library which an activated package
+    /// already links to.
+    Links(String),
+    /// The given package would see both the candidate and a different version
+    /// of it through public dependencies.
+    Public(PackageId),
+}
+
+/// One step from a package back towards a workspace root, meaning that the
+/// previous package in the path satisfies `requirement` of `package_id`.
+#[derive(Debug, Serialize)]
+pub struct PathStep {
+    pub package_id: PackageId,
+    pub requirement: String,
+}
+
+/// A previously activated package along with the candidates it ruled out.
+#[derive(Debug, Serialize)]
+pub struct Conflict {
+    pub package_id: PackageId,
+    pub reason: &'static str,
+    pub links: Option<String>,
+    pub visible_to: Option<PackageId>,
+    pub rejected: Vec<String>,
+    pub path: Vec<PathStep>,
+}
+
+/// Everything known about why a dependency could not be resolved.
+#[derive(Debug, Serialize)]
+pub struct ConflictTree {
+    pub dependency: String,
+    pub requirement: String,
+    pub required_by: PackageId,
+    pub required_by_path: Vec<PathStep>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Conflict {
+    pub fn new(package_id: PackageId,
+               reason: &ConflictReason,
+               path: Vec<PathStep>) -> Conflict {
+        let (reason, links, visible_to) = match *reason {
+            ConflictReason::Semver => ("semver", None, None),
+            ConflictReason::Links(ref links) => {
+                ("links", Some(links.clone()), None)
+            }
+            ConflictReason::Public(ref pkg) => ("public", None, Some(pkg.clone())),
+        };
+        Conflict {
+            package_id: package_id,
+            reason: reason,
+            links: links,
+            visible_to: visible_to,
+            rejected: Vec::new(),
+            path: path,
+        }
+    }
+}
+
+fn write_path(f: &mut fmt::Formatter, path: &[PathStep]) -> fmt::Result {
+    for step in path {
+        write!(f, "\n    ... which satisfies dependency `{}` of package `{}`",
+               step.requirement, step.package_id)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ConflictTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to select a version for `{}` (required by `{}`):\n\
+                   all possible versions conflict with previously selected \
+                   packages\n\n  \
+                   required by package `{}`",
+               self.dependency, self.required_by.name(), self.required_by)?;
+        write_path(f, &self.required_by_path)?;
+
+        for conflict in self.conflicts.iter() {
+            write!(f, "\n\n  previously selected package `{}`",
+                   conflict.package_id)?;
+            write_path(f, &conflict.path)?;
+            match (&conflict.links, &conflict.visible_to) {
+                (&Some(ref links), _) => {
+                    write!(f, "\n  which links to the native library `{}`, \
+                               as do rejected versions of `{}`: {}",
+                           links, self.dependency,
+                           conflict.rejected.join(", "))?;
+                }
+                (_, &Some(ref pkg)) => {
+                    write!(f, "\n  which package `{}` sees through public \
+                               dependencies, as it would rejected versions \
+                               of `{}`: {}",
+                           pkg, self.dependency,
+                           conflict.rejected.join(", "))?;
+                }
+                _ => {
+                    write!(f, "\n  which is semver-compatible with rejected \
+                               versions of `{}`: {}",
+                           self.dependency, conflict.rejected.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when the resolver runs out of candidates, which keeps
+/// the full `ConflictTree` around for machine-readable output.
+#[derive(Debug)]
+pub struct ResolveError {
+    tree: ConflictTree,
+    desc: String,
+}
+
+impl ResolveError {
+    pub fn new(tree: ConflictTree) -> ResolveError {
+        ResolveError { desc: tree.to_string(), tree: tree }
+    }
+
+    pub fn tree(&self) -> &ConflictTree {
+        &self.tree
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.desc)
+    }
+}
+
+impl Error for ResolveError {
+    fn description(&self) -> &str { &self.desc }
+}
+
+impl CargoError for ResolveError {
+    fn is_human(&self) -> bool { true }
+    fn conflict_tree(&self) -> Option<&ConflictTree> { Some(&self.tree) }
+}
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet, BTreeMap};
 use std::fmt;
 use std::str::FromStr;
 
+use semver::Version;
 use serde::ser;
 use serde::de;
 
@@ -12,16 +13,60 @@ use super::Resolve;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EncodableResolve {
+    /// Absent in lock files written in the original format.
+    version: Option<u32>,
     package: Option<Vec<EncodableDependency>>,
     /// `root` is optional to allow forward compatibility.
     root: Option<EncodableDependency>,
     metadata: Option<Metadata>,
+    #[serde(default, skip_serializing_if = "Patch::is_empty")]
+    patch: Patch,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Patch {
+    unused: Vec<EncodableDependency>,
+}
+
+impl Patch {
+    fn is_empty(&self) -> bool {
+        self.unused.is_empty()
+    }
 }
 
 pub type Metadata = BTreeMap<String, String>;
 
+/// The format a lock file is written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResolveVersion {
+    /// Checksums are listed in the `[metadata]` table, and dependencies
+    /// always by their full package id.
+    V1,
+    /// Marked with `version = 2`. Each package lists its own checksum, and
+    /// dependencies only list as much of the package id as is needed to tell
+    /// them apart from other packages in the lock file.
+    V2,
+}
+
+impl Default for ResolveVersion {
+    /// The format used for new lock files.
+    fn default() -> ResolveVersion {
+        ResolveVersion::V2
+    }
+}
+
 impl EncodableResolve {
     pub fn into_resolve(self, ws: &Workspace) -> CargoResult<Resolve> {
+        let version = match self.version {
+            None => ResolveVersion::V1,
+            Some(2) => ResolveVersion::V2,
+            Some(n) => {
+                bail!("lock file version `{}` was found, but this version of \
+                       Cargo does not understand it, perhaps Cargo needs to \
+                       be updated?", n)
+            }
+        };
+
         let path_deps = build_path_deps(ws);
 
         let packages = {
@@ -40,7 +85,7 @@ impl EncodableResolve {
             for pkg in packages.iter() {
                 let enc_id = EncodablePackageId {
                     name: pkg.name.clone(),
-                    version: pkg.version.clone(),
+                    version: Some(pkg.version.clone()),
                     source: pkg.source.clone(),
                 };
 
@@ -60,20 +105,37 @@ impl EncodableResolve {
             (live_pkgs, all_pkgs)
         };
 
-        let lookup_id = |enc_id: &EncodablePackageId| -> CargoResult<Option<PackageId>> {
-            match live_pkgs.get(enc_id) {
-                Some(&(ref id, _)) => Ok(Some(id.clone())),
-                None => if all_pkgs.contains(enc_id) {
-                    // Package is found in the lockfile, but it is
-                    // no longer a member of the workspace.
-                    Ok(None)
-                } else {
+        // Dependencies may leave out the version and source of a package if
+        // it's still unambiguous without them, so find the package they mean.
+        let full_id = |enc_id: &EncodablePackageId| -> CargoResult<EncodablePackageId> {
+            if all_pkgs.contains(enc_id) {
+                return Ok(enc_id.clone())
+            }
+            let mut matches = all_pkgs.iter().filter(|id| {
+                id.name == enc_id.name &&
+                    (enc_id.version.is_none() || id.version == enc_id.version) &&
+                    (enc_id.source.is_none() || id.source == enc_id.source)
+            });
+            match (matches.next(), matches.next()) {
+                (Some(id), None) => Ok(id.clone()),
+                (Some(_), Some(_)) => {
+                    Err(internal(format!("package `{}` is specified as a dependency, \
+                                          but matches several packages in the \
+                                          package list", enc_id)))
+                }
+                (None, _) => {
                     Err(internal(format!("package `{}` is specified as a dependency, \
                                           but is missing from the package list", enc_id)))
                 }
             }
         };
 
+        let lookup_id = |enc_id: &EncodablePackageId| -> CargoResult<Option<PackageId>> {
+            // If the package is found in the lockfile, but isn't live, it's no
+            // longer a member of the workspace.
+            Ok(live_pkgs.get(&full_id(enc_id)?).map(|&(ref id, _)| id.clone()))
+        };
+
         let g = {
             let mut g = Graph::new();
 
@@ -127,6 +189,11 @@ impl EncodableResolve {
         // really worry about it. We just try to slurp up as many checksums as
         // possible.
         let mut checksums = HashMap::new();
+        for &(ref id, ref pkg) in live_pkgs.values() {
+            if let Some(ref cksum) = pkg.checksum {
+                checksums.insert(id.clone(), Some(cksum.to_string()));
+            }
+        }
         let prefix = "checksum ";
         let mut to_remove = Vec::new();
         for (k, v) in metadata.iter().filter(|p| p.0.starts_with(prefix)) {
@@ -152,13 +219,28 @@ impl EncodableResolve {
             metadata.remove(&k);
         }
 
+        let mut unused_patches = Vec::new();
+        for pkg in self.patch.unused {
+            let id = match pkg.source.as_ref().or(path_deps.get(&pkg.name)) {
+                Some(src) => PackageId::new(&pkg.name, &pkg.version, src)?,
+                None => continue,
+            };
+            unused_patches.push(id);
+        }
+
         Ok(Resolve {
             graph: g,
             empty_features: HashSet::new(),
             features: HashMap::new(),
+            optional_deps: HashMap::new(),
+            resolved_features: None,
             replacements: replacements,
             checksums: checksums,
             metadata: metadata,
+            unused_patches: unused_patches,
+            patched: HashMap::new(),
+            held_back: HashMap::new(),
+            version: version,
         })
     }
 }
@@ -190,9 +272,11 @@ fn build_path_deps(ws: &Workspace) -> HashMap<String, SourceId> {
              ret: &mut HashMap<String, SourceId>,
              visited: &mut HashSet<SourceId>) {
         let replace = pkg.manifest().replace();
+        let patch = pkg.manifest().patch();
         let deps = pkg.dependencies()
                       .iter()
                       .chain(replace.iter().map(|p| &p.1))
+                      .chain(patch.values().flat_map(|v| v))
                       .map(|d| d.source_id())
                       .filter(|id| !visited.contains(id) && id.is_path())
                       .filter_map(|id| id.url().to_file_path().ok())
@@ -213,6 +297,7 @@ pub struct EncodableDependency {
     name: String,
     version: String,
     source: Option<SourceId>,
+    checksum: Option<String>,
     dependencies: Option<Vec<EncodablePackageId>>,
     replace: Option<EncodablePackageId>,
 }
@@ -220,13 +305,16 @@ pub struct EncodableDependency {
 #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Clone)]
 pub struct EncodablePackageId {
     name: String,
-    version: String,
+    version: Option<String>,
     source: Option<SourceId>
 }
 
 impl fmt::Display for EncodablePackageId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {}", self.name, self.version)?;
+        write!(f, "{}", self.name)?;
+        if let Some(ref s) = self.version {
+            write!(f, " {}", s)?;
+        }
         if let Some(ref s) = self.source {
             write!(f, " ({})", s.to_url())?;
         }
@@ -240,9 +328,7 @@ impl FromStr for EncodablePackageId {
     fn from_str(s: &str) -> CargoResult<EncodablePackageId> {
         let mut s = s.splitn(3, ' ');
         let name = s.next().unwrap();
-        let version = s.next().chain_error(|| {
-            internal("invalid serialized PackageId")
-        })?;
+        let version = s.next();
         let source_id = match s.next() {
             Some(s) => {
                 if s.starts_with("(") && s.ends_with(")") {
@@ -256,7 +342,7 @@ impl FromStr for EncodablePackageId {
 
         Ok(EncodablePackageId {
             name: name.to_string(),
-            version: version.to_string(),
+            version: version.map(|v| v.to_string()),
             source: source_id
         })
     }
@@ -298,41 +384,89 @@ impl<'a, 'cfg> ser::Serialize for WorkspaceResolve<'a, 'cfg> {
             member.name()
         }).map(Package::package_id);
 
+        let state = EncodeState::new(self.resolve);
+
         let encodable = ids.iter().filter_map(|&id| {
             if self.use_root_key && root.unwrap() == id {
                 return None
             }
 
-            Some(encodable_resolve_node(id, self.resolve))
+            Some(encodable_resolve_node(id, self.resolve, &state))
         }).collect::<Vec<_>>();
 
         let mut metadata = self.resolve.metadata.clone();
 
-        for id in ids.iter().filter(|id| !id.source_id().is_path()) {
-            let checksum = match self.resolve.checksums[*id] {
-                Some(ref s) => &s[..],
-                None => "<none>",
-            };
-            let id = encodable_package_id(id);
-            metadata.insert(format!("checksum {}", id.to_string()),
-                            checksum.to_string());
+        if self.resolve.version() == ResolveVersion::V1 {
+            for id in ids.iter().filter(|id| !id.source_id().is_path()) {
+                let checksum = match self.resolve.checksums[*id] {
+                    Some(ref s) => &s[..],
+                    None => "<none>",
+                };
+                let id = encodable_package_id(id);
+                metadata.insert(format!("checksum {}", id.to_string()),
+                                checksum.to_string());
+            }
         }
 
         let metadata = if metadata.len() == 0 {None} else {Some(metadata)};
 
         let root = match root {
-            Some(root) if self.use_root_key => Some(encodable_resolve_node(&root, self.resolve)),
+            Some(root) if self.use_root_key => {
+                Some(encodable_resolve_node(&root, self.resolve, &state))
+            }
             _ => None,
         };
+
+        let patch = Patch {
+            unused: self.resolve.unused_patches().iter().map(|id| {
+                EncodableDependency {
+                    name: id.name().to_string(),
+                    version: id.version().to_string(),
+                    source: encode_source(id.source_id()),
+                    checksum: None,
+                    dependencies: None,
+                    replace: None,
+                }
+            }).collect(),
+        };
+        let version = match self.resolve.version() {
+            ResolveVersion::V1 => None,
+            ResolveVersion::V2 => Some(2),
+        };
         EncodableResolve {
+            version: version,
             package: Some(encodable),
             root: root,
             metadata: metadata,
+            patch: patch,
         }.serialize(s)
     }
 }
 
-fn encodable_resolve_node(id: &PackageId, resolve: &Resolve)
+// For lock files in the current format, how many packages of each name and
+// version are listed, which decides how much of a package id is needed to
+// refer to it.
+struct EncodeState<'a> {
+    counts: Option<HashMap<&'a str, HashMap<&'a Version, usize>>>,
+}
+
+impl<'a> EncodeState<'a> {
+    fn new(resolve: &'a Resolve) -> EncodeState<'a> {
+        let counts = if resolve.version() >= ResolveVersion::V2 {
+            let mut counts = HashMap::new();
+            for id in resolve.iter() {
+                let versions = counts.entry(id.name()).or_insert(HashMap::new());
+                *versions.entry(id.version()).or_insert(0) += 1;
+            }
+            Some(counts)
+        } else {
+            None
+        };
+        EncodeState { counts: counts }
+    }
+}
+
+fn encodable_resolve_node(id: &PackageId, resolve: &Resolve, state: &EncodeState)
                           -> EncodableDependency {
     let (replace, deps) = match resolve.replacement(id) {
         Some(id) => {
@@ -341,23 +475,25 @@ fn encodable_resolve_node(id: &PackageId, resolve: &Resolve)
         None => {
             let mut deps = resolve.graph.edges(id)
                                   .into_iter().flat_map(|a| a)
-                                  .map(encodable_package_id)
+                                  .map(|id| encodable_dependency_id(id, state))
                                   .collect::<Vec<_>>();
             deps.sort();
             (None, Some(deps))
         }
     };
 
-    let source = if id.source_id().is_path() {
-        None
-    } else {
-        Some(id.source_id().clone())
+    let checksum = match resolve.version() {
+        ResolveVersion::V1 => None,
+        ResolveVersion::V2 => {
+            resolve.checksums.get(id).and_then(|c| c.clone())
+        }
     };
 
     EncodableDependency {
         name: id.name().to_string(),
         version: id.version().to_string(),
-        source: source,
+        source: encode_source(id.source_id()),
+        checksum: checksum,
         dependencies: deps,
         replace: replace,
     }
@@ -371,7 +507,30 @@ fn encodable_package_id(id: &PackageId) -> EncodablePackageId {
     };
     EncodablePackageId {
         name: id.name().to_string(),
-        version: id.version().to_string(),
+        version: Some(id.version().to_string()),
         source: source,
     }
 }
+
+fn encodable_dependency_id(id: &PackageId, state: &EncodeState)
+                           -> EncodablePackageId {
+    let mut ret = encodable_package_id(id);
+    if let Some(ref counts) = state.counts {
+        let versions = &counts[id.name()];
+        if versions[id.version()] == 1 {
+            ret.source = None;
+            if versions.len() == 1 {
+                ret.version = None;
+            }
+        }
+    }
+    ret
+}
+
+fn encode_source(id: &SourceId) -> Option<SourceId> {
+    if id.is_path() {
+        None
+    } else {
+        Some(id.clone())
+    }
+}
@@ -0,0 +1,225 @@
+//! Resolution of features separately for each way a package is built.
+//!
+//! The main resolver unifies all features enabled for a package into a single
+//! set, so enabling `std` for a build dependency also enables it when the same
+//! package is compiled for the target, and features of dev-dependencies are
+//! active even when nothing needing dev-dependencies is built.
+//!
+//! Workspaces opting in with `resolver = "2"` run a second pass once versions
+//! have been selected. It walks the resolved graph again and tracks features
+//! per package, per side of the build (host or target) and per whether the
+//! dev-dependencies of the workspace members are being built.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use core::{PackageId, Summary};
+use util::{CargoResult, human};
+
+use super::{Method, Resolve};
+
+/// Which algorithm is used to decide the features of each package, as
+/// selected with the `resolver` key of the root manifest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveBehavior {
+    /// Features are unified across the whole dependency graph.
+    V1,
+    /// Features are resolved separately for the host and the target, and
+    /// dev-dependency features only apply when dev-dependencies are built.
+    V2,
+}
+
+impl ResolveBehavior {
+    pub fn from_manifest(resolver: &str) -> CargoResult<ResolveBehavior> {
+        match resolver {
+            "1" => Ok(ResolveBehavior::V1),
+            "2" => Ok(ResolveBehavior::V2),
+            s => Err(human(format!("`resolver` setting `{}` is not valid, \
+                                    valid options are \"1\" or \"2\"", s))),
+        }
+    }
+}
+
+/// The side of the build a package is compiled for, as far as its features
+/// are concerned.
+///
+/// Build scripts and everything they depend on are on the host side, all
+/// other packages are on the target side. This is independent of `Kind`, a
+/// build dependency is on the host side even when no `--target` is given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FeaturesFor {
+    Target,
+    Host,
+}
+
+type Key = (PackageId, FeaturesFor, bool);
+
+/// The features and optional dependencies activated for each package, keyed
+/// by the side of the build and whether dev-dependencies are built.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ResolvedFeatures {
+    features: HashMap<Key, HashSet<String>>,
+    optional_deps: HashMap<Key, HashSet<String>>,
+}
+
+/// Features requested of a package by its dependents so far.
+#[derive(Default)]
+struct Requested {
+    features: Vec<String>,
+    uses_default_features: bool,
+    everything: bool,
+}
+
+impl ResolvedFeatures {
+    pub fn features(&self, pkg: &PackageId, features_for: FeaturesFor,
+                    dev_units: bool) -> Option<&HashSet<String>> {
+        self.features.get(&(pkg.clone(), features_for, dev_units))
+    }
+
+    pub fn optional_deps(&self, pkg: &PackageId, features_for: FeaturesFor,
+                         dev_units: bool) -> Option<&HashSet<String>> {
+        self.optional_deps.get(&(pkg.clone(), features_for, dev_units))
+    }
+}
+
+/// Resolves features for the `roots` of an already resolved graph, once with
+/// and once without the dev-dependencies of the roots.
+///
+/// `summaries` must contain the summary of every package in `resolve`.
+pub fn resolve_features(resolve: &Resolve,
+                        summaries: &HashMap<&PackageId, &Summary>,
+                        roots: &[(Summary, Method)])
+                        -> CargoResult<ResolvedFeatures> {
+    let mut ret = ResolvedFeatures::default();
+    for &dev_units in [false, true].iter() {
+        let mut walk = Walk {
+            resolve: resolve,
+            summaries: summaries,
+            dev_units: dev_units,
+            requested: HashMap::new(),
+            queue: VecDeque::new(),
+            ret: &mut ret,
+        };
+        for &(ref summary, ref method) in roots {
+            let key = (summary.package_id().clone(), FeaturesFor::Target,
+                       dev_units);
+            match *method {
+                Method::Everything => walk.request_everything(key),
+                Method::Required { features, uses_default_features, .. } => {
+                    walk.request(key, features, uses_default_features)
+                }
+            }
+        }
+        walk.run(roots)?;
+    }
+    Ok(ret)
+}
+
+struct Walk<'a> {
+    resolve: &'a Resolve,
+    summaries: &'a HashMap<&'a PackageId, &'a Summary>,
+    dev_units: bool,
+    requested: HashMap<Key, Requested>,
+    queue: VecDeque<Key>,
+    ret: &'a mut ResolvedFeatures,
+}
+
+impl<'a> Walk<'a> {
+    fn request(&mut self, key: Key, features: &[String],
+               uses_default_features: bool) {
+        let changed = {
+            let prev = self.requested.entry(key.clone())
+                           .or_insert(Requested::default());
+            let mut changed = !self.ret.features.contains_key(&key);
+            for feature in features {
+                if !prev.features.contains(feature) {
+                    prev.features.push(feature.clone());
+                    changed = true;
+                }
+            }
+            if uses_default_features && !prev.uses_default_features {
+                prev.uses_default_features = true;
+                changed = true;
+            }
+            changed
+        };
+        if changed {
+            self.queue.push_back(key);
+        }
+    }
+
+    fn request_everything(&mut self, key: Key) {
+        self.requested.entry(key.clone())
+            .or_insert(Requested::default())
+            .everything = true;
+        self.queue.push_back(key);
+    }
+
+    // Processes queued packages until no package has any new feature
+    // requested of it, as features only ever get added this terminates.
+    fn run(&mut self, roots: &[(Summary, Method)]) -> CargoResult<()> {
+        while let Some(key) = self.queue.pop_front() {
+            let is_root = roots.iter().any(|&(ref s, _)| {
+                *s.package_id() == key.0
+            });
+            self.activate(key, is_root)?;
+        }
+        Ok(())
+    }
+
+    fn activate(&mut self, key: Key, is_root: bool) -> CargoResult<()> {
+        let summaries = self.summaries;
+        let summary = match summaries.get(&key.0) {
+            Some(summary) => *summary,
+            None => return Ok(()),
+        };
+        let (feature_deps, mut weak_deps, used) = {
+            let requested = &self.requested[&key];
+            let method = if requested.everything {
+                Method::Everything
+            } else {
+                Method::Required {
+                    dev_deps: is_root && self.dev_units,
+                    features: &requested.features,
+                    uses_default_features: requested.uses_default_features,
+                }
+            };
+            super::build_features(summary, &method)?
+        };
+        self.ret.features.insert(key.clone(), used);
+
+        let mut optional_deps = HashSet::new();
+        for dep in summary.dependencies() {
+            if !dep.is_transitive() && !(is_root && self.dev_units) {
+                continue
+            }
+            let name = dep.name_in_toml();
+            if dep.is_optional() && !feature_deps.contains_key(name) {
+                continue
+            }
+            let dep_id = self.resolve.deps(&key.0).find(|id| {
+                self.resolve.dep_matches_id(dep, id)
+            }).cloned();
+            let dep_id = match dep_id {
+                Some(id) => id,
+                None => continue,
+            };
+            if dep.is_optional() {
+                optional_deps.insert(name.to_string());
+            }
+
+            let mut features = feature_deps.get(name).cloned()
+                                           .unwrap_or(Vec::new());
+            features.extend(weak_deps.remove(name).unwrap_or(Vec::new()));
+            features.extend(dep.features().iter().cloned());
+            let features_for = if dep.is_build() {
+                FeaturesFor::Host
+            } else {
+                key.1
+            };
+            self.request((dep_id, features_for, key.2), &features,
+                         dep.uses_default_features());
+        }
+        self.ret.optional_deps.insert(key, optional_deps);
+        Ok(())
+    }
+}
@@ -27,7 +27,18 @@
 //! * Always try to activate the highest version crate first. The default
 //!   dependency in Cargo (e.g. when you write `foo = "0.1.2"`) is
 //!   semver-compatible, so selecting the highest version possible will allow us
-//!   to hopefully satisfy as many dependencies at once.
+//!   to hopefully satisfy as many dependencies at once. In "minimal versions"
+//!   mode this is reversed and the lowest version is tried first, which is
+//!   useful for checking that the lower bounds of version requirements are
+//!   actually accurate.
+//!
+//! Dependencies can also be declared `public`, in which case the crates a
+//! package sees through them must each resolve to a single version, see the
+//! `public` module.
+//!
+//! Once versions have been selected, workspaces opting into the second
+//! feature resolver additionally have features resolved per build context,
+//! see the `features` module.
 //!
 //! Beyond that, what's implemented below is just a naive backtracking version
 //! which should in theory try all possible combinations of dependencies and
@@ -45,7 +56,7 @@
 //! that we're implementing something that probably shouldn't be allocating all
 //! over the place.
 
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
 use std::collections::{HashSet, HashMap, BinaryHeap, BTreeMap};
 use std::iter::FromIterator;
 use std::fmt;
@@ -53,6 +64,7 @@ use std::ops::Range;
 use std::rc::Rc;
 
 use semver;
+use url::Url;
 
 use core::{PackageId, Registry, SourceId, Summary, Dependency};
 use core::PackageIdSpec;
@@ -62,23 +74,38 @@ use util::ChainError;
 use util::graph::{Nodes, Edges};
 
 pub use self::encode::{EncodableResolve, EncodableDependency, EncodablePackageId};
-pub use self::encode::{Metadata, WorkspaceResolve};
+pub use self::encode::{Metadata, WorkspaceResolve, Patch, ResolveVersion};
+pub use self::conflict::{ConflictTree, Conflict, PathStep, ResolveError};
+pub use self::features::{FeaturesFor, ResolveBehavior, ResolvedFeatures};
 
+use self::conflict::ConflictReason;
+use self::public::PublicDependency;
+
+mod conflict;
 mod encode;
+mod features;
+mod public;
 
 /// Represents a fully resolved package dependency graph. Each node in the graph
 /// is a package and edges represent dependencies between packages.
 ///
 /// Each instance of `Resolve` also understands the full set of features used
-/// for each package.
+/// for each package, and with `ResolveBehavior::V2` the features used for
+/// each package in each build context.
 #[derive(PartialEq, Eq, Clone)]
 pub struct Resolve {
     graph: Graph<PackageId>,
     replacements: HashMap<PackageId, PackageId>,
     empty_features: HashSet<String>,
     features: HashMap<PackageId, HashSet<String>>,
+    optional_deps: HashMap<PackageId, HashSet<String>>,
+    resolved_features: Option<ResolvedFeatures>,
     checksums: HashMap<PackageId, Option<String>>,
     metadata: Metadata,
+    unused_patches: Vec<PackageId>,
+    patched: HashMap<PackageId, HashSet<SourceId>>,
+    held_back: HashMap<PackageId, (PackageId, String)>,
+    version: ResolveVersion,
 }
 
 pub struct Deps<'a> {
@@ -112,6 +139,18 @@ struct Candidate {
 }
 
 impl Resolve {
+    /// Records all `[patch]` summaries which did not end up in the resolved
+    /// graph, so they can be preserved in the lock file and warned about.
+    pub fn register_used_patches(&mut self,
+                                 patches: &HashMap<Url, Vec<Summary>>) {
+        for summary in patches.values().flat_map(|v| v) {
+            if self.iter().any(|id| id == summary.package_id()) {
+                continue
+            }
+            self.unused_patches.push(summary.package_id().clone());
+        }
+    }
+
     pub fn merge_from(&mut self, previous: &Resolve) -> CargoResult<()> {
         // Given a previous instance of resolve, it should be forbidden to ever
         // have a checksums which *differ*. If the same package id has differing
@@ -189,9 +228,84 @@ unable to verify that `{0}` is the same as when the lockfile was generated
 
         // Be sure to just copy over any unknown metadata.
         self.metadata = previous.metadata.clone();
+        // The lock file keeps its format until it's rewritten.
+        self.version = previous.version;
         Ok(())
     }
 
+    /// Combines the two sides of a lock file with merge conflicts.
+    ///
+    /// All packages of both sides are kept, except where the two sides lock
+    /// semver compatible versions of the same package, in which case only the
+    /// higher version is kept and dependencies on the other are redirected to
+    /// it. The result is meant to be used as the previous resolve when
+    /// resolving again.
+    pub fn merge_conflict(ours: &Resolve, theirs: &Resolve) -> CargoResult<Resolve> {
+        // Packages of `ours` come last so they're kept over equal versions of
+        // `theirs`, which only differ in things like git revisions.
+        let all = theirs.iter().chain(ours.iter()).collect::<Vec<_>>();
+        let kept = all.iter().map(|&id| {
+            let newest = all.iter().filter(|other| {
+                other.name() == id.name() &&
+                    other.source_id() == id.source_id() &&
+                    compatible(other.version(), id.version())
+            }).max_by(|a, b| a.version().cmp(b.version())).unwrap();
+            (id, *newest)
+        }).collect::<HashMap<_, _>>();
+
+        let mut graph = Graph::new();
+        let mut replacements = HashMap::new();
+        let mut checksums = HashMap::new();
+        let mut metadata = ours.metadata.clone();
+        let mut unused_patches = ours.unused_patches.clone();
+        for side in [ours, theirs].iter() {
+            for id in side.iter().filter(|id| kept[id] == *id) {
+                if graph.edges(id).is_none() {
+                    graph.add(id.clone(), &[]);
+                }
+                for dep in side.graph.edges(id).into_iter().flat_map(|e| e) {
+                    graph.link(id.clone(), kept[dep].clone());
+                }
+                if let Some(replace) = side.replacements.get(id) {
+                    replacements.insert(id.clone(), kept[replace].clone());
+                }
+                if let Some(cksum) = side.checksums.get(id) {
+                    match checksums.get(id) {
+                        Some(prev) if prev != cksum => {
+                            bail!("checksum for `{}` differs between the two \
+                                   sides of the conflict", id)
+                        }
+                        _ => {}
+                    }
+                    checksums.insert(id.clone(), cksum.clone());
+                }
+            }
+            for (k, v) in side.metadata.iter() {
+                metadata.entry(k.clone()).or_insert(v.clone());
+            }
+            for id in side.unused_patches.iter() {
+                if !unused_patches.contains(id) {
+                    unused_patches.push(id.clone());
+                }
+            }
+        }
+
+        Ok(Resolve {
+            graph: graph,
+            empty_features: HashSet::new(),
+            features: HashMap::new(),
+            optional_deps: HashMap::new(),
+            resolved_features: None,
+            replacements: replacements,
+            checksums: checksums,
+            metadata: metadata,
+            unused_patches: unused_patches,
+            patched: HashMap::new(),
+            held_back: HashMap::new(),
+            version: cmp::max(ours.version, theirs.version),
+        })
+    }
+
     pub fn iter(&self) -> Nodes<PackageId> {
         self.graph.iter()
     }
@@ -212,19 +326,114 @@ unable to verify that `{0}` is the same as when the lockfile was generated
         &self.replacements
     }
 
+    /// Returns whether `dep` is the dependency which pulled in `id`.
+    ///
+    /// Unlike `Dependency::matches_id` this also holds when the package was
+    /// redirected to a different source, either by a `[patch]` of the
+    /// dependency's source or by a `[replace]` of the package it matched.
+    pub fn dep_matches_id(&self, dep: &Dependency, id: &PackageId) -> bool {
+        let matches = |id: &PackageId| {
+            if dep.matches_id(id) {
+                return true
+            }
+            let patched = self.patched.get(id).map(|sources| {
+                sources.contains(dep.source_id())
+            }).unwrap_or(false);
+            patched && dep.name() == id.name() &&
+                dep.version_req().matches(id.version())
+        };
+        matches(id) || self.replacements.iter().any(|(original, replace)| {
+            replace == id && matches(original)
+        })
+    }
+
     pub fn features(&self, pkg: &PackageId) -> &HashSet<String> {
         self.features.get(pkg).unwrap_or(&self.empty_features)
     }
 
+    /// Returns whether the optional dependency `dep` of `pkg` was activated,
+    /// either through a feature or with `dep:`.
+    pub fn is_optional_dep_activated(&self, pkg: &PackageId, dep: &str) -> bool {
+        self.optional_deps.get(pkg).map(|deps| deps.contains(dep))
+            .unwrap_or(false)
+    }
+
     pub fn features_sorted(&self, pkg: &PackageId) -> Vec<&str> {
         let mut v = Vec::from_iter(self.features(pkg).iter().map(|s| s.as_ref()));
         v.sort();
         v
     }
 
+    /// Returns whether features were resolved separately per build context,
+    /// in which case the `*_for` accessors below may differ from the unified
+    /// ones.
+    pub fn has_resolved_features(&self) -> bool {
+        self.resolved_features.is_some()
+    }
+
+    /// Returns the features of `pkg` when it is built for `features_for`,
+    /// with or without the dev-dependencies of the workspace members.
+    ///
+    /// Without a separate feature resolution this is the same as `features`.
+    pub fn features_for(&self, pkg: &PackageId, features_for: FeaturesFor,
+                        dev_units: bool) -> &HashSet<String> {
+        match self.resolved_features {
+            Some(ref r) => {
+                r.features(pkg, features_for, dev_units)
+                 .unwrap_or(&self.empty_features)
+            }
+            None => self.features(pkg),
+        }
+    }
+
+    pub fn features_sorted_for(&self, pkg: &PackageId, features_for: FeaturesFor,
+                               dev_units: bool) -> Vec<&str> {
+        let features = self.features_for(pkg, features_for, dev_units);
+        let mut v = Vec::from_iter(features.iter().map(|s| s.as_ref()));
+        v.sort();
+        v
+    }
+
+    /// Same as `is_optional_dep_activated`, but for one build context.
+    pub fn is_optional_dep_activated_for(&self, pkg: &PackageId, dep: &str,
+                                         features_for: FeaturesFor,
+                                         dev_units: bool) -> bool {
+        match self.resolved_features {
+            Some(ref r) => {
+                r.optional_deps(pkg, features_for, dev_units)
+                 .map(|deps| deps.contains(dep))
+                 .unwrap_or(false)
+            }
+            None => self.is_optional_dep_activated(pkg, dep),
+        }
+    }
+
     pub fn query(&self, spec: &str) -> CargoResult<&PackageId> {
         PackageIdSpec::query_str(spec, self.iter())
     }
+
+    pub fn unused_patches(&self) -> &[PackageId] {
+        &self.unused_patches
+    }
+
+    /// Packages which were resolved to an older version because newer ones
+    /// require a more recent rustc than the workspace's `rust-version`.
+    ///
+    /// Each is mapped to the newest version which was passed over, along with
+    /// the `rust-version` that version declares.
+    pub fn held_back(&self) -> &HashMap<PackageId, (PackageId, String)> {
+        &self.held_back
+    }
+
+    /// The format of the lock file this resolve was loaded from, or will be
+    /// written in.
+    pub fn version(&self) -> ResolveVersion {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: ResolveVersion) {
+        self.version = version;
+    }
 }
 
 impl fmt::Debug for Resolve {
@@ -261,20 +470,52 @@ struct Context<'a> {
     activations: HashMap<(String, SourceId), Vec<Rc<Summary>>>,
     resolve_graph: Graph<PackageId>,
     resolve_features: HashMap<PackageId, HashSet<String>>,
+    resolve_optional_deps: HashMap<PackageId, HashSet<String>>,
+    // Features requested with `dep?/feature`, by package and then by the name
+    // of the optional dependency they apply to once it's activated.
+    resolve_weak_deps: HashMap<PackageId, HashMap<String, HashSet<String>>>,
     resolve_replacements: HashMap<PackageId, PackageId>,
+    // Packages pulled in by `[patch]`, along with the sources they stand in
+    // for.
+    resolve_patches: HashMap<PackageId, HashSet<SourceId>>,
+    public_dependency: PublicDependency,
     replacements: &'a [(PackageIdSpec, Dependency)],
+    minimal_versions: bool,
+    rust_version: Option<RustVersion>,
 }
 
+// A `rust-version` split into its major, minor and patch components, with
+// missing ones being zero.
+type RustVersion = (u64, u64, u64);
+
 /// Builds the list of all packages required to build the first argument.
+///
+/// If `minimal_versions` is set then the lowest version matching each
+/// dependency is tried first instead of the highest. With
+/// `ResolveBehavior::V2` features are afterwards also resolved separately for
+/// each build context.
+///
+/// If `rust_version` is given then versions declaring a newer `rust-version`
+/// are only tried after all those which support it.
 pub fn resolve(summaries: &[(Summary, Method)],
                replacements: &[(PackageIdSpec, Dependency)],
-               registry: &mut Registry) -> CargoResult<Resolve> {
+               registry: &mut Registry,
+               minimal_versions: bool,
+               behavior: ResolveBehavior,
+               rust_version: Option<&str>) -> CargoResult<Resolve> {
+    let rust_version = rust_version.map(parse_rust_version);
     let cx = Context {
         resolve_graph: Graph::new(),
         resolve_features: HashMap::new(),
+        resolve_optional_deps: HashMap::new(),
+        resolve_weak_deps: HashMap::new(),
         resolve_replacements: HashMap::new(),
+        resolve_patches: HashMap::new(),
+        public_dependency: PublicDependency::new(),
         activations: HashMap::new(),
         replacements: replacements,
+        minimal_versions: minimal_versions,
+        rust_version: rust_version,
     };
     let _p = profile::start(format!("resolving"));
     let cx = activate_deps_loop(cx, registry, summaries)?;
@@ -283,9 +524,15 @@ pub fn resolve(summaries: &[(Summary, Method)],
         graph: cx.resolve_graph,
         empty_features: HashSet::new(),
         features: cx.resolve_features,
+        optional_deps: cx.resolve_optional_deps,
+        resolved_features: None,
         checksums: HashMap::new(),
         metadata: BTreeMap::new(),
         replacements: cx.resolve_replacements,
+        unused_patches: Vec::new(),
+        patched: cx.resolve_patches,
+        held_back: HashMap::new(),
+        version: ResolveVersion::default(),
     };
 
     for summary in cx.activations.values().flat_map(|v| v.iter()) {
@@ -295,10 +542,88 @@ pub fn resolve(summaries: &[(Summary, Method)],
 
     check_cycles(&resolve, &cx.activations)?;
 
+    if let Some(rust_version) = rust_version {
+        if !minimal_versions {
+            resolve.held_back = held_back(&resolve, &cx.activations, registry,
+                                          rust_version)?;
+        }
+    }
+
+    if behavior == ResolveBehavior::V2 {
+        let _p = profile::start("resolving features");
+        let all: HashMap<_, _> = cx.activations.values()
+            .flat_map(|v| v.iter())
+            .map(|s| (s.package_id(), &**s))
+            .collect();
+        let resolved = features::resolve_features(&resolve, &all, summaries)?;
+        resolve.resolved_features = Some(resolved);
+    }
+
     trace!("resolved: {:?}", resolve);
     Ok(resolve)
 }
 
+fn parse_rust_version(s: &str) -> RustVersion {
+    let mut parts = s.split('.').map(|p| p.parse().unwrap_or(0));
+    (parts.next().unwrap_or(0),
+     parts.next().unwrap_or(0),
+     parts.next().unwrap_or(0))
+}
+
+// Whether `summary` can be built by the rustc `rust_version`, which is the
+// case unless it declares a newer `rust-version` itself.
+fn supports_rust_version(summary: &Summary,
+                         rust_version: Option<RustVersion>) -> bool {
+    match (summary.rust_version(), rust_version) {
+        (Some(required), Some(available)) => {
+            parse_rust_version(required) <= available
+        }
+        _ => true,
+    }
+}
+
+// Finds the activated packages for which a newer version matching the
+// dependency on them exists, but was passed over as it doesn't support
+// `rust_version`.
+fn held_back(resolve: &Resolve,
+             activations: &HashMap<(String, SourceId), Vec<Rc<Summary>>>,
+             registry: &mut Registry,
+             rust_version: RustVersion)
+             -> CargoResult<HashMap<PackageId, (PackageId, String)>> {
+    let summaries: HashMap<_, _> = activations.values()
+        .flat_map(|v| v.iter())
+        .map(|s| (s.package_id(), s))
+        .collect();
+    let mut ret = HashMap::new();
+    for (id, summary) in summaries.iter() {
+        for child in resolve.deps_not_replaced(id) {
+            if ret.contains_key(child) {
+                continue
+            }
+            match summaries.get(child) {
+                Some(s) if supports_rust_version(s, Some(rust_version)) => {}
+                _ => continue,
+            }
+            let deps = summary.dependencies().iter().filter(|d| {
+                resolve.dep_matches_id(d, child)
+            });
+            for dep in deps {
+                let newest = registry.query(dep)?.into_iter().filter(|s| {
+                    s.version() > child.version() &&
+                        !supports_rust_version(s, Some(rust_version))
+                }).max_by(|a, b| a.version().cmp(b.version()));
+                if let Some(newest) = newest {
+                    let required = newest.rust_version().unwrap().to_string();
+                    ret.insert(child.clone(),
+                               (newest.package_id().clone(), required));
+                    break
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
 /// Attempts to activate the summary `candidate` in the context `cx`.
 ///
 /// This function will pull dependency summaries from the registry provided, and
@@ -307,13 +632,21 @@ pub fn resolve(summaries: &[(Summary, Method)],
 /// iterate through next.
 fn activate(cx: &mut Context,
             registry: &mut Registry,
-            parent: Option<&Rc<Summary>>,
+            parent: Option<(&Rc<Summary>, &Dependency)>,
             candidate: Candidate,
             method: &Method)
             -> CargoResult<Option<DepsFrame>> {
-    if let Some(parent) = parent {
+    if let Some((parent, dep)) = parent {
         cx.resolve_graph.link(parent.package_id().clone(),
                               candidate.summary.package_id().clone());
+        cx.public_dependency.add_edge(parent.package_id(),
+                                      candidate.summary.package_id(),
+                                      dep.is_public());
+        if candidate.summary.source_id() != dep.source_id() {
+            cx.resolve_patches.entry(candidate.summary.package_id().clone())
+              .or_insert_with(HashSet::new)
+              .insert(dep.source_id().clone());
+        }
     }
 
     let activated = cx.flag_activated(&candidate.summary, method);
@@ -431,6 +764,107 @@ struct BacktrackFrame<'a> {
     parent: Rc<Summary>,
     dep: Dependency,
     features: Vec<String>,
+    // The activations, all present in `context_backup`, which were involved in
+    // ruling out candidates of `dep` so far, or `None` if a failure couldn't
+    // be attributed to a set of activations.
+    conflicting: Option<HashSet<PackageId>>,
+}
+
+/// Sets of activations which are known to make a dependency impossible to
+/// resolve, keyed by the dependency's name, source and version requirement.
+///
+/// Entries are added whenever every candidate of a dependency has been ruled
+/// out, and are consulted before activating a candidate so that versions whose
+/// dependencies are already known to fail aren't explored over and over again.
+struct ConflictCache {
+    known: HashMap<(String, SourceId, String), Vec<HashSet<PackageId>>>,
+}
+
+impl ConflictCache {
+    fn new() -> ConflictCache {
+        ConflictCache { known: HashMap::new() }
+    }
+
+    fn key(dep: &Dependency) -> (String, SourceId, String) {
+        (dep.name().to_string(), dep.source_id().clone(),
+         dep.version_req().to_string())
+    }
+
+    fn insert(&mut self, dep: &Dependency, conflicting: &HashSet<PackageId>) {
+        let known = self.known.entry(ConflictCache::key(dep))
+                              .or_insert(Vec::new());
+        if !known.contains(conflicting) {
+            known.push(conflicting.clone());
+        }
+    }
+
+    /// Returns the activations in `cx` which, together with `candidate`, are
+    /// already known to leave one of the candidate's dependencies without any
+    /// valid version.
+    fn check(&self, cx: &Context, candidate: &Candidate)
+             -> Option<HashSet<PackageId>> {
+        if candidate.replace.is_some() {
+            return None
+        }
+        let id = candidate.summary.package_id();
+        for dep in candidate.summary.dependencies() {
+            if !dep.is_transitive() || dep.is_optional() {
+                continue
+            }
+            let known = match self.known.get(&ConflictCache::key(dep)) {
+                Some(known) => known,
+                None => continue,
+            };
+            for conflicting in known {
+                if conflicting.iter().all(|p| p == id || cx.is_active(p)) {
+                    return Some(conflicting.iter().filter(|p| *p != id)
+                                           .cloned().collect())
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Takes the next candidate out of `remaining` which isn't already known to
+/// fail in `cx`, adding the reasons for skipping any others to `conflicting`.
+fn next_candidate(cx: &Context,
+                  conflict_cache: &ConflictCache,
+                  remaining: &mut RcVecIter<Candidate>,
+                  conflicting: &mut Option<HashSet<PackageId>>)
+                  -> Option<Candidate> {
+    while let Some((_, candidate)) = remaining.next() {
+        match conflict_cache.check(cx, &candidate) {
+            Some(known) => {
+                trace!("skipping {}, known to conflict",
+                       candidate.summary.package_id());
+                if let Some(ref mut conflicting) = *conflicting {
+                    conflicting.extend(known);
+                }
+            }
+            None => return Some(candidate),
+        }
+    }
+    None
+}
+
+/// The activations to backjump with when no candidate of `dep` is left: those
+/// that ruled the candidates out plus the parent which required `dep` in the
+/// first place.
+///
+/// An optional dependency is only required depending on what features end up
+/// being enabled, so the failure can't be pinned on its parent being active.
+fn conflict_with_parent(dep: &Dependency,
+                        parent: &PackageId,
+                        conflicting: Option<HashSet<PackageId>>)
+                        -> Option<HashSet<PackageId>> {
+    if dep.is_optional() {
+        return None
+    }
+    conflicting.map(|mut conflicting| {
+        conflicting.insert(parent.clone());
+        conflicting
+    })
 }
 
 /// Recursively activates the dependencies for `top`, in depth-first order,
@@ -451,6 +885,7 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
     // use (those with more candidates).
     let mut backtrack_stack = Vec::new();
     let mut remaining_deps = BinaryHeap::new();
+    let mut conflict_cache = ConflictCache::new();
     for &(ref summary, ref method) in summaries {
         debug!("initial activation: {}", summary.package_id());
         let summary = Rc::new(summary.clone());
@@ -484,6 +919,7 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
         let (mut parent, (mut cur, (mut dep, candidates, mut features))) = frame;
         assert!(!remaining_deps.is_empty());
 
+        let mut rejected = Vec::new();
         let my_candidates = {
             let prev_active = cx.prev_active(&dep);
             trace!("{}[{}]>{} {} candidates", parent.name(), cur, dep.name(),
@@ -497,12 +933,30 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
             // incompatible with all other activated versions. Note that we
             // define "compatible" here in terms of the semver sense where if
             // the left-most nonzero digit is the same they're considered
-            // compatible.
+            // compatible. Candidates linking to a native library that's
+            // already linked to are filtered out as well, as are those which
+            // would let a package see two versions of a crate through public
+            // dependencies.
+            //
+            // Everything filtered out is remembered along with the reason so
+            // we can explain ourselves if resolution fails.
             candidates.iter().filter(|&b| {
-                prev_active.iter().any(|a| *a == b.summary) ||
-                    prev_active.iter().all(|a| {
-                        !compatible(a.version(), b.summary.version())
-                    })
+                let conflict = cx.conflicting_activation(&b.summary,
+                                                         prev_active);
+                let conflict = conflict.or_else(|| {
+                    cx.public_dependency.conflict(parent.package_id(),
+                                                  b.summary.package_id(),
+                                                  dep.is_public())
+                      .map(|(pkg, id)| (id, ConflictReason::Public(pkg)))
+                });
+                match conflict {
+                    Some((id, reason)) => {
+                        rejected.push((b.summary.package_id().clone(), id,
+                                       reason));
+                        false
+                    }
+                    None => true,
+                }
             }).cloned().collect()
         };
 
@@ -517,10 +971,33 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
         //
         // This means that we're going to attempt to activate each candidate in
         // turn. We could possibly fail to activate each candidate, so we try
-        // each one in turn.
+        // each one in turn, skipping those which previous failures tell us
+        // won't work anyway. If that's all of them we still try the first so
+        // that a failure is reported for an actual conflict.
+        //
+        // Conflicts through public dependencies depend on the edges of the
+        // graph rather than just on which packages are activated, so those
+        // can't be used to jump back.
+        let public_conflict = rejected.iter().any(|&(_, _, ref reason)| {
+            match *reason {
+                ConflictReason::Public(..) => true,
+                _ => false,
+            }
+        });
+        let mut conflicting = if public_conflict {
+            None
+        } else {
+            Some(rejected.iter().map(|&(_, ref id, _)| {
+                id.clone()
+            }).collect::<HashSet<_>>())
+        };
+        let first = my_candidates.get(0).cloned();
         let mut remaining_candidates = RcVecIter::new(my_candidates);
-        let candidate = match remaining_candidates.next() {
-            Some((_, candidate)) => {
+        let next = next_candidate(&cx, &conflict_cache,
+                                  &mut remaining_candidates,
+                                  &mut conflicting);
+        let candidate = match next.or(first) {
+            Some(candidate) => {
                 // We have a candidate. Add an entry to the `backtrack_stack` so
                 // we can try the next one if this one fails.
                 backtrack_stack.push(BacktrackFrame {
@@ -530,26 +1007,35 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
                     parent: parent.clone(),
                     dep: dep.clone(),
                     features: features.clone(),
+                    conflicting: conflicting,
                 });
                 candidate
             }
             None => {
-                // This dependency has no valid candidate. Backtrack until we
-                // find a dependency that does have a candidate to try, and try
-                // to activate that one.  This resets the `remaining_deps` to
-                // their state at the found level of the `backtrack_stack`.
+                // This dependency has no valid candidate. Remember why, and
+                // jump back to the most recent dependency that could change
+                // that and still has a candidate to try, and try to activate
+                // that one. This resets the `remaining_deps` to their state at
+                // the found level of the `backtrack_stack`.
                 trace!("{}[{}]>{} -- no candidates", parent.name(), cur,
                        dep.name());
+                if let Some(ref conflicting) = conflicting {
+                    conflict_cache.insert(&dep, conflicting);
+                }
+                let conflicting = conflict_with_parent(&dep,
+                                                       parent.package_id(),
+                                                       conflicting);
                 match find_candidate(&mut backtrack_stack,
+                                     &mut conflict_cache,
                                      &mut cx,
                                      &mut remaining_deps,
                                      &mut parent,
                                      &mut cur,
                                      &mut dep,
-                                     &mut features) {
+                                     &mut features,
+                                     conflicting) {
                     None => return Err(activation_error(&cx, registry, &parent,
-                                                        &dep,
-                                                        &cx.prev_active(&dep),
+                                                        &dep, &rejected,
                                                         &candidates)),
                     Some(candidate) => candidate,
                 }
@@ -563,8 +1049,9 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
         };
         trace!("{}[{}]>{} trying {}", parent.name(), cur, dep.name(),
                candidate.summary.version());
-        remaining_deps.extend(activate(&mut cx, registry, Some(&parent),
-                              candidate, &method)?);
+        remaining_deps.extend(activate(&mut cx, registry,
+                                       Some((&parent, &dep)),
+                                       candidate, &method)?);
     }
 
     Ok(cx)
@@ -573,15 +1060,47 @@ fn activate_deps_loop<'a>(mut cx: Context<'a>,
 // Searches up `backtrack_stack` until it finds a dependency with remaining
 // candidates. Resets `cx` and `remaining_deps` to that level and returns the
 // next candidate. If all candidates have been exhausted, returns None.
+//
+// Frames which already had everything in `conflicting` activated before they
+// picked a candidate can't resolve the conflict by picking another one, so
+// those are jumped over entirely. When a frame runs out of candidates the
+// activations which ruled them all out are recorded in `conflict_cache` and
+// become the new set to jump back with.
 fn find_candidate<'a>(backtrack_stack: &mut Vec<BacktrackFrame<'a>>,
+                      conflict_cache: &mut ConflictCache,
                       cx: &mut Context<'a>,
                       remaining_deps: &mut BinaryHeap<DepsFrame>,
                       parent: &mut Rc<Summary>,
                       cur: &mut usize,
                       dep: &mut Dependency,
-                      features: &mut Vec<String>) -> Option<Candidate> {
+                      features: &mut Vec<String>,
+                      mut conflicting: Option<HashSet<PackageId>>)
+                      -> Option<Candidate> {
     while let Some(mut frame) = backtrack_stack.pop() {
-        if let Some((_, candidate)) = frame.remaining_candidates.next() {
+        if let Some(ref conflicting) = conflicting {
+            if conflicting.iter().all(|id| frame.context_backup.is_active(id)) {
+                trace!("backjumping over {}>{}", frame.parent.name(),
+                       frame.dep.name());
+                continue
+            }
+        }
+
+        // The conflict involves this frame's choice, so whatever else was
+        // involved becomes part of the reason this dependency may fail.
+        frame.conflicting = match (frame.conflicting.take(), conflicting) {
+            (Some(mut mine), Some(theirs)) => {
+                let backup = &frame.context_backup;
+                mine.extend(theirs.into_iter().filter(|id| backup.is_active(id)));
+                Some(mine)
+            }
+            _ => None,
+        };
+
+        let next = next_candidate(&frame.context_backup,
+                                  conflict_cache,
+                                  &mut frame.remaining_candidates,
+                                  &mut frame.conflicting);
+        if let Some(candidate) = next {
             *cx = frame.context_backup.clone();
             *remaining_deps = frame.deps_backup.clone();
             *parent = frame.parent.clone();
@@ -591,6 +1110,13 @@ fn find_candidate<'a>(backtrack_stack: &mut Vec<BacktrackFrame<'a>>,
             backtrack_stack.push(frame);
             return Some(candidate)
         }
+
+        if let Some(ref conflicting) = frame.conflicting {
+            conflict_cache.insert(&frame.dep, conflicting);
+        }
+        conflicting = conflict_with_parent(&frame.dep,
+                                           frame.parent.package_id(),
+                                           frame.conflicting);
     }
     None
 }
@@ -599,41 +1125,33 @@ fn activation_error(cx: &Context,
                     registry: &mut Registry,
                     parent: &Summary,
                     dep: &Dependency,
-                    prev_active: &[Rc<Summary>],
+                    rejected: &[(PackageId, PackageId, ConflictReason)],
                     candidates: &[Candidate]) -> Box<CargoError> {
     if candidates.len() > 0 {
-        let mut msg = format!("failed to select a version for `{}` \
-                               (required by `{}`):\n\
-                               all possible versions conflict with \
-                               previously selected versions of `{}`",
-                              dep.name(), parent.name(),
-                              dep.name());
-        'outer: for v in prev_active.iter() {
-            for node in cx.resolve_graph.iter() {
-                let edges = match cx.resolve_graph.edges(node) {
-                    Some(edges) => edges,
-                    None => continue,
-                };
-                for edge in edges {
-                    if edge != v.package_id() { continue }
-
-                    msg.push_str(&format!("\n  version {} in use by {}",
-                                          v.version(), edge));
-                    continue 'outer;
+        // Group the rejected candidates by the activated package they
+        // conflicted with, so each conflicting path is only printed once.
+        let mut conflicts: Vec<Conflict> = Vec::new();
+        for &(ref candidate, ref activated, ref reason) in rejected {
+            let pos = conflicts.iter().position(|c| c.package_id == *activated);
+            let pos = match pos {
+                Some(pos) => pos,
+                None => {
+                    let path = cx.path_to_root(activated);
+                    conflicts.push(Conflict::new(activated.clone(), reason, path));
+                    conflicts.len() - 1
                 }
-            }
-            msg.push_str(&format!("\n  version {} in use by ??",
-                                  v.version()));
+            };
+            conflicts[pos].rejected.push(candidate.version().to_string());
         }
 
-        msg.push_str(&format!("\n  possible versions to select: {}",
-                              candidates.iter()
-                                        .map(|v| v.summary.version())
-                                        .map(|v| v.to_string())
-                                        .collect::<Vec<_>>()
-                                        .join(", ")));
-
-        return human(msg)
+        let tree = ConflictTree {
+            dependency: dep.name().to_string(),
+            requirement: dep.version_req().to_string(),
+            required_by: parent.package_id().clone(),
+            required_by_path: cx.path_to_root(parent.package_id()),
+            conflicts: conflicts,
+        };
+        return Box::new(ResolveError::new(tree))
     }
 
     // Once we're all the way down here, we're definitely lost in the
@@ -722,55 +1240,84 @@ fn compatible(a: &semver::Version, b: &semver::Version) -> bool {
 // enabled, which is later used when compiling to instruct the code what
 // features were enabled.
 fn build_features(s: &Summary, method: &Method)
-                  -> CargoResult<(HashMap<String, Vec<String>>, HashSet<String>)> {
+                  -> CargoResult<(HashMap<String, Vec<String>>,
+                                  HashMap<String, Vec<String>>,
+                                  HashSet<String>)> {
     let mut deps = HashMap::new();
+    let mut weak_deps = HashMap::new();
     let mut used = HashSet::new();
     let mut visited = HashSet::new();
-    match *method {
-        Method::Everything => {
-            for key in s.features().keys() {
-                add_feature(s, key, &mut deps, &mut used, &mut visited)?;
-            }
-            for dep in s.dependencies().iter().filter(|d| d.is_optional()) {
-                add_feature(s, dep.name(), &mut deps, &mut used,
-                            &mut visited)?;
+    {
+        let mut add = |feat: &str| {
+            add_feature(s, feat, &mut deps, &mut weak_deps, &mut used,
+                        &mut visited)
+        };
+        match *method {
+            Method::Everything => {
+                for key in s.features().keys() {
+                    add(key)?;
+                }
+                for dep in s.dependencies().iter().filter(|d| d.is_optional()) {
+                    let name = dep.name_in_toml();
+                    if s.has_implicit_feature(name) {
+                        add(name)?;
+                    } else {
+                        add(&format!("dep:{}", name))?;
+                    }
+                }
             }
-        }
-        Method::Required { features: requested_features, .. } =>  {
-            for feat in requested_features.iter() {
-                add_feature(s, feat, &mut deps, &mut used, &mut visited)?;
+            Method::Required { features: requested_features, .. } =>  {
+                for feat in requested_features.iter() {
+                    add(feat)?;
+                }
             }
         }
-    }
-    match *method {
-        Method::Everything |
-        Method::Required { uses_default_features: true, .. } => {
-            if s.features().get("default").is_some() {
-                add_feature(s, "default", &mut deps, &mut used,
-                            &mut visited)?;
+        match *method {
+            Method::Everything |
+            Method::Required { uses_default_features: true, .. } => {
+                if s.features().get("default").is_some() {
+                    add("default")?;
+                }
             }
+            Method::Required { uses_default_features: false, .. } => {}
         }
-        Method::Required { uses_default_features: false, .. } => {}
     }
-    return Ok((deps, used));
+    return Ok((deps, weak_deps, used));
 
     fn add_feature(s: &Summary, feat: &str,
                    deps: &mut HashMap<String, Vec<String>>,
+                   weak_deps: &mut HashMap<String, Vec<String>>,
                    used: &mut HashSet<String>,
                    visited: &mut HashSet<String>) -> CargoResult<()> {
         if feat.is_empty() { return Ok(()) }
 
+        // A feature of the form `dep:foo` only activates the optional
+        // dependency `foo`, without enabling a feature of that name.
+        if feat.starts_with("dep:") {
+            deps.entry(feat[4..].to_string()).or_insert(Vec::new());
+            return Ok(())
+        }
+
         // If this feature is of the form `foo/bar`, then we just lookup package
-        // `foo` and enable its feature `bar`. Otherwise this feature is of the
-        // form `foo` and we need to recurse to enable the feature `foo` for our
-        // own package, which may end up enabling more features or just enabling
-        // a dependency.
+        // `foo` and enable its feature `bar`, or only record it for later if
+        // the form is `foo?/bar` as `foo` must be activated by something else.
+        // Otherwise this feature is of the form `foo` and we need to recurse to
+        // enable the feature `foo` for our own package, which may end up
+        // enabling more features or just enabling a dependency.
         let mut parts = feat.splitn(2, '/');
         let feat_or_package = parts.next().unwrap();
         match parts.next() {
+            Some(feat) if feat_or_package.ends_with('?') => {
+                let package = &feat_or_package[..feat_or_package.len() - 1];
+                weak_deps.entry(package.to_string())
+                         .or_insert(Vec::new())
+                         .push(feat.to_string());
+            }
             Some(feat) => {
                 let package = feat_or_package;
-                used.insert(package.to_string());
+                if s.has_implicit_feature(package) {
+                    used.insert(package.to_string());
+                }
                 deps.entry(package.to_string())
                     .or_insert(Vec::new())
                     .push(feat.to_string());
@@ -781,14 +1328,22 @@ fn build_features(s: &Summary, method: &Method)
                     bail!("Cyclic feature dependency: feature `{}` depends \
                            on itself", feat)
                 }
-                used.insert(feat.to_string());
                 match s.features().get(feat) {
                     Some(recursive) => {
+                        used.insert(feat.to_string());
                         for f in recursive {
-                            add_feature(s, f, deps, used, visited)?;
+                            add_feature(s, f, deps, weak_deps, used, visited)?;
                         }
                     }
+                    None if !s.has_implicit_feature(feat) => {
+                        bail!("Package `{}` does not have the feature `{}`.\n\
+                               It has an optional dependency with that name, \
+                               but that dependency is enabled with `dep:{}` \
+                               in its features, so it has no implicit feature \
+                               with that name", s.package_id(), feat, feat)
+                    }
                     None => {
+                        used.insert(feat.to_string());
                         deps.entry(feat.to_string()).or_insert(Vec::new());
                     }
                 }
@@ -847,9 +1402,24 @@ impl<'a> Context<'a> {
         let mut deps = deps.into_iter().map(|(dep, features)| {
             let mut candidates = self.query(registry, &dep)?;
             // When we attempt versions for a package, we'll want to start at
-            // the maximum version and work our way down.
+            // the maximum version and work our way down, unless we've been
+            // asked for minimal versions in which case it's the other way
+            // around. Either way versions which don't support the
+            // workspace's `rust-version` are only tried as a last resort.
+            let minimal_versions = self.minimal_versions;
+            let rust_version = self.rust_version;
             candidates.sort_by(|a, b| {
-                b.summary.version().cmp(a.summary.version())
+                let a_ok = supports_rust_version(&a.summary, rust_version);
+                let b_ok = supports_rust_version(&b.summary, rust_version);
+                match b_ok.cmp(&a_ok) {
+                    Ordering::Equal if minimal_versions => {
+                        a.summary.version().cmp(b.summary.version())
+                    }
+                    Ordering::Equal => {
+                        b.summary.version().cmp(a.summary.version())
+                    }
+                    ordering => ordering,
+                }
             });
             Ok((dep, candidates, features))
         }).collect::<CargoResult<Vec<DepInfo>>>()?;
@@ -932,6 +1502,78 @@ impl<'a> Context<'a> {
         }).collect()
     }
 
+    /// Returns the activated package which prevents `candidate` from being
+    /// activated as well, along with the reason, if there is one.
+    fn conflicting_activation(&self,
+                              candidate: &Summary,
+                              prev_active: &[Rc<Summary>])
+                              -> Option<(PackageId, ConflictReason)> {
+        if !prev_active.iter().any(|a| **a == *candidate) {
+            let semver = prev_active.iter().find(|a| {
+                compatible(a.version(), candidate.version())
+            });
+            if let Some(a) = semver {
+                return Some((a.package_id().clone(), ConflictReason::Semver))
+            }
+        }
+
+        let links = match candidate.links() {
+            Some(links) => links,
+            None => return None,
+        };
+        self.activations.values().flat_map(|v| v.iter()).find(|a| {
+            a.package_id() != candidate.package_id() &&
+                a.links() == Some(links)
+        }).map(|a| {
+            (a.package_id().clone(), ConflictReason::Links(links.to_string()))
+        })
+    }
+
+    /// Walks the activated dependency graph from `id` back up to a root,
+    /// recording the requirement through which each package was pulled in.
+    fn path_to_root(&self, id: &PackageId) -> Vec<PathStep> {
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+        let mut cur = id;
+        visited.insert(cur);
+        loop {
+            // The graph is hash-based, so pick the smallest parent to keep
+            // the reported path stable between runs.
+            let parent = self.resolve_graph.iter().filter(|node| {
+                !visited.contains(node) &&
+                    self.resolve_graph.edges(node)
+                        .map(|mut edges| edges.any(|e| e == cur))
+                        .unwrap_or(false)
+            }).min();
+            let parent = match parent {
+                Some(parent) => parent,
+                None => break,
+            };
+            let key = (parent.name().to_string(), parent.source_id().clone());
+            let requirement = self.activations.get(&key).and_then(|summaries| {
+                summaries.iter().find(|s| s.package_id() == parent)
+            }).and_then(|summary| {
+                summary.dependencies().iter().find(|d| d.matches_id(cur))
+            }).map(|d| {
+                format!("{} = \"{}\"", d.name_in_toml(), d.version_req())
+            }).unwrap_or_else(|| cur.name().to_string());
+            path.push(PathStep {
+                package_id: parent.clone(),
+                requirement: requirement,
+            });
+            visited.insert(parent);
+            cur = parent;
+        }
+        path
+    }
+
+    fn is_active(&self, id: &PackageId) -> bool {
+        let key = (id.name().to_string(), id.source_id().clone());
+        self.activations.get(&key).map(|prev| {
+            prev.iter().any(|s| s.package_id() == id)
+        }).unwrap_or(false)
+    }
+
     fn prev_active(&self, dep: &Dependency) -> &[Rc<Summary>] {
         let key = (dep.name().to_string(), dep.source_id().clone());
         self.activations.get(&key).map(|v| &v[..]).unwrap_or(&[])
@@ -948,25 +1590,58 @@ impl<'a> Context<'a> {
         let deps = candidate.dependencies();
         let deps = deps.iter().filter(|d| d.is_transitive() || dev_deps);
 
-        let (mut feature_deps, used_features) = build_features(candidate,
-                                                                    method)?;
+        let (mut feature_deps, weak_deps, used_features) =
+            build_features(candidate, method)?;
         let mut ret = Vec::new();
+        let mut optional_deps = HashSet::new();
+        let pkgid = candidate.package_id();
+
+        // Features enabled with `dep?/feature` only apply once the dependency
+        // has been activated, which may happen through another activation of
+        // this package, before or after this one. They're remembered until
+        // then.
+        {
+            let pending = self.resolve_weak_deps.entry(pkgid.clone())
+                              .or_insert(HashMap::new());
+            for (dep, features) in weak_deps {
+                pending.entry(dep).or_insert(HashSet::new()).extend(features);
+            }
+        }
 
         // Next, sanitize all requested features by whitelisting all the
         // requested features that correspond to optional dependencies
-        for dep in deps {
-            // weed out optional dependencies, but not those required
-            if dep.is_optional() && !feature_deps.contains_key(dep.name()) {
-                continue
-            }
-            let mut base = feature_deps.remove(dep.name()).unwrap_or(vec![]);
-            base.extend(dep.features().iter().map(|x| x.clone()));
-            for feature in base.iter() {
-                if feature.contains("/") {
-                    bail!("feature names may not contain slashes: `{}`", feature);
+        {
+            let weak_deps = &self.resolve_weak_deps[pkgid];
+            let prev_optional_deps = self.resolve_optional_deps.get(pkgid);
+            for dep in deps {
+                let name = dep.name_in_toml();
+                let weak = weak_deps.get(name);
+                // weed out optional dependencies, but not those required or
+                // already activated before which have weak features to pick up
+                if dep.is_optional() && !feature_deps.contains_key(name) {
+                    let prev = prev_optional_deps.map(|d| d.contains(name))
+                                                 .unwrap_or(false);
+                    if !prev || weak.is_none() {
+                        continue
+                    }
+                }
+                let mut base = feature_deps.remove(name).unwrap_or(vec![]);
+                base.extend(weak.into_iter().flat_map(|w| w.iter().cloned()));
+                base.extend(dep.features().iter().map(|x| x.clone()));
+                for feature in base.iter() {
+                    if feature.contains("/") {
+                        bail!("feature names may not contain slashes: `{}`", feature);
+                    }
+                    if feature.starts_with("dep:") {
+                        bail!("features of dependencies may not use `dep:`: `{}`",
+                              feature);
+                    }
                 }
+                if dep.is_optional() {
+                    optional_deps.insert(name.to_string());
+                }
+                ret.push((dep.clone(), base));
             }
-            ret.push((dep.clone(), base));
         }
 
         // All features can only point to optional dependencies, in which case
@@ -983,13 +1658,18 @@ impl<'a> Context<'a> {
             }
         }
 
-        // Record what list of features is active for this package.
+        // Record what list of features is active for this package, along
+        // with the optional dependencies that were activated.
         if !used_features.is_empty() {
-            let pkgid = candidate.package_id();
             self.resolve_features.entry(pkgid.clone())
                 .or_insert(HashSet::new())
                 .extend(used_features);
         }
+        if !optional_deps.is_empty() {
+            self.resolve_optional_deps.entry(pkgid.clone())
+                .or_insert(HashSet::new())
+                .extend(optional_deps);
+        }
 
         Ok(ret)
     }
@@ -1057,3 +1737,135 @@ fn check_cycles(resolve: &Resolve,
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use core::registry::test::RegistryBuilder;
+    use core::{Dependency, PackageId, SourceId, Summary};
+    use util::ToUrl;
+
+    use super::{resolve, Method, Resolve, ResolveBehavior};
+
+    fn source() -> SourceId {
+        SourceId::for_registry(&"http://example.com".to_url().unwrap())
+    }
+
+    fn dep(name: &str, req: &str) -> Dependency {
+        Dependency::parse_no_deprecated(name, Some(req), &source()).unwrap()
+    }
+
+    fn pkg(name: &str, vers: &str, deps: Vec<Dependency>) -> Summary {
+        let id = PackageId::new(name, vers, &source()).unwrap();
+        Summary::new(id, deps, HashMap::new()).unwrap()
+    }
+
+    fn resolve_root(deps: Vec<Dependency>,
+                    registry: &mut RegistryBuilder,
+                    minimal_versions: bool) -> Vec<String> {
+        let root = pkg("root", "1.0.0", deps);
+        let resolve = resolve(&[(root, Method::Everything)], &[], registry,
+                              minimal_versions, ResolveBehavior::V1,
+                              None).unwrap();
+        let mut ids = resolve.iter().map(|id| {
+            format!("{} {}", id.name(), id.version())
+        }).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn resolve_rust_version(deps: Vec<Dependency>,
+                            registry: &mut RegistryBuilder,
+                            rust_version: &str) -> Resolve {
+        let root = pkg("root", "1.0.0", deps);
+        resolve(&[(root, Method::Everything)], &[], registry, false,
+                ResolveBehavior::V1, Some(rust_version)).unwrap()
+    }
+
+    #[test]
+    fn highest_version_by_default() {
+        let mut reg = RegistryBuilder::new()
+            .summary(pkg("foo", "1.0.1", vec![]))
+            .summary(pkg("foo", "1.1.0", vec![]))
+            .summary(pkg("foo", "1.0.0", vec![]));
+
+        let res = resolve_root(vec![dep("foo", "1.0.1")], &mut reg, false);
+        assert_eq!(res, vec!["foo 1.1.0", "root 1.0.0"]);
+    }
+
+    #[test]
+    fn minimal_versions_picks_lowest() {
+        let mut reg = RegistryBuilder::new()
+            .summary(pkg("foo", "1.0.1", vec![]))
+            .summary(pkg("foo", "1.1.0", vec![]))
+            .summary(pkg("foo", "1.0.0", vec![]));
+
+        let res = resolve_root(vec![dep("foo", "1.0.1")], &mut reg, true);
+        assert_eq!(res, vec!["foo 1.0.1", "root 1.0.0"]);
+    }
+
+    #[test]
+    fn minimal_versions_applies_to_transitive_deps() {
+        let mut reg = RegistryBuilder::new()
+            .summary(pkg("foo", "1.0.0", vec![dep("bar", "0.2.3")]))
+            .summary(pkg("foo", "1.2.0", vec![dep("bar", "0.2.5")]))
+            .summary(pkg("bar", "0.2.3", vec![]))
+            .summary(pkg("bar", "0.2.5", vec![]))
+            .summary(pkg("bar", "0.2.9", vec![]));
+
+        let res = resolve_root(vec![dep("foo", "1")], &mut reg, true);
+        assert_eq!(res, vec!["bar 0.2.3", "foo 1.0.0", "root 1.0.0"]);
+    }
+
+    #[test]
+    fn minimal_versions_backtracks_upwards() {
+        // The lowest `foo` needs a `bar` that conflicts with the root's
+        // requirement, so the next lowest `foo` has to be used instead.
+        let mut reg = RegistryBuilder::new()
+            .summary(pkg("foo", "1.0.0", vec![dep("bar", "=1.0.0")]))
+            .summary(pkg("foo", "1.0.1", vec![dep("bar", "1")]))
+            .summary(pkg("foo", "1.0.2", vec![dep("bar", "1")]))
+            .summary(pkg("bar", "1.0.0", vec![]))
+            .summary(pkg("bar", "1.0.1", vec![]))
+            .summary(pkg("bar", "1.0.2", vec![]));
+
+        let res = resolve_root(vec![dep("foo", "1"), dep("bar", "1.0.1")],
+                               &mut reg, true);
+        assert_eq!(res, vec!["bar 1.0.1", "foo 1.0.1", "root 1.0.0"]);
+    }
+
+    #[test]
+    fn prefers_versions_supporting_rust_version() {
+        let mut reg = RegistryBuilder::new()
+            .summary(pkg("foo", "1.0.0", vec![])
+                         .set_rust_version(Some("1.10".to_string())))
+            .summary(pkg("foo", "1.1.0", vec![])
+                         .set_rust_version(Some("1.20.1".to_string())))
+            .summary(pkg("foo", "1.2.0", vec![])
+                         .set_rust_version(Some("1.30".to_string())));
+
+        let res = resolve_rust_version(vec![dep("foo", "1")], &mut reg,
+                                       "1.20");
+        let foo = res.iter().find(|id| id.name() == "foo").unwrap();
+        assert_eq!(foo.version().to_string(), "1.0.0");
+        let &(ref newest, ref rust_version) = &res.held_back()[foo];
+        assert_eq!(newest.version().to_string(), "1.2.0");
+        assert_eq!(rust_version, "1.30");
+    }
+
+    #[test]
+    fn falls_back_to_versions_not_supporting_rust_version() {
+        let mut reg = RegistryBuilder::new()
+            .summary(pkg("foo", "1.0.0", vec![])
+                         .set_rust_version(Some("1.30".to_string())))
+            .summary(pkg("foo", "1.1.0", vec![])
+                         .set_rust_version(Some("1.40".to_string())));
+
+        let res = resolve_rust_version(vec![dep("foo", "1")], &mut reg,
+                                       "1.20");
+        let foo = res.iter().find(|id| id.name() == "foo").unwrap();
+        assert_eq!(foo.version().to_string(), "1.1.0");
+        assert!(res.held_back().is_empty());
+    }
+}
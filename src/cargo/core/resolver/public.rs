@@ -0,0 +1,155 @@
+//! Tracking of which packages can see each other through public dependencies.
+//!
+//! A dependency marked `public = true` is part of the API of the package
+//! depending on it, so anything that can see that package can see the
+//! dependency as well. Types from two versions of the same crate never unify,
+//! which is why among the crates a package sees through public dependencies
+//! only one version of each may be activated. Crates which a package merely
+//! depends on directly may still be present in several versions, as is the
+//! case with renamed dependencies.
+
+use std::collections::{HashMap, HashSet};
+
+use core::PackageId;
+
+#[derive(Clone)]
+pub struct PublicDependency {
+    // For each activated package, the packages it can see.
+    visible: HashMap<PackageId, Vec<Visible>>,
+    // For each activated package, the packages depending on it and whether
+    // they do so publicly.
+    parents: HashMap<PackageId, Vec<(PackageId, bool)>>,
+}
+
+#[derive(Clone)]
+struct Visible {
+    id: PackageId,
+    // Whether the package seeing `id` also re-exports it to its dependents.
+    exported: bool,
+    // Whether `id` is seen through a public dependency of some dependency,
+    // rather than only as a direct dependency.
+    indirect: bool,
+}
+
+impl PublicDependency {
+    pub fn new() -> PublicDependency {
+        PublicDependency {
+            visible: HashMap::new(),
+            parents: HashMap::new(),
+        }
+    }
+
+    /// Returns the package which would see two different versions of a crate
+    /// if `parent` depended on `candidate`, along with the version it already
+    /// sees.
+    pub fn conflict(&self,
+                    parent: &PackageId,
+                    candidate: &PackageId,
+                    public: bool) -> Option<(PackageId, PackageId)> {
+        for export in self.exports(candidate) {
+            let indirect = export != *candidate;
+            let mut stack = vec![(parent, public, indirect)];
+            let mut seen = HashSet::new();
+            while let Some((pkg, public, indirect)) = stack.pop() {
+                if !seen.insert((pkg, public, indirect)) {
+                    continue
+                }
+                let other = self.visible_to(pkg).iter().find(|v| {
+                    same_crate(&v.id, &export) && v.id != export &&
+                        (indirect || v.indirect)
+                });
+                if let Some(other) = other {
+                    return Some((pkg.clone(), other.id.clone()))
+                }
+                // Only the public dependencies of `pkg` are seen by its own
+                // dependents.
+                if public {
+                    for &(ref grand, grand_public) in self.parents_of(pkg) {
+                        stack.push((grand, grand_public, true));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Records that `parent` depends on `candidate`, making `candidate` and
+    /// everything it re-exports visible to `parent`, and further up through
+    /// public dependencies.
+    pub fn add_edge(&mut self,
+                    parent: &PackageId,
+                    candidate: &PackageId,
+                    public: bool) {
+        {
+            let parents = self.parents.entry(candidate.clone())
+                                      .or_insert(Vec::new());
+            let edge = (parent.clone(), public);
+            if parents.contains(&edge) {
+                return
+            }
+            parents.push(edge);
+        }
+
+        for export in self.exports(candidate) {
+            let indirect = export != *candidate;
+            let mut stack = vec![(parent.clone(), public, indirect)];
+            while let Some((pkg, public, indirect)) = stack.pop() {
+                // Dependents of `pkg` only need to learn about `export` the
+                // first time `pkg` re-exports it.
+                if !self.see(&pkg, &export, public, indirect) || !public {
+                    continue
+                }
+                for &(ref grand, grand_public) in self.parents_of(&pkg) {
+                    stack.push((grand.clone(), grand_public, true));
+                }
+            }
+        }
+    }
+
+    // Marks `id` as visible to `pkg`, returning whether `pkg` newly
+    // re-exports it.
+    fn see(&mut self,
+           pkg: &PackageId,
+           id: &PackageId,
+           exported: bool,
+           indirect: bool) -> bool {
+        if pkg == id {
+            return false
+        }
+        let visible = self.visible.entry(pkg.clone()).or_insert(Vec::new());
+        if let Some(prev) = visible.iter_mut().find(|v| v.id == *id) {
+            let newly_exported = exported && !prev.exported;
+            prev.exported = prev.exported || exported;
+            prev.indirect = prev.indirect && indirect;
+            return newly_exported
+        }
+        visible.push(Visible {
+            id: id.clone(),
+            exported: exported,
+            indirect: indirect,
+        });
+        exported
+    }
+
+    // The packages which become visible to anything depending on `id`:
+    // `id` itself and everything it re-exports.
+    fn exports(&self, id: &PackageId) -> Vec<PackageId> {
+        let mut ret = vec![id.clone()];
+        ret.extend(self.visible_to(id).iter().filter(|v| v.exported).map(|v| {
+            v.id.clone()
+        }));
+        ret
+    }
+
+    fn visible_to(&self, id: &PackageId) -> &[Visible] {
+        self.visible.get(id).map(|v| &v[..]).unwrap_or(&[])
+    }
+
+    fn parents_of(&self, id: &PackageId) -> &[(PackageId, bool)] {
+        self.parents.get(id).map(|v| &v[..]).unwrap_or(&[])
+    }
+}
+
+fn same_crate(a: &PackageId, b: &PackageId) -> bool {
+    a.name() == b.name() && a.source_id() == b.source_id()
+}
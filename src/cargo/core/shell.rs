@@ -287,7 +287,7 @@ impl Shell {
         Ok(())
     }
 
-    fn colored(&self) -> bool {
+    pub fn colored(&self) -> bool {
         self.config.tty && Auto == self.config.color_config
             || Always == self.config.color_config
     }
@@ -53,6 +53,19 @@ pub trait Source: Registry {
     fn verify(&self, _pkg: &PackageId) -> CargoResult<()> {
         Ok(())
     }
+
+    /// Returns whether the package has been yanked from this source, which is
+    /// only ever the case for registries.
+    fn yanked(&mut self, _pkg: &PackageId) -> CargoResult<Option<Yanked>> {
+        Ok(None)
+    }
+}
+
+/// Information about a yanked package.
+#[derive(Clone, Debug)]
+pub struct Yanked {
+    /// Why the package was yanked, if the source says so.
+    pub reason: Option<String>,
 }
 
 impl<'a, T: Source + ?Sized + 'a> Source for Box<T> {
@@ -75,6 +88,10 @@ impl<'a, T: Source + ?Sized + 'a> Source for Box<T> {
     fn verify(&self, pkg: &PackageId) -> CargoResult<()> {
         (**self).verify(pkg)
     }
+
+    fn yanked(&mut self, pkg: &PackageId) -> CargoResult<Option<Yanked>> {
+        (**self).yanked(pkg)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -232,7 +249,7 @@ impl SourceId {
     /// This is the main cargo registry by default, but it can be overridden in
     /// a `.cargo/config`.
     pub fn crates_io(config: &Config) -> CargoResult<SourceId> {
-        let cfg = ops::registry_configuration(config)?;
+        let cfg = ops::registry_configuration(config, None)?;
         let url = if let Some(ref index) = cfg.index {
             static WARNED: AtomicBool = ATOMIC_BOOL_INIT;
             if !WARNED.swap(true, SeqCst) {
@@ -249,6 +266,13 @@ impl SourceId {
         Ok(SourceId::for_registry(&url))
     }
 
+    /// Returns the `SourceId` of the alternate registry `key`, as configured
+    /// in the `[registries]` table of `.cargo/config`.
+    pub fn alt_registry(config: &Config, key: &str) -> CargoResult<SourceId> {
+        let url = config.get_registry_index(key)?;
+        Ok(SourceId::for_registry(&url))
+    }
+
     pub fn url(&self) -> &Url {
         &self.inner.url
     }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 
 use semver::Version;
@@ -16,31 +16,76 @@ pub struct Summary {
     dependencies: Vec<Dependency>,
     features: HashMap<String, Vec<String>>,
     checksum: Option<String>,
+    links: Option<String>,
+    rust_version: Option<String>,
 }
 
 impl Summary {
     pub fn new(pkg_id: PackageId,
                dependencies: Vec<Dependency>,
                features: HashMap<String, Vec<String>>) -> CargoResult<Summary> {
+        // Optional dependencies referred to with `dep:` don't get an implicit
+        // feature of the same name, so they're free to share a name with an
+        // explicit feature.
+        let namespaced = features.values().flat_map(|list| list).filter(|f| {
+            f.starts_with("dep:")
+        }).map(|f| f[4..].to_string()).collect::<HashSet<_>>();
         for dep in dependencies.iter() {
-            if features.get(dep.name()).is_some() {
+            if features.get(dep.name_in_toml()).is_some() &&
+               !namespaced.contains(dep.name_in_toml()) {
                 bail!("Features and dependencies cannot have the \
-                       same name: `{}`", dep.name())
+                       same name: `{}`", dep.name_in_toml())
             }
             if dep.is_optional() && !dep.is_transitive() {
                 bail!("Dev-dependencies are not allowed to be optional: `{}`",
-                      dep.name())
+                      dep.name_in_toml())
             }
         }
         for (feature, list) in features.iter() {
-            for dep in list.iter() {
-                let mut parts = dep.splitn(2, '/');
+            if feature.starts_with("dep:") {
+                bail!("Feature names may not start with `dep:`, but found \
+                       the feature `{}`", feature)
+            }
+            for value in list.iter() {
+                if value.starts_with("dep:") {
+                    let dep = &value[4..];
+                    match dependencies.iter().find(|d| d.name_in_toml() == dep) {
+                        Some(d) if d.is_optional() => continue,
+                        Some(_) => {
+                            bail!("Feature `{}` includes `{}`, but `{}` is \
+                                   not an optional dependency.\nConsider \
+                                   adding `optional = true` to the \
+                                   dependency", feature, value, dep)
+                        }
+                        None => {
+                            bail!("Feature `{}` includes `{}`, but `{}` is \
+                                   not a dependency", feature, value, dep)
+                        }
+                    }
+                }
+                let mut parts = value.splitn(2, '/');
                 let dep = parts.next().unwrap();
                 let is_reexport = parts.next().is_some();
+                let is_weak = is_reexport && dep.ends_with('?');
+                let dep = if is_weak { &dep[..dep.len() - 1] } else { dep };
                 if !is_reexport && features.get(dep).is_some() { continue }
-                match dependencies.iter().find(|d| d.name() == dep) {
+                match dependencies.iter().find(|d| d.name_in_toml() == dep) {
                     Some(d) => {
-                        if d.is_optional() || is_reexport { continue }
+                        if is_weak && !d.is_optional() {
+                            bail!("Feature `{}` includes `{}` with a `?`, but \
+                                   `{}` is not an optional dependency.\nA \
+                                   non-optional dependency is always \
+                                   enabled, so `{}/...` can be used instead",
+                                   feature, value, dep, dep)
+                        }
+                        if is_reexport { continue }
+                        if d.is_optional() && namespaced.contains(dep) {
+                            bail!("Feature `{}` includes `{}`, but `{}` is an \
+                                   optional dependency without an implicit \
+                                   feature.\nUse `dep:{}` to enable the \
+                                   dependency", feature, dep, dep, dep)
+                        }
+                        if d.is_optional() { continue }
                         bail!("Feature `{}` depends on `{}` which is not an \
                                optional dependency.\nConsider adding \
                                `optional = true` to the dependency",
@@ -62,6 +107,8 @@ impl Summary {
             dependencies: dependencies,
             features: features,
             checksum: None,
+            links: None,
+            rust_version: None,
         })
     }
 
@@ -71,9 +118,26 @@ impl Summary {
     pub fn source_id(&self) -> &SourceId { self.package_id.source_id() }
     pub fn dependencies(&self) -> &[Dependency] { &self.dependencies }
     pub fn features(&self) -> &HashMap<String, Vec<String>> { &self.features }
+
+    /// Returns whether the optional dependency `dep` can be enabled through a
+    /// feature of the same name, which is no longer the case once any feature
+    /// refers to it with `dep:`.
+    pub fn has_implicit_feature(&self, dep: &str) -> bool {
+        !self.features.values().flat_map(|list| list).any(|f| {
+            f.starts_with("dep:") && &f[4..] == dep
+        })
+    }
     pub fn checksum(&self) -> Option<&str> {
         self.checksum.as_ref().map(|s| &s[..])
     }
+    pub fn links(&self) -> Option<&str> {
+        self.links.as_ref().map(|s| &s[..])
+    }
+
+    /// The oldest rustc the package supports, as declared by `rust-version`.
+    pub fn rust_version(&self) -> Option<&str> {
+        self.rust_version.as_ref().map(|s| &s[..])
+    }
 
     pub fn override_id(mut self, id: PackageId) -> Summary {
         self.package_id = id;
@@ -85,6 +149,16 @@ impl Summary {
         self
     }
 
+    pub fn set_links(mut self, links: Option<String>) -> Summary {
+        self.links = links;
+        self
+    }
+
+    pub fn set_rust_version(mut self, rust_version: Option<String>) -> Summary {
+        self.rust_version = rust_version;
+        self
+    }
+
     pub fn map_dependencies<F>(mut self, f: F) -> Summary
                                where F: FnMut(Dependency) -> Dependency {
         let deps = mem::replace(&mut self.dependencies, Vec::new());
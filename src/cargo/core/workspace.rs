@@ -3,11 +3,16 @@ use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::slice;
 
+use toml;
+use url::Url;
+
 use core::{Package, VirtualManifest, EitherManifest, SourceId};
 use core::{PackageIdSpec, Dependency, Profile, Profiles};
+use core::resolver::ResolveBehavior;
 use ops;
 use util::{Config, CargoResult, Filesystem, human};
 use util::paths;
+use util::toml::{self as cargo_toml, InheritableFields};
 
 /// The core abstraction in Cargo for working with a workspace of crates.
 ///
@@ -56,6 +61,9 @@ pub struct Workspace<'cfg> {
 struct Packages<'cfg> {
     config: &'cfg Config,
     packages: HashMap<PathBuf, MaybePackage>,
+    // Fields of the workspace root which members inherit with
+    // `workspace = true`, once the root has been found.
+    inheritable: Option<InheritableFields>,
 }
 
 enum MaybePackage {
@@ -71,6 +79,7 @@ pub enum WorkspaceConfig {
     Root {
         members: Option<Vec<String>>,
         exclude: Vec<String>,
+        inheritable: InheritableFields,
     },
 
     /// Indicates that `[workspace]` was present and the `root` field is the
@@ -102,6 +111,7 @@ impl<'cfg> Workspace<'cfg> {
             packages: Packages {
                 config: config,
                 packages: HashMap::new(),
+                inheritable: None,
             },
             root_manifest: None,
             target_dir: target_dir,
@@ -110,6 +120,7 @@ impl<'cfg> Workspace<'cfg> {
             require_optional_deps: true,
         };
         ws.root_manifest = ws.find_root(manifest_path)?;
+        ws.load_inheritable()?;
         ws.find_members()?;
         ws.validate()?;
         Ok(ws)
@@ -132,6 +143,7 @@ impl<'cfg> Workspace<'cfg> {
             packages: Packages {
                 config: config,
                 packages: HashMap::new(),
+                inheritable: None,
             },
             root_manifest: None,
             target_dir: None,
@@ -217,6 +229,42 @@ impl<'cfg> Workspace<'cfg> {
         }
     }
 
+    /// Returns the root [patch] section of this workspace.
+    ///
+    /// This may be from a virtual crate or an actual crate.
+    pub fn root_patch(&self) -> &HashMap<Url, Vec<Dependency>> {
+        let path = match self.root_manifest {
+            Some(ref p) => p,
+            None => &self.current_manifest,
+        };
+        match *self.packages.get(path) {
+            MaybePackage::Package(ref p) => p.manifest().patch(),
+            MaybePackage::Virtual(ref v) => v.patch(),
+        }
+    }
+
+    /// Returns the feature resolver selected with `resolver` in the root
+    /// manifest, defaulting to `ResolveBehavior::V1`.
+    pub fn resolve_behavior(&self) -> ResolveBehavior {
+        let path = match self.root_manifest {
+            Some(ref p) => p,
+            None => &self.current_manifest,
+        };
+        let behavior = match *self.packages.get(path) {
+            MaybePackage::Package(ref p) => p.manifest().resolve_behavior(),
+            MaybePackage::Virtual(ref v) => v.resolve_behavior(),
+        };
+        behavior.unwrap_or(ResolveBehavior::V1)
+    }
+
+    /// Returns the oldest `rust-version` declared by any member of the
+    /// workspace, which the resolver prefers dependencies to also support.
+    pub fn rust_version(&self) -> Option<&str> {
+        self.members().filter_map(|p| p.manifest().rust_version()).min_by_key(|v| {
+            v.split('.').map(|p| p.parse::<u64>().unwrap_or(0)).collect::<Vec<_>>()
+        })
+    }
+
     /// Returns an iterator over all packages in this workspace
     pub fn members<'a>(&'a self) -> Members<'a, 'cfg> {
         Members {
@@ -252,26 +300,23 @@ impl<'cfg> Workspace<'cfg> {
             return Ok(paths::normalize_path(&path))
         };
 
-        {
-            let current = self.packages.load(&manifest_path)?;
-            match *current.workspace_config() {
-                WorkspaceConfig::Root { .. } => {
-                    debug!("find_root - is root {}", manifest_path.display());
-                    return Ok(Some(manifest_path.to_path_buf()))
-                }
-                WorkspaceConfig::Member { root: Some(ref path_to_root) } => {
-                    return Ok(Some(read_root_pointer(manifest_path, path_to_root)?))
-                }
-                WorkspaceConfig::Member { root: None } => {}
+        match self.packages.workspace_config(manifest_path)? {
+            WorkspaceConfig::Root { .. } => {
+                debug!("find_root - is root {}", manifest_path.display());
+                return Ok(Some(manifest_path.to_path_buf()))
             }
+            WorkspaceConfig::Member { root: Some(ref path_to_root) } => {
+                return Ok(Some(read_root_pointer(manifest_path, path_to_root)?))
+            }
+            WorkspaceConfig::Member { root: None } => {}
         }
 
         for path in paths::ancestors(manifest_path).skip(2) {
             let manifest = path.join("Cargo.toml");
             debug!("find_root - trying {}", manifest.display());
             if manifest.exists() {
-                match *self.packages.load(&manifest)?.workspace_config() {
-                    WorkspaceConfig::Root { ref exclude, ref members } => {
+                match self.packages.workspace_config(&manifest)? {
+                    WorkspaceConfig::Root { ref exclude, ref members, .. } => {
                         debug!("find_root - found a root checking exclusion");
                         if !is_excluded(members, exclude, path, manifest_path) {
                             debug!("find_root - found!");
@@ -290,6 +335,33 @@ impl<'cfg> Workspace<'cfg> {
         Ok(None)
     }
 
+    /// Loads the root of the workspace, once it has been located, so that
+    /// members can inherit fields from it while they're loaded.
+    fn load_inheritable(&mut self) -> CargoResult<()> {
+        let root_manifest = match self.root_manifest {
+            Some(ref path) => path.clone(),
+            None => return Ok(()),
+        };
+        if !root_manifest.exists() {
+            return Ok(())
+        }
+        let inheritable = match *self.packages.load(&root_manifest)?
+                                              .workspace_config() {
+            WorkspaceConfig::Root { ref inheritable, .. } => {
+                Some(inheritable.clone())
+            }
+            WorkspaceConfig::Member { .. } => None,
+        };
+        self.packages.inheritable = inheritable;
+        Ok(())
+    }
+
+    /// Returns the fields of the workspace root which members may inherit,
+    /// if this workspace has a root.
+    pub fn inheritable_fields(&self) -> Option<&InheritableFields> {
+        self.packages.inheritable.as_ref()
+    }
+
     /// After the root of a workspace has been located, probes for all members
     /// of a workspace.
     ///
@@ -302,6 +374,7 @@ impl<'cfg> Workspace<'cfg> {
             Some(ref path) => path.clone(),
             None => {
                 debug!("find_members - only me as a member");
+                self.packages.load(&self.current_manifest)?;
                 self.members.push(self.current_manifest.clone());
                 return Ok(())
             }
@@ -344,7 +417,7 @@ impl<'cfg> Workspace<'cfg> {
 
         let root = root_manifest.parent().unwrap();
         match *self.packages.load(root_manifest)?.workspace_config() {
-            WorkspaceConfig::Root { ref members, ref exclude } => {
+            WorkspaceConfig::Root { ref members, ref exclude, .. } => {
                 if is_excluded(members, exclude, root, &manifest_path) {
                     return Ok(())
                 }
@@ -506,6 +579,9 @@ impl<'cfg> Workspace<'cfg> {
                 custom_build: Profile::default_custom_build(),
                 check: Profile::default_check(),
                 doctest: Profile::default_doctest(),
+                package_overrides: Vec::new(),
+                build_override: None,
+                named: HashMap::new(),
             };
 
             for pkg in self.members().filter(|p| p.manifest_path() != root_manifest) {
@@ -527,6 +603,19 @@ impl<'cfg> Workspace<'cfg> {
     }
 }
 
+impl WorkspaceConfig {
+    /// Returns whether this is the configuration of a workspace root in
+    /// `root_path` which includes the package at `manifest_path`.
+    pub fn is_root_of(&self, root_path: &Path, manifest_path: &Path) -> bool {
+        match *self {
+            WorkspaceConfig::Root { ref members, ref exclude, .. } => {
+                !is_excluded(members, exclude, root_path, manifest_path)
+            }
+            WorkspaceConfig::Member { .. } => false,
+        }
+    }
+}
+
 fn is_excluded(members: &Option<Vec<String>>,
                exclude: &[String],
                root_path: &Path,
@@ -553,6 +642,25 @@ impl<'cfg> Packages<'cfg> {
         &self.packages[manifest_path.parent().unwrap()]
     }
 
+    /// Returns the workspace configuration of the manifest at
+    /// `manifest_path`. Manifests which haven't been loaded yet are only
+    /// parsed far enough for this, as they may inherit fields from a
+    /// workspace root that is still being looked for.
+    fn workspace_config(&mut self, manifest_path: &Path)
+                        -> CargoResult<WorkspaceConfig> {
+        let key = manifest_path.parent().unwrap();
+        if let Some(package) = self.packages.get(key) {
+            return Ok(package.workspace_config().clone())
+        }
+        // Invalid manifests are loaded in full to report errors about them
+        // the same way as everywhere else.
+        let contents = paths::read(manifest_path)?;
+        match contents.parse::<toml::Value>() {
+            Ok(toml) => Ok(cargo_toml::to_workspace_config(&toml, key)),
+            Err(..) => Ok(self.load(manifest_path)?.workspace_config().clone()),
+        }
+    }
+
     fn load(&mut self, manifest_path: &Path) -> CargoResult<&MaybePackage> {
         let key = manifest_path.parent().unwrap();
         match self.packages.entry(key.to_path_buf()) {
@@ -560,6 +668,7 @@ impl<'cfg> Packages<'cfg> {
             Entry::Vacant(v) => {
                 let source_id = SourceId::for_path(key)?;
                 let pair = ops::read_manifest(&manifest_path, &source_id,
+                                              self.inheritable.as_ref(),
                                               self.config)?;
                 let (manifest, _nested_paths) = pair;
                 Ok(v.insert(match manifest {
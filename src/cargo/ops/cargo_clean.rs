@@ -3,6 +3,7 @@ use std::fs;
 use std::path::Path;
 
 use core::{Profiles, Workspace};
+use core::resolver::FeaturesFor;
 use util::{CargoResult, human, ChainError, Config};
 use ops::{self, Context, BuildConfig, Kind, Unit};
 
@@ -48,21 +49,28 @@ pub fn clean(ws: &Workspace, opts: &CleanOptions) -> CargoResult<()> {
 
         // Generate all relevant `Unit` targets for this package
         for target in pkg.targets() {
-            for kind in [Kind::Host, Kind::Target].iter() {
+            for &for_host in [false, true].iter() {
                 let Profiles {
                     ref release, ref dev, ref test, ref bench, ref doc,
                     ref custom_build, ref test_deps, ref bench_deps, ref check,
-                    ref doctest,
-                } = *profiles;
+                    ref doctest, ..
+                } = *profiles.for_package(&pkgid, for_host);
                 let profiles = [release, dev, test, bench, doc, custom_build,
                                 test_deps, bench_deps, check, doctest];
-                for profile in profiles.iter() {
-                    units.push(Unit {
-                        pkg: &pkg,
-                        target: target,
-                        profile: profile,
-                        kind: *kind,
-                    });
+                for kind in [Kind::Host, Kind::Target].iter() {
+                    for profile in profiles.iter() {
+                        for features_for in [FeaturesFor::Target,
+                                             FeaturesFor::Host].iter() {
+                            units.push(Unit {
+                                pkg: &pkg,
+                                target: target,
+                                profile: profile,
+                                kind: *kind,
+                                features_for: *features_for,
+                                for_host: for_host,
+                            });
+                        }
+                    }
                 }
             }
         }
@@ -29,10 +29,11 @@ use std::sync::Arc;
 
 use core::{Source, Package, Target};
 use core::{Profile, TargetKind, Profiles, Workspace, PackageId, PackageIdSpec};
-use core::resolver::Resolve;
+use core::resolver::{Resolve, FeaturesFor};
 use ops::{self, BuildOutput, Executor, DefaultExecutor};
 use util::config::Config;
-use util::{CargoResult, profile};
+use util::{CargoResult, CargoError, profile};
+use util::machine_message;
 
 /// Contains information about how a package should be compiled.
 pub struct CompileOptions<'a> {
@@ -54,6 +55,13 @@ pub struct CompileOptions<'a> {
     pub filter: CompileFilter<'a>,
     /// Whether this is a release build or not
     pub release: bool,
+    /// The custom profile to build with, as defined by `[profile.<name>]`
+    pub profile: Option<&'a str>,
+    /// Whether to build packages even if they require a newer rustc than the
+    /// one in use, as declared by `rust-version`
+    pub ignore_rust_version: bool,
+    /// Whether to print the build plan as JSON instead of building anything
+    pub build_plan: bool,
     /// Mode for this compile.
     pub mode: CompileMode,
     /// `--error_format` flag for the compiler.
@@ -78,6 +86,9 @@ impl<'a> CompileOptions<'a> {
             spec: ops::Packages::Packages(&[]),
             mode: mode,
             release: false,
+            profile: None,
+            ignore_rust_version: false,
+            build_plan: false,
             filter: ops::CompileFilter::new(false, &[], &[], &[], &[]),
             message_format: MessageFormat::Human,
             target_rustdoc_args: None,
@@ -156,6 +167,22 @@ pub fn compile_with_exec<'a>(ws: &Workspace<'a>,
     compile_ws(ws, None, options, exec)
 }
 
+/// Emits the conflict tree of a failed resolution, if `error` or any of its
+/// causes carries one, as a machine-readable message.
+fn emit_conflict_tree(error: &CargoError) {
+    let mut cur = Some(error);
+    while let Some(error) = cur {
+        if let Some(tree) = error.conflict_tree() {
+            machine_message::emit(machine_message::ResolveConflict {
+                message: tree.to_string(),
+                conflict: tree,
+            });
+            return
+        }
+        cur = error.cargo_cause();
+    }
+}
+
 pub fn compile_ws<'a>(ws: &Workspace<'a>,
                       source: Option<Box<Source + 'a>>,
                       options: &CompileOptions<'a>,
@@ -163,7 +190,8 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
                       -> CargoResult<ops::Compilation<'a>> {
     let CompileOptions { config, jobs, target, spec, features,
                          all_features, no_default_features,
-                         release, mode, message_format,
+                         release, profile, ignore_rust_version,
+                         build_plan, mode, message_format,
                          ref filter,
                          ref target_rustdoc_args,
                          ref target_rustc_args } = *options;
@@ -174,7 +202,17 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
         bail!("jobs must be at least 1")
     }
 
-    let profiles = ws.profiles();
+    // `dev` and `release` are the built-in profiles, everything else must be
+    // a custom profile defined in the manifest.
+    let (release, profile) = match profile {
+        Some("dev") => (false, None),
+        Some("release") => (true, None),
+        profile => (release, profile),
+    };
+    let profiles = match profile {
+        Some(name) => ws.profiles().named(name)?,
+        None => ws.profiles(),
+    };
 
     let specs = spec.into_package_id_specs(ws)?;
     let resolve = ops::resolve_ws_precisely(ws,
@@ -182,8 +220,20 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
                                             features,
                                             all_features,
                                             no_default_features,
-                                            &specs)?;
-    let (packages, resolve_with_overrides) = resolve;
+                                            &specs);
+    let (packages, resolve_with_overrides) = match resolve {
+        Ok(resolve) => resolve,
+        Err(e) => {
+            if message_format == MessageFormat::Json {
+                emit_conflict_tree(&*e);
+            }
+            return Err(e)
+        }
+    };
+
+    // Whether any unit will be built with the dev-dependencies of its
+    // package, which decides the features seen by `required-features`.
+    let dev_units = filter.need_dev_deps(mode);
 
     let mut pkgids = Vec::new();
     if specs.len() > 0 {
@@ -193,7 +243,8 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
     } else {
         let root_package = ws.current()?;
         let all_features = resolve_all_features(&resolve_with_overrides,
-                                                root_package.package_id());
+                                                root_package.package_id(),
+                                                dev_units);
         generate_targets(root_package, profiles, mode, filter, &all_features, release)?;
         pkgids.push(root_package.package_id());
     };
@@ -212,7 +263,8 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
         }
         (Some(args), _) => {
             let all_features = resolve_all_features(&resolve_with_overrides,
-                                                    to_builds[0].package_id());
+                                                    to_builds[0].package_id(),
+                                                    dev_units);
             let targets = generate_targets(to_builds[0], profiles,
                                            mode, filter, &all_features, release)?;
             if targets.len() == 1 {
@@ -228,7 +280,8 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
         }
         (None, Some(args)) => {
             let all_features = resolve_all_features(&resolve_with_overrides,
-                                                    to_builds[0].package_id());
+                                                    to_builds[0].package_id(),
+                                                    dev_units);
             let targets = generate_targets(to_builds[0], profiles,
                                            mode, filter, &all_features, release)?;
             if targets.len() == 1 {
@@ -245,7 +298,8 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
         (None, None) => {
             for &to_build in to_builds.iter() {
                 let all_features = resolve_all_features(&resolve_with_overrides,
-                                                        to_build.package_id());
+                                                        to_build.package_id(),
+                                                        dev_units);
                 let targets = generate_targets(to_build, profiles, mode,
                                                filter, &all_features, release)?;
                 package_targets.push((to_build, targets));
@@ -263,11 +317,15 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
         let _p = profile::start("compiling");
         let mut build_config = scrape_build_config(config, jobs, target)?;
         build_config.release = release;
+        build_config.profile = profile.map(|s| s.to_string());
+        build_config.ignore_rust_version = ignore_rust_version;
+        build_config.build_plan = build_plan;
         build_config.test = mode == CompileMode::Test || mode == CompileMode::Bench;
         build_config.json_messages = message_format == MessageFormat::Json;
         if let CompileMode::Doc { deps } = mode {
             build_config.doc_all = deps;
         }
+        build_config.dev_units = dev_units;
 
         ops::compile_targets(ws,
                              &package_targets,
@@ -284,15 +342,19 @@ pub fn compile_ws<'a>(ws: &Workspace<'a>,
     return Ok(ret);
 
     fn resolve_all_features(resolve_with_overrides: &Resolve,
-                            package_id: &PackageId)
+                            package_id: &PackageId,
+                            dev_units: bool)
                             -> HashSet<String> {
-        let mut features = resolve_with_overrides.features(package_id).clone();
+        let mut features = resolve_with_overrides.features_for(
+            package_id, FeaturesFor::Target, dev_units).clone();
 
         // Include features enabled for use by dependencies so targets can also use them with the
         // required-features field when deciding whether to be built or skipped.
         let deps = resolve_with_overrides.deps(package_id);
         for dep in deps {
-            for feature in resolve_with_overrides.features(dep) {
+            let dep_features = resolve_with_overrides.features_for(
+                dep, FeaturesFor::Target, dev_units);
+            for feature in dep_features {
                 features.insert(dep.name().to_string() + "/" + feature);
             }
         }
@@ -320,6 +382,23 @@ impl<'a> CompileFilter<'a> {
         }
     }
 
+    /// Returns whether building with this filter in `mode` compiles any
+    /// tests, benchmarks or examples, which see dev-dependencies.
+    pub fn need_dev_deps(&self, mode: CompileMode) -> bool {
+        match mode {
+            CompileMode::Test | CompileMode::Bench | CompileMode::Doctest => true,
+            CompileMode::Build | CompileMode::Check | CompileMode::Doc { .. } => {
+                match *self {
+                    CompileFilter::Everything { .. } => false,
+                    CompileFilter::Only { examples, tests, benches, .. } => {
+                        !examples.is_empty() || !tests.is_empty() ||
+                            !benches.is_empty()
+                    }
+                }
+            }
+        }
+    }
+
     pub fn matches(&self, target: &Target) -> bool {
         match *self {
             CompileFilter::Everything { .. } => true,
@@ -348,6 +427,7 @@ fn generate_targets<'a>(pkg: &'a Package,
                         features: &HashSet<String>,
                         release: bool)
                         -> CargoResult<Vec<(&'a Target, &'a Profile)>> {
+    let profiles = profiles.for_package(pkg.package_id(), false);
     let build = if release {&profiles.release} else {&profiles.dev};
     let test = if release {&profiles.bench} else {&profiles.test};
     let profile = match mode {
@@ -516,10 +596,17 @@ fn scrape_build_config(config: &Config,
     let jobs = jobs.or(cfg_jobs).unwrap_or(::num_cpus::get() as u32);
     let cfg_target = config.get_string("build.target")?.map(|s| s.val);
     let target = target.or(cfg_target);
+    // Pipelining is on by default if the compiler supports it, but can be
+    // turned off to debug the order in which units are built.
+    let pipelining = match config.get_bool("build.pipelining")? {
+        Some(v) => v.val,
+        None => config.rustc()?.supports_json_artifacts(),
+    };
     let mut base = ops::BuildConfig {
         host_triple: config.rustc()?.host.clone(),
         requested_target: target.clone(),
         jobs: jobs,
+        pipelining: pipelining,
         ..Default::default()
     };
     base.host = scrape_target_config(config, &base.host_triple)?;
@@ -1,25 +1,101 @@
 use std::collections::{BTreeMap, HashSet};
 
-use core::PackageId;
+use semver::Version;
+
+use core::{Dependency, PackageId, Registry};
 use core::registry::PackageRegistry;
 use core::{Resolve, SourceId, Workspace};
 use core::resolver::Method;
-use ops;
+use ops::{self, MessageFormat};
 use util::config::Config;
-use util::CargoResult;
+use util::{CargoResult, machine_message};
 
 pub struct UpdateOptions<'a> {
     pub config: &'a Config,
     pub to_update: &'a [String],
     pub precise: Option<&'a str>,
     pub aggressive: bool,
+    /// Only move packages locked to a yanked version, each to the nearest
+    /// compatible version which isn't yanked.
+    pub yanked: bool,
+    /// Only report what would change, without writing `Cargo.lock`.
+    pub dry_run: bool,
+    pub message_format: MessageFormat,
+}
+
+/// How a package changed between the previous and the new `Cargo.lock`.
+#[derive(Serialize, Debug, PartialEq)]
+pub enum ChangeKind {
+    #[serde(rename = "added")]
+    Added,
+    #[serde(rename = "removed")]
+    Removed,
+    #[serde(rename = "upgraded")]
+    Upgraded,
+    #[serde(rename = "downgraded")]
+    Downgraded,
+    /// The version stayed the same but the precise revision changed, as
+    /// happens for git dependencies.
+    #[serde(rename = "updated")]
+    Updated,
+}
+
+/// One entry of the difference between two resolves.
+#[derive(Serialize, Debug)]
+pub struct PackageChange {
+    pub kind: ChangeKind,
+    pub name: String,
+    pub source: SourceId,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    #[serde(skip_serializing)]
+    old_id: Option<PackageId>,
+    #[serde(skip_serializing)]
+    new_id: Option<PackageId>,
+}
+
+impl PackageChange {
+    fn new(kind: ChangeKind, old: Option<&PackageId>, new: Option<&PackageId>)
+           -> PackageChange {
+        let id = old.or(new).unwrap();
+        PackageChange {
+            kind: kind,
+            name: id.name().to_string(),
+            source: id.source_id().clone(),
+            old_version: old.map(|id| id.version().to_string()),
+            new_version: new.map(|id| id.version().to_string()),
+            old_id: old.cloned(),
+            new_id: new.cloned(),
+        }
+    }
+
+    fn print(&self, config: &Config) -> CargoResult<()> {
+        let (status, msg) = match (self.old_id.as_ref(), self.new_id.as_ref()) {
+            (Some(old), Some(new)) => {
+                let msg = if old.source_id().is_git() {
+                    format!("{} -> #{}", old,
+                            &new.source_id().precise().unwrap()[..8])
+                } else {
+                    format!("{} -> v{}", old, new.version())
+                };
+                match self.kind {
+                    ChangeKind::Downgraded => ("Downgrading", msg),
+                    _ => ("Updating", msg),
+                }
+            }
+            (Some(old), None) => ("Removing", old.to_string()),
+            (None, Some(new)) => ("Adding", new.to_string()),
+            (None, None) => unreachable!(),
+        };
+        config.shell().status(status, msg)
+    }
 }
 
 pub fn generate_lockfile(ws: &Workspace) -> CargoResult<()> {
     let mut registry = PackageRegistry::new(ws.config())?;
     let resolve = ops::resolve_with_previous(&mut registry, ws,
                                              Method::Everything,
-                                             None, None, &[])?;
+                                             None, None, &[], true)?;
     ops::write_pkg_lockfile(ws, &resolve)?;
     Ok(())
 }
@@ -31,18 +107,34 @@ pub fn update_lockfile(ws: &Workspace, opts: &UpdateOptions)
         bail!("cannot specify both aggressive and precise simultaneously")
     }
 
+    if opts.yanked && (opts.aggressive || opts.precise.is_some() ||
+                       !opts.to_update.is_empty()) {
+        bail!("cannot specify `--yanked` together with packages to update")
+    }
+
     if ws.members().is_empty() {
         bail!("you can't generate a lockfile for an empty workspace.")
     }
 
     let previous_resolve = match ops::load_pkg_lockfile(ws)? {
         Some(resolve) => resolve,
-        None => return generate_lockfile(ws),
+        None if !opts.dry_run => return generate_lockfile(ws),
+        None => {
+            // Without a lock file everything that would be locked is new.
+            let mut registry = PackageRegistry::new(opts.config)?;
+            let resolve = ops::resolve_with_previous(&mut registry, ws,
+                                                     Method::Everything,
+                                                     None, None, &[], true)?;
+            let changes = resolve_changes(None, &resolve);
+            return report_changes(opts, &resolve, &changes)
+        }
     };
     let mut registry = PackageRegistry::new(opts.config)?;
     let mut to_avoid = HashSet::new();
 
-    if opts.to_update.is_empty() {
+    if opts.yanked {
+        avoid_yanked(&mut registry, &previous_resolve, &mut to_avoid)?;
+    } else if opts.to_update.is_empty() {
         to_avoid.extend(previous_resolve.iter());
     } else {
         let mut sources = Vec::new();
@@ -79,32 +171,14 @@ pub fn update_lockfile(ws: &Workspace, opts: &UpdateOptions)
                                                   Method::Everything,
                                                   Some(&previous_resolve),
                                                   Some(&to_avoid),
-                                                  &[])?;
+                                                  &[],
+                                                  true)?;
 
-    // Summarize what is changing for the user.
-    let print_change = |status: &str, msg: String| {
-        opts.config.shell().status(status, msg)
-    };
-    for (removed, added) in compare_dependency_graphs(&previous_resolve, &resolve) {
-        if removed.len() == 1 && added.len() == 1 {
-            let msg = if removed[0].source_id().is_git() {
-                format!("{} -> #{}", removed[0],
-                        &added[0].source_id().precise().unwrap()[..8])
-            } else {
-                format!("{} -> v{}", removed[0], added[0].version())
-            };
-            print_change("Updating", msg)?;
-        } else {
-            for package in removed.iter() {
-                print_change("Removing", format!("{}", package))?;
-            }
-            for package in added.iter() {
-                print_change("Adding", format!("{}", package))?;
-            }
-        }
+    let changes = resolve_changes(Some(&previous_resolve), &resolve);
+    report_changes(opts, &resolve, &changes)?;
+    if !opts.dry_run {
+        ops::write_pkg_lockfile(&ws, &resolve)?;
     }
-
-    ops::write_pkg_lockfile(&ws, &resolve)?;
     return Ok(());
 
     fn fill_with_deps<'a>(resolve: &'a Resolve, dep: &'a PackageId,
@@ -118,66 +192,171 @@ pub fn update_lockfile(ws: &Workspace, opts: &UpdateOptions)
             fill_with_deps(resolve, dep, set, visited);
         }
     }
+}
 
-    fn compare_dependency_graphs<'a>(previous_resolve: &'a Resolve,
-                                     resolve: &'a Resolve) ->
-                                     Vec<(Vec<&'a PackageId>, Vec<&'a PackageId>)> {
-        fn key(dep: &PackageId) -> (&str, &SourceId) {
-            (dep.name(), dep.source_id())
+/// Adds every yanked package of `resolve` to `to_avoid`, and locks each to the
+/// nearest semver-compatible version which isn't yanked, preferring newer
+/// versions. If there's no such version the package is left to the resolver.
+fn avoid_yanked<'a>(registry: &mut PackageRegistry,
+                    resolve: &'a Resolve,
+                    to_avoid: &mut HashSet<&'a PackageId>) -> CargoResult<()> {
+    // Make sure the index of every registry is up to date before looking for
+    // yanked versions.
+    let mut sources = Vec::new();
+    for id in resolve.iter().filter(|id| id.source_id().is_registry()) {
+        let source = id.source_id().clone().with_precise(None);
+        if !sources.contains(&source) {
+            sources.push(source);
         }
+    }
+    registry.add_sources(&sources)?;
 
-        // Removes all package ids in `b` from `a`. Note that this is somewhat
-        // more complicated because the equality for source ids does not take
-        // precise versions into account (e.g. git shas), but we want to take
-        // that into account here.
-        fn vec_subtract<'a>(a: &[&'a PackageId],
-                            b: &[&'a PackageId]) -> Vec<&'a PackageId> {
-            a.iter().filter(|a| {
-                // If this package id is not found in `b`, then it's definitely
-                // in the subtracted set
-                let i = match b.binary_search(a) {
-                    Ok(i) => i,
-                    Err(..) => return true,
-                };
-
-                // If we've found `a` in `b`, then we iterate over all instances
-                // (we know `b` is sorted) and see if they all have different
-                // precise versions. If so, then `a` isn't actually in `b` so
-                // we'll let it through.
-                //
-                // Note that we only check this for non-registry sources,
-                // however, as registries contain enough version information in
-                // the package id to disambiguate
-                if a.source_id().is_registry() {
-                    return false
-                }
-                b[i..].iter().take_while(|b| a == b).all(|b| {
-                    a.source_id().precise() != b.source_id().precise()
-                })
-            }).cloned().collect()
+    for id in resolve.iter() {
+        if registry.yanked(id)?.is_none() {
+            continue
         }
+        to_avoid.insert(id);
 
-        // Map (package name, package source) to (removed versions, added versions).
-        let mut changes = BTreeMap::new();
-        let empty = (Vec::new(), Vec::new());
-        for dep in previous_resolve.iter() {
-            changes.entry(key(dep)).or_insert(empty.clone()).0.push(dep);
-        }
-        for dep in resolve.iter() {
-            changes.entry(key(dep)).or_insert(empty.clone()).1.push(dep);
+        let req = compatible_req(id.version());
+        let source = id.source_id().clone().with_precise(None);
+        let dep = Dependency::parse_no_deprecated(id.name(), Some(&req), &source)?;
+        let candidates = registry.query(&dep)?;
+        let newer = candidates.iter().filter(|s| s.version() > id.version())
+                              .min_by_key(|s| s.version().clone());
+        let older = candidates.iter().filter(|s| s.version() < id.version())
+                              .max_by_key(|s| s.version().clone());
+        if let Some(summary) = newer.or(older) {
+            registry.register_lock(summary.package_id().clone(), Vec::new());
         }
+    }
+    Ok(())
+}
+
+/// A requirement matching exactly the versions which are semver-compatible
+/// with `version`, those with the same left-most nonzero component.
+fn compatible_req(version: &Version) -> String {
+    if version.major != 0 {
+        format!("^{}", version.major)
+    } else if version.minor != 0 {
+        format!("^0.{}", version.minor)
+    } else {
+        format!("=0.0.{}", version.patch)
+    }
+}
 
-        for (_, v) in changes.iter_mut() {
-            let (ref mut old, ref mut new) = *v;
-            old.sort();
-            new.sort();
-            let removed = vec_subtract(old, new);
-            let added = vec_subtract(new, old);
-            *old = removed;
-            *new = added;
+/// Computes the packages added, removed, upgraded or downgraded when going from
+/// `previous` to `resolve`.
+pub fn resolve_changes(previous: Option<&Resolve>, resolve: &Resolve)
+                       -> Vec<PackageChange> {
+    let mut ret = Vec::new();
+    for (removed, added) in compare_dependency_graphs(previous, resolve) {
+        if removed.len() == 1 && added.len() == 1 {
+            let (old, new) = (removed[0], added[0]);
+            let kind = if new.version() > old.version() {
+                ChangeKind::Upgraded
+            } else if new.version() < old.version() {
+                ChangeKind::Downgraded
+            } else {
+                ChangeKind::Updated
+            };
+            ret.push(PackageChange::new(kind, Some(old), Some(new)));
+        } else {
+            for old in removed {
+                ret.push(PackageChange::new(ChangeKind::Removed, Some(old), None));
+            }
+            for new in added {
+                ret.push(PackageChange::new(ChangeKind::Added, None, Some(new)));
+            }
         }
-        debug!("{:#?}", changes);
+    }
+    ret
+}
+
+/// Summarizes what is changing for the user, along with a JSON message if
+/// requested. Packages which were kept at an older version because of the
+/// workspace's `rust-version` are listed as well.
+fn report_changes(opts: &UpdateOptions,
+                  resolve: &Resolve,
+                  changes: &[PackageChange]) -> CargoResult<()> {
+    for change in changes {
+        change.print(opts.config)?;
+    }
+    let mut held_back = resolve.held_back().iter().collect::<Vec<_>>();
+    held_back.sort();
+    for (id, &(ref newest, ref rust_version)) in held_back {
+        opts.config.shell().status("Holding", format!(
+            "{}, as v{} requires rust {}", id, newest.version(), rust_version))?;
+    }
+    if opts.message_format == MessageFormat::Json {
+        machine_message::emit(machine_message::LockfileDiff {
+            changes: changes,
+            dry_run: opts.dry_run,
+        });
+    }
+    if opts.dry_run {
+        opts.config.shell().warn("not updating lockfile due to dry run")?;
+    }
+    Ok(())
+}
+
+fn compare_dependency_graphs<'a>(previous_resolve: Option<&'a Resolve>,
+                                 resolve: &'a Resolve) ->
+                                 Vec<(Vec<&'a PackageId>, Vec<&'a PackageId>)> {
+    fn key(dep: &PackageId) -> (&str, &SourceId) {
+        (dep.name(), dep.source_id())
+    }
+
+    // Removes all package ids in `b` from `a`. Note that this is somewhat
+    // more complicated because the equality for source ids does not take
+    // precise versions into account (e.g. git shas), but we want to take
+    // that into account here.
+    fn vec_subtract<'a>(a: &[&'a PackageId],
+                        b: &[&'a PackageId]) -> Vec<&'a PackageId> {
+        a.iter().filter(|a| {
+            // If this package id is not found in `b`, then it's definitely
+            // in the subtracted set
+            let i = match b.binary_search(a) {
+                Ok(i) => i,
+                Err(..) => return true,
+            };
+
+            // If we've found `a` in `b`, then we iterate over all instances
+            // (we know `b` is sorted) and see if they all have different
+            // precise versions. If so, then `a` isn't actually in `b` so
+            // we'll let it through.
+            //
+            // Note that we only check this for non-registry sources,
+            // however, as registries contain enough version information in
+            // the package id to disambiguate
+            if a.source_id().is_registry() {
+                return false
+            }
+            b[i..].iter().take_while(|b| a == b).all(|b| {
+                a.source_id().precise() != b.source_id().precise()
+            })
+        }).cloned().collect()
+    }
 
-        changes.into_iter().map(|(_, v)| v).collect()
+    // Map (package name, package source) to (removed versions, added versions).
+    let mut changes = BTreeMap::new();
+    let empty = (Vec::new(), Vec::new());
+    for dep in previous_resolve.into_iter().flat_map(|r| r.iter()) {
+        changes.entry(key(dep)).or_insert(empty.clone()).0.push(dep);
     }
+    for dep in resolve.iter() {
+        changes.entry(key(dep)).or_insert(empty.clone()).1.push(dep);
+    }
+
+    for (_, v) in changes.iter_mut() {
+        let (ref mut old, ref mut new) = *v;
+        old.sort();
+        new.sort();
+        let removed = vec_subtract(old, new);
+        let added = vec_subtract(new, old);
+        *old = removed;
+        *new = added;
+    }
+    debug!("{:#?}", changes);
+
+    changes.into_iter().map(|(_, v)| v).collect()
 }
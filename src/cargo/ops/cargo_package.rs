@@ -1,7 +1,7 @@
 use std::fs::{self, File};
 use std::io::SeekFrom;
 use std::io::prelude::*;
-use std::path::{self, Path};
+use std::path::{self, Path, PathBuf};
 use std::sync::Arc;
 
 use flate2::read::GzDecoder;
@@ -38,9 +38,12 @@ pub fn package(ws: &Workspace,
 
     verify_dependencies(&pkg)?;
 
+    let (files, changes) = src.list_files_and_changes(&pkg)?;
+    warn_pattern_changes(&pkg, &changes, config)?;
+
     if opts.list {
         let root = pkg.root();
-        let mut list: Vec<_> = src.list_files(&pkg)?.iter().map(|file| {
+        let mut list: Vec<_> = files.iter().map(|file| {
             util::without_prefix(&file, &root).unwrap().to_path_buf()
         }).collect();
         list.sort();
@@ -51,7 +54,7 @@ pub fn package(ws: &Workspace,
     }
 
     if !opts.allow_dirty {
-        check_not_dirty(&pkg, &src)?;
+        check_not_dirty(&pkg, &files)?;
     }
 
     let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
@@ -67,7 +70,7 @@ pub fn package(ws: &Workspace,
     // it exists.
     config.shell().status("Packaging", pkg.package_id().to_string())?;
     dst.file().set_len(0)?;
-    tar(ws, &src, dst.file(), &filename).chain_error(|| {
+    tar(ws, &files, dst.file(), &filename).chain_error(|| {
         human("failed to prepare local package for uploading")
     })?;
     if opts.verify {
@@ -122,6 +125,32 @@ fn check_metadata(pkg: &Package, config: &Config) -> CargoResult<()> {
     Ok(())
 }
 
+// `include` and `exclude` used to be matched as plain globs rather than like
+// `.gitignore` patterns, so point out the files this changed for.
+fn warn_pattern_changes(pkg: &Package,
+                        changes: &[(PathBuf, bool)],
+                        config: &Config) -> CargoResult<()> {
+    if changes.is_empty() {
+        return Ok(())
+    }
+    let including = !pkg.manifest().include().is_empty();
+    let key = if including {"include"} else {"exclude"};
+    let mut msg = format!("the patterns of `package.{}` are now matched like \
+                           `.gitignore` patterns rather than as plain globs, \
+                           which changes whether these files are packaged:",
+                          key);
+    for &(ref file, packaged) in changes.iter() {
+        let change = match (including, packaged) {
+            (false, true) => "no longer excluded",
+            (false, false) => "now excluded",
+            (true, true) => "now included",
+            (true, false) => "no longer included",
+        };
+        msg.push_str(&format!("\n  {} ({})", file.display(), change));
+    }
+    config.shell().warn(msg)
+}
+
 // check that the package dependencies are safe to deploy.
 fn verify_dependencies(pkg: &Package) -> CargoResult<()> {
     for dep in pkg.dependencies() {
@@ -136,7 +165,7 @@ fn verify_dependencies(pkg: &Package) -> CargoResult<()> {
     Ok(())
 }
 
-fn check_not_dirty(p: &Package, src: &PathSource) -> CargoResult<()> {
+fn check_not_dirty(p: &Package, files: &[PathBuf]) -> CargoResult<()> {
     if let Ok(repo) = git2::Repository::discover(p.root()) {
         if let Some(workdir) = repo.workdir() {
             debug!("found a git repo at {:?}, checking if index present",
@@ -146,7 +175,7 @@ fn check_not_dirty(p: &Package, src: &PathSource) -> CargoResult<()> {
             if let Ok(status) = repo.status_file(path) {
                 if (status & git2::STATUS_IGNORED).is_empty() {
                     debug!("Cargo.toml found in repo, checking if dirty");
-                    return git(p, src, &repo)
+                    return git(p, files, &repo)
                 }
             }
         }
@@ -157,10 +186,10 @@ fn check_not_dirty(p: &Package, src: &PathSource) -> CargoResult<()> {
     return Ok(());
 
     fn git(p: &Package,
-           src: &PathSource,
+           files: &[PathBuf],
            repo: &git2::Repository) -> CargoResult<()> {
         let workdir = repo.workdir().unwrap();
-        let dirty = src.list_files(p)?.iter().filter(|file| {
+        let dirty = files.iter().filter(|file| {
             let relative = file.strip_prefix(workdir).unwrap();
             if let Ok(status) = repo.status_file(relative) {
                 status != git2::STATUS_CURRENT
@@ -182,7 +211,7 @@ fn check_not_dirty(p: &Package, src: &PathSource) -> CargoResult<()> {
 }
 
 fn tar(ws: &Workspace,
-       src: &PathSource,
+       files: &[PathBuf],
        dst: &File,
        filename: &str) -> CargoResult<()> {
     // Prepare the encoder and its header
@@ -195,13 +224,21 @@ fn tar(ws: &Workspace,
     let pkg = ws.current()?;
     let config = ws.config();
     let root = pkg.root();
-    for file in src.list_files(pkg)?.iter() {
+    for file in files.iter() {
         let relative = util::without_prefix(&file, &root).unwrap();
         check_filename(relative)?;
         let relative = relative.to_str().chain_error(|| {
             human(format!("non-utf8 path in source directory: {}",
                           relative.display()))
         })?;
+        // Fields inherited from the workspace are written out literally, as
+        // the packaged crate won't have access to the workspace it came from.
+        let manifest = if relative == "Cargo.toml" {
+            util::toml::to_packaged_manifest(file, ws.inheritable_fields(),
+                                             config)?
+        } else {
+            None
+        };
         let mut file = File::open(file).chain_error(|| {
             human(format!("failed to open for archiving: `{}`", file.display()))
         })?;
@@ -237,9 +274,18 @@ fn tar(ws: &Workspace,
             human(format!("failed to add to archive: `{}`", relative))
         })?;
         header.set_metadata(&metadata);
-        header.set_cksum();
-
-        ar.append(&header, &mut file).chain_error(|| {
+        let res = match manifest {
+            Some(contents) => {
+                header.set_size(contents.len() as u64);
+                header.set_cksum();
+                ar.append(&header, contents.as_bytes())
+            }
+            None => {
+                header.set_cksum();
+                ar.append(&header, &mut file)
+            }
+        };
+        res.chain_error(|| {
             internal(format!("could not archive source file `{}`", relative))
         })?;
     }
@@ -296,6 +342,9 @@ fn run_verify(ws: &Workspace, tar: &File, opts: &PackageOpts) -> CargoResult<()>
         spec: ops::Packages::Packages(&[]),
         filter: ops::CompileFilter::Everything { required_features_filterable: true },
         release: false,
+        profile: None,
+        ignore_rust_version: false,
+        build_plan: false,
         message_format: ops::MessageFormat::Human,
         mode: ops::CompileMode::Build,
         target_rustdoc_args: None,
@@ -6,16 +6,20 @@ use std::path::{Path, PathBuf};
 use core::{Package, SourceId, PackageId, EitherManifest};
 use util::{self, paths, CargoResult, human, Config, ChainError};
 use util::important_paths::find_project_manifest_exact;
-use util::toml::Layout;
+use util::toml::{Layout, InheritableFields};
 
-pub fn read_manifest(path: &Path, source_id: &SourceId, config: &Config)
+pub fn read_manifest(path: &Path,
+                     source_id: &SourceId,
+                     inherit: Option<&InheritableFields>,
+                     config: &Config)
                      -> CargoResult<(EitherManifest, Vec<PathBuf>)> {
     trace!("read_package; path={}; source-id={}", path.display(), source_id);
     let contents = paths::read(path)?;
 
     let layout = Layout::from_project_path(path.parent().unwrap());
     let root = layout.root.clone();
-    util::toml::to_manifest(&contents, source_id, layout, config).chain_error(|| {
+    util::toml::to_manifest(&contents, source_id, layout, inherit,
+                            config).chain_error(|| {
         human(format!("failed to parse manifest at `{}`",
                       root.join("Cargo.toml").display()))
     })
@@ -24,7 +28,7 @@ pub fn read_manifest(path: &Path, source_id: &SourceId, config: &Config)
 pub fn read_package(path: &Path, source_id: &SourceId, config: &Config)
                     -> CargoResult<(Package, Vec<PathBuf>)> {
     trace!("read_package; path={}; source-id={}", path.display(), source_id);
-    let (manifest, nested) = read_manifest(path, source_id, config)?;
+    let (manifest, nested) = read_manifest(path, source_id, None, config)?;
     let manifest = match manifest {
         EitherManifest::Real(manifest) => manifest,
         EitherManifest::Virtual(..) => {
@@ -121,7 +125,8 @@ fn read_nested_packages(path: &Path,
 
     let manifest_path = find_project_manifest_exact(path, "Cargo.toml")?;
 
-    let (manifest, nested) = read_manifest(&manifest_path, source_id, config)?;
+    let (manifest, nested) = read_manifest(&manifest_path, source_id, None,
+                                           config)?;
     let manifest = match manifest {
         EitherManifest::Real(manifest) => manifest,
         EitherManifest::Virtual(..) => return Ok(()),
@@ -22,6 +22,24 @@ pub fn run(ws: &Workspace,
         }
     };
 
+    // Packages with several binaries can name the one to run by default
+    let default_run;
+    let default_options;
+    let options = match (&options.filter, pkg.manifest().default_run()) {
+        (&CompileFilter::Everything { .. }, Some(bin)) => {
+            default_run = [bin.to_string()];
+            default_options = ops::CompileOptions {
+                filter: CompileFilter::Only {
+                    lib: false, bins: &default_run, examples: &[],
+                    tests: &[], benches: &[],
+                },
+                ..*options
+            };
+            &default_options
+        }
+        _ => options,
+    };
+
     let mut bins = pkg.manifest().targets().iter().filter(|a| {
         !a.is_lib() && !a.is_custom_build() && match options.filter {
             CompileFilter::Everything { .. } => a.is_bin(),
@@ -0,0 +1,164 @@
+//! The build plan printed by `cargo build --build-plan`.
+//!
+//! Instead of running anything, every unit of the build is described as the
+//! process which would be invoked for it, listed after everything it depends
+//! on. Running a build script is a node of its own: consumers are expected to
+//! run it themselves, and to pass what it prints on to the invocations
+//! depending on it (`cargo:rustc-link-search` and friends as `-L`, `-l` and
+//! `--cfg` flags, and `cargo:KEY=VALUE` metadata as `DEP_*` variables), as
+//! that is only known once it has run.
+//!
+//! The rustc and rustdoc commands are handed to the `Executor` of the build
+//! through `Executor::plan` before they're recorded.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use core::{PackageId, Profile, Target};
+use util::{CargoResult, ProcessBuilder};
+use util::machine_message::Message;
+
+use super::{Context, Executor, Kind, Unit};
+use super::custom_build;
+
+#[derive(Serialize)]
+pub struct BuildPlan<'a> {
+    invocations: Vec<Invocation<'a>>,
+    #[serde(skip_serializing)]
+    indices: HashMap<Unit<'a>, Option<usize>>,
+    #[serde(skip_serializing)]
+    exec: Arc<Executor>,
+}
+
+#[derive(Serialize)]
+struct Invocation<'a> {
+    package_id: &'a PackageId,
+    target: &'a Target,
+    profile: &'a Profile,
+    kind: &'static str,
+    /// Whether this runs a build script rather than a compiler.
+    run_custom_build: bool,
+    program: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    cwd: Option<PathBuf>,
+    outputs: Vec<PathBuf>,
+    /// Hard links created from the outputs to their final location.
+    links: BTreeMap<PathBuf, PathBuf>,
+    /// Indices of the invocations which have to finish first.
+    deps: Vec<usize>,
+}
+
+impl<'a> BuildPlan<'a> {
+    pub fn new(exec: Arc<Executor>) -> BuildPlan<'a> {
+        BuildPlan {
+            invocations: Vec::new(),
+            indices: HashMap::new(),
+            exec: exec,
+        }
+    }
+
+    /// Adds `unit` and everything it depends on to the plan, returning its
+    /// index if it has an invocation at all.
+    ///
+    /// Build scripts overridden in the configuration never run, so they don't
+    /// show up in the plan and their output is passed on directly.
+    pub fn add<'cfg>(&mut self,
+                     cx: &mut Context<'a, 'cfg>,
+                     unit: &Unit<'a>) -> CargoResult<Option<usize>> {
+        if let Some(&index) = self.indices.get(unit) {
+            return Ok(index)
+        }
+        super::check_rust_version(cx, unit)?;
+
+        let mut deps = Vec::new();
+        for dep in cx.dep_targets(unit)?.iter() {
+            // Like the job queue, tests don't wait on the binaries they run.
+            if unit.target.is_test() && dep.target.is_bin() {
+                continue
+            }
+            if let Some(index) = self.add(cx, dep)? {
+                deps.push(index);
+            }
+        }
+
+        let index = if unit.profile.run_custom_build &&
+                       cx.build_state.has_override(unit) {
+            None
+        } else if unit.profile.doc && unit.profile.test {
+            // Doc tests are run by `cargo test`, not built.
+            None
+        } else {
+            let cmd = if unit.profile.run_custom_build {
+                custom_build::command(cx, unit)?
+            } else if unit.profile.doc {
+                let cmd = super::rustdoc_command(cx, unit)?;
+                self.exec.plan(&cmd, unit.pkg.package_id())?;
+                cmd
+            } else {
+                let mut cmd = super::rustc_command(cx, unit)?;
+                add_overrides(cx, unit, &mut cmd);
+                self.exec.plan(&cmd, unit.pkg.package_id())?;
+                cmd
+            };
+            let mut outputs = Vec::new();
+            let mut links = BTreeMap::new();
+            if !unit.profile.run_custom_build && !unit.profile.doc {
+                for (dst, link_dst, _linkable) in cx.target_filenames(unit)? {
+                    if let Some(link_dst) = link_dst {
+                        links.insert(link_dst, dst.clone());
+                    }
+                    outputs.push(dst);
+                }
+            }
+            self.invocations.push(Invocation {
+                package_id: unit.pkg.package_id(),
+                target: unit.target,
+                profile: unit.profile,
+                kind: match unit.kind {
+                    Kind::Host => "host",
+                    Kind::Target => "target",
+                },
+                run_custom_build: unit.profile.run_custom_build,
+                program: cmd.get_program().to_string_lossy().into_owned(),
+                args: cmd.get_args().iter().map(|a| {
+                    a.to_string_lossy().into_owned()
+                }).collect(),
+                env: cmd.get_envs().iter().filter_map(|(k, v)| {
+                    v.as_ref().map(|v| (k.clone(), v.to_string_lossy().into_owned()))
+                }).collect(),
+                cwd: cmd.get_cwd().map(|p| p.to_path_buf()),
+                outputs: outputs,
+                links: links,
+                deps: deps,
+            });
+            Some(self.invocations.len() - 1)
+        };
+        self.indices.insert(*unit, index);
+        Ok(index)
+    }
+}
+
+impl<'a> Message for BuildPlan<'a> {
+    fn reason(&self) -> &str {
+        "build-plan"
+    }
+}
+
+// The output of overridden build scripts is known ahead of time, so it's
+// passed to rustc just as it would be during a build.
+fn add_overrides(cx: &Context, unit: &Unit, cmd: &mut ProcessBuilder) {
+    let build_scripts = match super::load_build_deps(cx, unit) {
+        Some(build_scripts) => build_scripts,
+        None => return,
+    };
+    let outputs = cx.build_state.outputs.lock().unwrap();
+    let pass_l_flag = super::pass_l_flag(unit);
+    for key in build_scripts.to_link.iter() {
+        if let Some(output) = outputs.get(key) {
+            super::add_native_output(cmd, key, output, pass_l_flag,
+                                     unit.pkg.package_id());
+        }
+    }
+}
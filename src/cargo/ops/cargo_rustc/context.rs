@@ -10,7 +10,8 @@ use std::sync::Arc;
 
 use core::{Package, PackageId, PackageSet, Resolve, Target, Profile};
 use core::{TargetKind, Profiles, Dependency, Workspace};
-use core::dependency::Kind as DepKind;
+use core::dependency::{ArtifactKind, Kind as DepKind};
+use core::resolver::FeaturesFor;
 use util::{self, CargoResult, ChainError, internal, Config, profile, Cfg, CfgExpr, human};
 
 use super::TargetConfig;
@@ -26,6 +27,10 @@ pub struct Unit<'a> {
     pub target: &'a Target,
     pub profile: &'a Profile,
     pub kind: Kind,
+    pub features_for: FeaturesFor,
+    /// Whether this is built for a build script, procedural macro or plugin,
+    /// which selects the `build-override` profile.
+    pub for_host: bool,
 }
 
 pub struct Context<'a, 'cfg: 'a> {
@@ -68,10 +73,14 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                build_config: BuildConfig,
                profiles: &'a Profiles) -> CargoResult<Context<'a, 'cfg>> {
 
-        let dest = if build_config.release { "release" } else { "debug" };
+        let dest = match build_config.profile {
+            Some(ref name) => name.clone(),
+            None if build_config.release => "release".to_string(),
+            None => "debug".to_string(),
+        };
         let host_layout = Layout::new(ws, None, &dest)?;
         let target_layout = match build_config.requested_target.as_ref() {
-            Some(target) => Some(Layout::new(ws, Some(&target), dest)?),
+            Some(target) => Some(Layout::new(ws, Some(&target), &dest)?),
             None => None,
         };
 
@@ -409,7 +418,14 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
 
         // Also mix in enabled features to our metadata. This'll ensure that
         // when changing feature sets each lib is separately cached.
-        self.resolve.features_sorted(unit.pkg.package_id()).hash(&mut hasher);
+        self.unit_features_sorted(unit).hash(&mut hasher);
+
+        // With separately resolved features a package may be built both for
+        // build scripts and for the target with the same `Kind`, so keep
+        // those apart even when their features happen to match.
+        if unit.features_for == FeaturesFor::Host {
+            unit.features_for.hash(&mut hasher);
+        }
 
         // Throw in the profile we're compiling with. This helps caching
         // panic=abort and panic=unwind artifacts, additionally with various
@@ -574,58 +590,26 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             return self.doc_deps(unit);
         }
 
-        let id = unit.pkg.package_id();
-        let deps = self.resolve.deps(id);
-        let mut ret = deps.filter(|dep| {
-            unit.pkg.dependencies().iter().filter(|d| {
-                d.name() == dep.name() && d.version_req().matches(dep.version())
-            }).any(|d| {
-                // If this target is a build command, then we only want build
-                // dependencies, otherwise we want everything *other than* build
-                // dependencies.
-                if unit.target.is_custom_build() != d.is_build() {
-                    return false
-                }
-
-                // If this dependency is *not* a transitive dependency, then it
-                // only applies to test/example targets
-                if !d.is_transitive() && !unit.target.is_test() &&
-                   !unit.target.is_example() && !unit.profile.test {
-                    return false
-                }
-
-                // If this dependency is only available for certain platforms,
-                // make sure we're only enabling it for that platform.
-                if !self.dep_platform_activated(d, unit.kind) {
-                    return false
-                }
-
-                // If the dependency is optional, then we're only activating it
-                // if the corresponding feature was activated
-                if d.is_optional() && !self.resolve.features(id).contains(d.name()) {
-                    return false;
-                }
-
-                // If we've gotten past all that, then this dependency is
-                // actually used!
-                true
-            })
-        }).filter_map(|id| {
-            match self.get_package(id) {
-                Ok(pkg) => {
-                    pkg.targets().iter().find(|t| t.is_lib()).map(|t| {
-                        let unit = Unit {
-                            pkg: pkg,
-                            target: t,
-                            profile: self.lib_or_check_profile(unit, t),
-                            kind: unit.kind.for_target(t),
-                        };
-                        Ok(unit)
-                    })
+        let mut ret = Vec::new();
+        for (pkg, deps) in self.used_deps(unit)? {
+            // Artifact dependencies only build the requested binaries or
+            // cdylib, all others link the library of the package.
+            if deps.iter().any(|d| d.artifact().is_none()) {
+                if let Some(t) = pkg.targets().iter().find(|t| t.is_lib()) {
+                    ret.push(Unit {
+                        pkg: pkg,
+                        target: t,
+                        profile: self.lib_or_check_profile(unit, pkg, t),
+                        kind: unit.kind.for_target(t),
+                        features_for: self.dep_features_for(unit),
+                        for_host: dep_for_host(unit, t),
+                    });
                 }
-                Err(e) => Some(Err(e))
             }
-        }).collect::<CargoResult<Vec<_>>>()?;
+            for d in deps.iter().filter(|d| d.artifact().is_some()) {
+                ret.extend(self.artifact_units(unit, pkg, d)?);
+            }
+        }
 
         // If this target is a build script, then what we've collected so far is
         // all we need. If this isn't a build script, then it depends on the
@@ -653,20 +637,193 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 t.is_bin() &&
                 // Skip binaries with required features that have not been selected.
                 t.required_features().unwrap_or(&no_required_features).iter().all(|f| {
-                    self.resolve.features(id).contains(f)
+                    self.unit_features(unit).contains(f)
                 })
             }).map(|t| {
                 Unit {
                     pkg: unit.pkg,
                     target: t,
-                    profile: self.lib_profile(),
+                    profile: self.package_lib_profile(unit.pkg.package_id(),
+                                                      unit.for_host),
                     kind: unit.kind.for_target(t),
+                    features_for: unit.features_for,
+                    for_host: unit.for_host,
                 }
             }));
         }
         Ok(ret)
     }
 
+    /// Returns the features enabled for `unit`.
+    pub fn unit_features(&self, unit: &Unit) -> &'a HashSet<String> {
+        self.resolve.features_for(unit.pkg.package_id(), unit.features_for,
+                                  self.build_config.dev_units)
+    }
+
+    /// Same as `unit_features`, but sorted for hashing and display.
+    pub fn unit_features_sorted(&self, unit: &Unit) -> Vec<&'a str> {
+        self.resolve.features_sorted_for(unit.pkg.package_id(),
+                                         unit.features_for,
+                                         self.build_config.dev_units)
+    }
+
+    /// Returns the side of the build that the dependencies of `unit` have
+    /// their features resolved for. Dependencies of build scripts are on the
+    /// host side, unless features are unified across the whole graph.
+    fn dep_features_for(&self, unit: &Unit<'a>) -> FeaturesFor {
+        if unit.target.is_custom_build() && self.resolve.has_resolved_features() {
+            FeaturesFor::Host
+        } else {
+            unit.features_for
+        }
+    }
+
+    /// Returns the packages that `unit` depends on, along with the
+    /// dependency declarations in its manifest which select each of them.
+    fn used_deps(&self, unit: &Unit<'a>)
+                 -> CargoResult<Vec<(&'a Package, Vec<&'a Dependency>)>> {
+        let id = unit.pkg.package_id();
+        let mut ret = Vec::new();
+        for dep_id in self.resolve.deps(id) {
+            let deps = unit.pkg.dependencies().iter().filter(|d| {
+                self.resolve.dep_matches_id(d, dep_id)
+            }).filter(|d| {
+                // If this target is a build command, then we only want build
+                // dependencies, otherwise we want everything *other than* build
+                // dependencies.
+                if unit.target.is_custom_build() != d.is_build() {
+                    return false
+                }
+
+                // If this dependency is *not* a transitive dependency, then it
+                // only applies to test/example targets
+                if !d.is_transitive() && !unit.target.is_test() &&
+                   !unit.target.is_example() && !unit.profile.test {
+                    return false
+                }
+
+                // If this dependency is only available for certain platforms,
+                // make sure we're only enabling it for that platform.
+                if !self.dep_platform_activated(d, unit.kind) {
+                    return false
+                }
+
+                // If the dependency is optional, then we're only activating it
+                // if the corresponding feature was activated
+                if d.is_optional() &&
+                   !self.resolve.is_optional_dep_activated_for(
+                       id, d.name_in_toml(), unit.features_for,
+                       self.build_config.dev_units) {
+                    return false;
+                }
+
+                // If we've gotten past all that, then this dependency is
+                // actually used!
+                true
+            }).collect::<Vec<_>>();
+            if !deps.is_empty() {
+                ret.push((self.get_package(dep_id)?, deps));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Returns the units to build for the artifact dependency `dep` of
+    /// `unit`, which resolved to `pkg`.
+    fn artifact_units(&self, unit: &Unit<'a>, pkg: &'a Package, dep: &Dependency)
+                      -> CargoResult<Vec<Unit<'a>>> {
+        let kind = match dep.artifact_target() {
+            None => unit.kind,
+            Some("target") => Kind::Target,
+            Some(triple) if triple == self.host_triple() => Kind::Host,
+            Some(triple) if Some(triple) == self.requested_target() => Kind::Target,
+            Some(triple) => {
+                bail!("artifact dependency `{}` of `{}` is built for `{}`, but \
+                       only the host or the `--target` triple can be used",
+                      dep.name_in_toml(), unit.pkg.package_id(), triple)
+            }
+        };
+        let (artifact, units) = match dep.artifact() {
+            Some(ArtifactKind::Bin) => {
+                ("bin", pkg.targets().iter().filter(|t| t.is_bin()).collect::<Vec<_>>())
+            }
+            Some(ArtifactKind::Cdylib) => {
+                ("cdylib", pkg.targets().iter().filter(|t| t.is_cdylib()).collect())
+            }
+            None => return Ok(Vec::new()),
+        };
+        if units.is_empty() {
+            bail!("dependency `{}` of `{}` requires a `{}` artifact, but \
+                   package `{}` does not have any such target",
+                  dep.name_in_toml(), unit.pkg.package_id(), artifact,
+                  pkg.package_id())
+        }
+        Ok(units.into_iter().map(|t| {
+            Unit {
+                pkg: pkg,
+                target: t,
+                profile: self.package_lib_profile(pkg.package_id(),
+                                                  dep_for_host(unit, t)),
+                kind: kind,
+                features_for: self.dep_features_for(unit),
+                for_host: dep_for_host(unit, t),
+            }
+        }).collect())
+    }
+
+    /// Returns the environment variables which point `unit` at the files built
+    /// for its artifact dependencies, `CARGO_BIN_FILE_<DEP>_<NAME>` for each
+    /// binary and `CARGO_CDYLIB_FILE_<DEP>` for a cdylib.
+    pub fn artifact_env(&mut self, unit: &Unit<'a>)
+                        -> CargoResult<Vec<(String, PathBuf)>> {
+        let mut env = Vec::new();
+        for (pkg, deps) in self.used_deps(unit)? {
+            for d in deps.iter().filter(|d| d.artifact().is_some()) {
+                let dep_name = super::envify(d.name_in_toml());
+                for artifact in self.artifact_units(unit, pkg, d)? {
+                    let (var, file) = if artifact.target.is_bin() {
+                        let file = self.target_filenames(&artifact)?
+                                       .into_iter()
+                                       .find(|&(_, _, linkable)| !linkable)
+                                       .map(|(dst, _, _)| dst);
+                        (format!("CARGO_BIN_FILE_{}_{}", dep_name,
+                                 artifact.target.name()), file)
+                    } else {
+                        (format!("CARGO_CDYLIB_FILE_{}", dep_name),
+                         self.cdylib_filename(&artifact))
+                    };
+                    if let Some(file) = file {
+                        env.push((var, file));
+                    }
+                }
+            }
+        }
+        Ok(env)
+    }
+
+    /// Returns the path of the `cdylib` built for `unit`, which may build other
+    /// crate types of its library as well, or `None` if it doesn't build one.
+    fn cdylib_filename(&mut self, unit: &Unit) -> Option<PathBuf> {
+        if !unit.target.is_cdylib() || unit.profile.check {
+            return None
+        }
+        let (prefix, suffix) = {
+            let info = if unit.target.for_host() {
+                &self.host_info
+            } else {
+                &self.target_info
+            };
+            match info.crate_types.get("cdylib") {
+                Some(&Some((ref prefix, ref suffix))) => {
+                    (prefix.clone(), suffix.clone())
+                }
+                _ => return None,
+            }
+        };
+        let stem = self.file_stem(unit);
+        Some(self.out_dir(unit).join(format!("{}{}{}", prefix, stem, suffix)))
+    }
+
     /// Returns the dependencies needed to run a build script.
     ///
     /// The `unit` provided must represent an execution of a build script, and
@@ -703,6 +860,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         }).chain(Some(Unit {
             profile: self.build_script_profile(unit.pkg.package_id()),
             kind: Kind::Host, // build scripts always compiled for the host
+            for_host: true,
             ..*unit
         })).collect())
     }
@@ -736,8 +894,11 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             ret.push(Unit {
                 pkg: dep,
                 target: lib,
-                profile: self.lib_profile(),
+                profile: self.package_lib_profile(dep.package_id(),
+                                                  dep_for_host(unit, lib)),
                 kind: unit.kind.for_target(lib),
+                features_for: unit.features_for,
+                for_host: dep_for_host(unit, lib),
             });
             if self.build_config.doc_all {
                 ret.push(Unit {
@@ -745,6 +906,8 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                     target: lib,
                     profile: &self.profiles.doc,
                     kind: unit.kind.for_target(lib),
+                    features_for: unit.features_for,
+                    for_host: dep_for_host(unit, lib),
                 });
             }
         }
@@ -773,6 +936,8 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 target: t,
                 profile: &self.profiles.custom_build,
                 kind: unit.kind,
+                features_for: unit.features_for,
+                for_host: unit.for_host,
             }
         })
     }
@@ -782,8 +947,10 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             Unit {
                 pkg: unit.pkg,
                 target: t,
-                profile: self.lib_or_check_profile(unit, t),
+                profile: self.lib_or_check_profile(unit, unit.pkg, t),
                 kind: unit.kind.for_target(t),
+                features_for: unit.features_for,
+                for_host: unit.for_host || t.for_host(),
             }
         })
     }
@@ -837,31 +1004,49 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     /// Number of jobs specified for this build
     pub fn jobs(&self) -> u32 { self.build_config.jobs }
 
+    /// Whether `unit` is built with pipelining, writing its metadata before
+    /// generating code so that dependents only needing the metadata can start
+    /// early. That's the case for libraries which are only built as an rlib.
+    pub fn pipelined(&self, unit: &Unit) -> bool {
+        self.build_config.pipelining &&
+            unit.target.is_lib() &&
+            !unit.profile.check && !unit.profile.test && !unit.profile.doc &&
+            !unit.profile.run_custom_build &&
+            unit.target.rustc_crate_types().iter().all(|t| *t == "lib" || *t == "rlib")
+    }
+
+    /// Whether compiling `parent` only needs the metadata of its dependency
+    /// `dep`, rather than waiting for it to be completely built. Anything
+    /// which is linked needs the code of its dependencies.
+    pub fn only_requires_rmeta(&self, parent: &Unit, dep: &Unit) -> bool {
+        self.pipelined(parent) && self.pipelined(dep)
+    }
+
     pub fn lib_profile(&self) -> &'a Profile {
-        let (normal, test) = if self.build_config.release {
-            (&self.profiles.release, &self.profiles.bench_deps)
-        } else {
-            (&self.profiles.dev, &self.profiles.test_deps)
-        };
-        if self.build_config.test {
-            test
-        } else {
-            normal
-        }
+        lib_profile(self.profiles, &self.build_config)
+    }
+
+    /// Returns the library profile for `pkg`, taking any profile overrides
+    /// for it into account, and `build-override` if it's built `for_host`.
+    pub fn package_lib_profile(&self, pkg: &PackageId, for_host: bool) -> &'a Profile {
+        let profiles = self.profiles.for_package(pkg, for_host);
+        lib_profile(profiles, &self.build_config)
     }
 
-    pub fn lib_or_check_profile(&self, unit: &Unit, target: &Target) -> &'a Profile {
+    pub fn lib_or_check_profile(&self, unit: &Unit, pkg: &Package, target: &Target)
+                                -> &'a Profile {
+        let for_host = dep_for_host(unit, target);
         if unit.profile.check && !target.is_custom_build() && !target.for_host() {
-            &self.profiles.check
+            &self.profiles.for_package(pkg.package_id(), for_host).check
         } else {
-            self.lib_profile()
+            self.package_lib_profile(pkg.package_id(), for_host)
         }
     }
 
-    pub fn build_script_profile(&self, _pkg: &PackageId) -> &'a Profile {
+    pub fn build_script_profile(&self, pkg: &PackageId) -> &'a Profile {
         // TODO: should build scripts always be built with the same library
         //       profile? How is this controlled at the CLI layer?
-        self.lib_profile()
+        self.package_lib_profile(pkg, true)
     }
 
     pub fn incremental_args(&self, unit: &Unit) -> CargoResult<Vec<String>> {
@@ -884,6 +1069,17 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         pkg.source_id().is_path() || self.config.extra_verbose()
     }
 
+    /// Returns the flags for the lint levels of the `[lints]` table of
+    /// `unit`'s package, which only apply to workspace members.
+    pub fn lint_args(&self, unit: &Unit<'a>) -> &'a [String] {
+        let id = unit.pkg.package_id();
+        if self.ws.members().any(|p| p.package_id() == id) {
+            unit.pkg.manifest().lints()
+        } else {
+            &[]
+        }
+    }
+
     fn info(&self, kind: &Kind) -> &TargetInfo {
         match *kind {
             Kind::Host => &self.host_info,
@@ -892,6 +1088,27 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     }
 }
 
+// Returns whether the dependency `target` of `unit` is built for a build
+// script, procedural macro or plugin. Everything such a target depends on is
+// as well.
+fn dep_for_host(unit: &Unit, target: &Target) -> bool {
+    unit.for_host || unit.target.is_custom_build() || unit.target.for_host() ||
+        target.for_host()
+}
+
+fn lib_profile<'a>(profiles: &'a Profiles, build_config: &BuildConfig) -> &'a Profile {
+    let (normal, test) = if build_config.release {
+        (&profiles.release, &profiles.bench_deps)
+    } else {
+        (&profiles.dev, &profiles.test_deps)
+    };
+    if build_config.test {
+        test
+    } else {
+        normal
+    }
+}
+
 // Acquire extra flags to pass to the compiler from the
 // RUSTFLAGS environment variable and similar config values
 fn env_args(config: &Config,
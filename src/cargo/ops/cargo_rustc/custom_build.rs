@@ -5,7 +5,7 @@ use std::str;
 use std::sync::{Mutex, Arc};
 
 use core::PackageId;
-use util::{CargoResult, Human, Freshness, Cfg};
+use util::{CargoResult, Human, Freshness, Cfg, ProcessBuilder};
 use util::{internal, ChainError, profile, paths};
 use util::machine_message;
 
@@ -84,65 +84,9 @@ pub fn prepare<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
 fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
                         -> CargoResult<(Work, Work)> {
     let dependencies = cx.dep_run_custom_build(unit)?;
-    let build_script_unit = dependencies.iter().find(|d| {
-        !d.profile.run_custom_build && d.target.is_custom_build()
-    }).expect("running a script not depending on an actual script");
-    let script_output = cx.build_script_dir(build_script_unit);
+    let script_output = cx.build_script_dir(script_unit(&dependencies));
     let build_output = cx.build_script_out_dir(unit);
-
-    // Building the command to execute
-    let to_exec = script_output.join(unit.target.name());
-
-    // Start preparing the process to execute, starting out with some
-    // environment variables. Note that the profile-related environment
-    // variables are not set with this the build script's profile but rather the
-    // package's library profile.
-    let profile = cx.lib_profile();
-    let to_exec = to_exec.into_os_string();
-    let mut cmd = cx.compilation.host_process(to_exec, unit.pkg)?;
-    cmd.env("OUT_DIR", &build_output)
-       .env("CARGO_MANIFEST_DIR", unit.pkg.root())
-       .env("NUM_JOBS", &cx.jobs().to_string())
-       .env("TARGET", &match unit.kind {
-           Kind::Host => cx.host_triple(),
-           Kind::Target => cx.target_triple(),
-       })
-       .env("DEBUG", &profile.debuginfo.is_some().to_string())
-       .env("OPT_LEVEL", &profile.opt_level)
-       .env("PROFILE", if cx.build_config.release { "release" } else { "debug" })
-       .env("HOST", cx.host_triple())
-       .env("RUSTC", &cx.config.rustc()?.path)
-       .env("RUSTDOC", &*cx.config.rustdoc()?);
-
-    if let Some(links) = unit.pkg.manifest().links() {
-        cmd.env("CARGO_MANIFEST_LINKS", links);
-    }
-
-    // Be sure to pass along all enabled features for this package, this is the
-    // last piece of statically known information that we have.
-    for feat in cx.resolve.features(unit.pkg.package_id()).iter() {
-        cmd.env(&format!("CARGO_FEATURE_{}", super::envify(feat)), "1");
-    }
-
-    let mut cfg_map = HashMap::new();
-    for cfg in cx.cfg(unit.kind) {
-        match *cfg {
-            Cfg::Name(ref n) => { cfg_map.insert(n.clone(), None); }
-            Cfg::KeyPair(ref k, ref v) => {
-                match *cfg_map.entry(k.clone()).or_insert(Some(Vec::new())) {
-                    Some(ref mut values) => values.push(v.clone()),
-                    None => { /* ... */ }
-                }
-            }
-        }
-    }
-    for (k, v) in cfg_map {
-        let k = format!("CARGO_CFG_{}", super::envify(&k));
-        match v {
-            Some(list) => { cmd.env(&k, list.join(",")); }
-            None => { cmd.env(&k, ""); }
-        }
-    }
+    let mut cmd = command(cx, unit)?;
 
     // Gather the set of native dependencies that this package has along with
     // some other variables to close over.
@@ -275,6 +219,85 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     Ok((dirty, fresh))
 }
 
+/// The command running the build script of `unit`, with all the environment
+/// which is known before it runs. The `DEP_*` variables from the build scripts
+/// of its `links` dependencies are only added once those have run.
+pub fn command<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
+                         -> CargoResult<ProcessBuilder> {
+    let dependencies = cx.dep_run_custom_build(unit)?;
+    let script_output = cx.build_script_dir(script_unit(&dependencies));
+    let build_output = cx.build_script_out_dir(unit);
+
+    // Building the command to execute
+    let to_exec = script_output.join(unit.target.name());
+
+    // Start preparing the process to execute, starting out with some
+    // environment variables. Note that the profile-related environment
+    // variables are not set with this the build script's profile but rather the
+    // package's library profile.
+    let profile = cx.lib_profile();
+    let to_exec = to_exec.into_os_string();
+    let mut cmd = cx.compilation.host_process(to_exec, unit.pkg)?;
+    cmd.env("OUT_DIR", &build_output)
+       .env("CARGO_MANIFEST_DIR", unit.pkg.root())
+       .env("NUM_JOBS", &cx.jobs().to_string())
+       .env("TARGET", &match unit.kind {
+           Kind::Host => cx.host_triple(),
+           Kind::Target => cx.target_triple(),
+       })
+       .env("DEBUG", &profile.debuginfo.is_some().to_string())
+       .env("OPT_LEVEL", &profile.opt_level)
+       .env("PROFILE", if cx.build_config.release { "release" } else { "debug" })
+       .env("HOST", cx.host_triple())
+       .env("RUSTC", &cx.config.rustc()?.path)
+       .env("RUSTDOC", &*cx.config.rustdoc()?);
+
+    if let Some(links) = unit.pkg.manifest().links() {
+        cmd.env("CARGO_MANIFEST_LINKS", links);
+    }
+
+    // Artifact build-dependencies were built before the build script, so
+    // point it at their files.
+    for (var, file) in cx.artifact_env(script_unit(&dependencies))? {
+        cmd.env(&var, file);
+    }
+
+    // Be sure to pass along all enabled features for this package, this is the
+    // last piece of statically known information that we have.
+    for feat in cx.unit_features(unit).iter() {
+        cmd.env(&format!("CARGO_FEATURE_{}", super::envify(feat)), "1");
+    }
+
+    let mut cfg_map = HashMap::new();
+    for cfg in cx.cfg(unit.kind) {
+        match *cfg {
+            Cfg::Name(ref n) => { cfg_map.insert(n.clone(), None); }
+            Cfg::KeyPair(ref k, ref v) => {
+                match *cfg_map.entry(k.clone()).or_insert(Some(Vec::new())) {
+                    Some(ref mut values) => values.push(v.clone()),
+                    None => { /* ... */ }
+                }
+            }
+        }
+    }
+    for (k, v) in cfg_map {
+        let k = format!("CARGO_CFG_{}", super::envify(&k));
+        match v {
+            Some(list) => { cmd.env(&k, list.join(",")); }
+            None => { cmd.env(&k, ""); }
+        }
+    }
+    Ok(cmd)
+}
+
+// The unit compiling the build script which `dependencies`, the dependencies
+// of running it, include.
+fn script_unit<'a, 'b>(dependencies: &'b [Unit<'a>]) -> &'b Unit<'a> {
+    dependencies.iter().find(|d| {
+        !d.profile.run_custom_build && d.target.is_custom_build()
+    }).expect("running a script not depending on an actual script")
+}
+
 impl BuildState {
     pub fn new(config: &super::BuildConfig) -> BuildState {
         let mut overrides = HashMap::new();
@@ -293,7 +316,7 @@ impl BuildState {
         self.outputs.lock().unwrap().insert((id, kind), output);
     }
 
-    fn has_override(&self, unit: &Unit) -> bool {
+    pub fn has_override(&self, unit: &Unit) -> bool {
         let key = unit.pkg.manifest().links().map(|l| (l.to_string(), unit.kind));
         match key.and_then(|k| self.overrides.get(&k)) {
             Some(output) => {
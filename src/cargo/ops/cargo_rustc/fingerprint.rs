@@ -139,6 +139,7 @@ pub struct Fingerprint {
     #[serde(skip_serializing, skip_deserializing)]
     memoized_hash: Mutex<Option<u64>>,
     rustflags: Vec<String>,
+    lints: u64,
 }
 
 fn serialize_deps<S>(deps: &Vec<(String, Arc<Fingerprint>)>, ser: S)
@@ -164,6 +165,7 @@ fn deserialize_deps<D>(d: D) -> Result<Vec<(String, Arc<Fingerprint>)>, D::Error
             deps: Vec::new(),
             memoized_hash: Mutex::new(Some(hash)),
             rustflags: Vec::new(),
+            lints: 0,
         }))
     }).collect())
 }
@@ -218,6 +220,9 @@ impl Fingerprint {
         if self.rustflags != old.rustflags {
             return Err(internal("RUSTFLAGS has changed"))
         }
+        if self.lints != old.lints {
+            return Err(internal("lint levels have changed"))
+        }
         match (&self.local, &old.local) {
             (&LocalFingerprint::Precalculated(ref a),
              &LocalFingerprint::Precalculated(ref b)) => {
@@ -269,8 +274,9 @@ impl hash::Hash for Fingerprint {
             ref local,
             memoized_hash: _,
             ref rustflags,
+            lints,
         } = *self;
-        (rustc, features, target, profile, deps, local, rustflags).hash(h)
+        (rustc, features, target, profile, deps, local, rustflags, lints).hash(h)
     }
 }
 
@@ -355,11 +361,12 @@ fn calculate<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
         rustc: util::hash_u64(&cx.config.rustc()?.verbose_version),
         target: util::hash_u64(&unit.target),
         profile: util::hash_u64(&unit.profile),
-        features: format!("{:?}", cx.resolve.features_sorted(unit.pkg.package_id())),
+        features: format!("{:?}", cx.unit_features_sorted(unit)),
         deps: deps,
         local: local,
         memoized_hash: Mutex::new(None),
         rustflags: extra_flags,
+        lints: util::hash_u64(&cx.lint_args(unit)),
     });
     cx.fingerprints.insert(*unit, fingerprint.clone());
     Ok(fingerprint)
@@ -440,6 +447,7 @@ pub fn prepare_build_cmd<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
         local: local,
         memoized_hash: Mutex::new(None),
         rustflags: Vec::new(),
+        lints: 0,
     };
     let compare = compare_old_fingerprint(&loc, &fingerprint);
     log_compare(unit, &compare);
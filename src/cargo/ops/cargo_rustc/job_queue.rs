@@ -8,6 +8,7 @@ use crossbeam::{self, Scope};
 use term::color::YELLOW;
 
 use core::{PackageId, Target, Profile};
+use core::resolver::FeaturesFor;
 use util::{Config, DependencyQueue, Fresh, Dirty, Freshness};
 use util::{CargoResult, ProcessBuilder, profile, internal, human};
 use {handle_error};
@@ -22,7 +23,7 @@ use super::job::Job;
 /// then later on the entire graph is processed and compiled.
 pub struct JobQueue<'a> {
     jobs: usize,
-    queue: DependencyQueue<Key<'a>, Vec<(Job, Freshness)>>,
+    queue: DependencyQueue<Key<'a>, Artifact, Vec<(Job, Freshness)>>,
     tx: Sender<(Key<'a>, Message)>,
     rx: Receiver<(Key<'a>, Message)>,
     active: usize,
@@ -42,12 +43,24 @@ struct PendingBuild {
     fresh: Freshness,
 }
 
+/// The output of a unit which its dependents wait on.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+enum Artifact {
+    /// Everything the unit produces.
+    All,
+    /// Only the metadata of a library, which is written before the code is
+    /// generated when it's built with pipelining.
+    Metadata,
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
 struct Key<'a> {
     pkg: &'a PackageId,
     target: &'a Target,
     profile: &'a Profile,
     kind: Kind,
+    features_for: FeaturesFor,
+    for_host: bool,
 }
 
 pub struct JobState<'a> {
@@ -59,6 +72,7 @@ enum Message {
     Run(String),
     Stdout(String),
     Stderr(String),
+    Metadata,
     Finish(CargoResult<()>),
 }
 
@@ -74,6 +88,12 @@ impl<'a> JobState<'a> {
     pub fn stderr(&self, err: &str) {
         let _ = self.tx.send((self.key, Message::Stderr(err.to_string())));
     }
+
+    /// Signals that the metadata of the library being built has been written,
+    /// so that the dependents only needing it may start.
+    pub fn metadata_produced(&self) {
+        let _ = self.tx.send((self.key, Message::Metadata));
+    }
 }
 
 impl<'a> JobQueue<'a> {
@@ -176,6 +196,11 @@ impl<'a> JobQueue<'a> {
                         writeln!(cx.config.shell().err(), "{}", err)?;
                     }
                 }
+                Message::Metadata => {
+                    info!("metadata: {:?}", key);
+                    let fresh = self.pending[&key].fresh;
+                    self.queue.finish_artifact(&key, &Artifact::Metadata, fresh);
+                }
                 Message::Finish(result) => {
                     info!("end: {:?}", key);
                     self.active -= 1;
@@ -202,7 +227,11 @@ impl<'a> JobQueue<'a> {
             }
         }
 
-        let build_type = if self.is_release { "release" } else { "dev" };
+        let build_type = match cx.build_config.profile {
+            Some(ref name) => name.clone(),
+            None if self.is_release => "release".to_string(),
+            None => "dev".to_string(),
+        };
         let profile = cx.lib_profile();
         let mut opt_type = String::from(if profile.opt_level == "0" { "unoptimized" }
                                         else { "optimized" });
@@ -340,27 +369,57 @@ impl<'a> Key<'a> {
             target: unit.target,
             profile: unit.profile,
             kind: unit.kind,
+            features_for: unit.features_for,
+            for_host: unit.for_host,
         }
     }
 
     fn dependencies<'cfg>(&self, cx: &Context<'a, 'cfg>)
-                          -> CargoResult<Vec<Key<'a>>> {
+                          -> CargoResult<Vec<(Key<'a>, Artifact)>> {
         let unit = Unit {
             pkg: cx.get_package(self.pkg)?,
             target: self.target,
             profile: self.profile,
             kind: self.kind,
+            features_for: self.features_for,
+            for_host: self.for_host,
         };
         let targets = cx.dep_targets(&unit)?;
-        Ok(targets.iter().filter_map(|unit| {
+        let mut seen = HashSet::new();
+        let mut deps = Vec::new();
+        for dep in targets.iter() {
             // Binaries aren't actually needed to *compile* tests, just to run
             // them, so we don't include this dependency edge in the job graph.
-            if self.target.is_test() && unit.target.is_bin() {
-                None
+            if self.target.is_test() && dep.target.is_bin() {
+                continue
+            }
+            let artifact = if cx.only_requires_rmeta(&unit, dep) {
+                Artifact::Metadata
             } else {
-                Some(Key::new(unit))
+                Artifact::All
+            };
+            seen.insert(Key::new(dep));
+            deps.push((Key::new(dep), artifact));
+        }
+
+        // Anything which links needs the rlibs of all the libraries it
+        // reaches, but a pipelined library may finish before the libraries it
+        // only needed the metadata of. Those are waited on directly.
+        if !cx.pipelined(&unit) {
+            let mut libs = targets.iter().filter(|dep| {
+                cx.pipelined(dep)
+            }).cloned().collect::<Vec<_>>();
+            while let Some(lib) = libs.pop() {
+                for dep in cx.dep_targets(&lib)? {
+                    if cx.only_requires_rmeta(&lib, &dep) &&
+                       seen.insert(Key::new(&dep)) {
+                        deps.push((Key::new(&dep), Artifact::All));
+                        libs.push(dep);
+                    }
+                }
             }
-        }).collect())
+        }
+        Ok(deps)
     }
 }
 
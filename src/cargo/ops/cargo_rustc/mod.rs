@@ -10,11 +10,13 @@ use serde_json;
 
 use core::{Package, PackageId, PackageSet, Target, Resolve};
 use core::{Profile, Profiles, Workspace};
+use core::resolver::FeaturesFor;
 use core::shell::ColorConfig;
 use util::{self, CargoResult, ProcessBuilder, ProcessError, human, machine_message};
 use util::{Config, internal, ChainError, profile, join_paths, short_hash};
 use util::Freshness;
 
+use self::build_plan::BuildPlan;
 use self::job::{Job, Work};
 use self::job_queue::JobQueue;
 
@@ -24,6 +26,7 @@ pub use self::compilation::Compilation;
 pub use self::context::{Context, Unit};
 pub use self::custom_build::{BuildOutput, BuildMap, BuildScripts};
 
+mod build_plan;
 mod compilation;
 mod context;
 mod custom_build;
@@ -45,9 +48,19 @@ pub struct BuildConfig {
     pub target: TargetConfig,
     pub jobs: u32,
     pub release: bool,
+    pub profile: Option<String>,
+    pub ignore_rust_version: bool,
     pub test: bool,
     pub doc_all: bool,
     pub json_messages: bool,
+    /// Print what would be run for each unit instead of building anything.
+    pub build_plan: bool,
+    /// Whether dependents of libraries may start compiling once the metadata
+    /// of the library is written, see `Context::pipelined`.
+    pub pipelining: bool,
+    /// Whether any unit built needs the dev-dependencies of its package,
+    /// which selects the features of the second feature resolver.
+    pub dev_units: bool,
 }
 
 #[derive(Clone, Default)]
@@ -80,6 +93,13 @@ pub trait Executor: Send + Sync + 'static {
         cmd.exec_with_streaming(handle_stdout, handle_stderr)?;
         Ok(())
     }
+
+    /// Called instead of `exec` with the rustc and rustdoc commands of
+    /// `cargo build --build-plan`, which are recorded in the plan rather than
+    /// run. Returning an error aborts the build plan.
+    fn plan(&self, _cmd: &ProcessBuilder, _id: &PackageId) -> CargoResult<()> {
+        Ok(())
+    }
 }
 
 /// A DefaultExecutor calls rustc without doing anything else. It is Cargo's
@@ -112,6 +132,8 @@ pub fn compile_targets<'a, 'cfg: 'a>(ws: &Workspace<'cfg>,
                 target: target,
                 profile: profile,
                 kind: if target.for_host() {Kind::Host} else {default_kind},
+                features_for: FeaturesFor::Target,
+                for_host: false,
             }
         })
     }).collect::<Vec<_>>();
@@ -126,6 +148,16 @@ pub fn compile_targets<'a, 'cfg: 'a>(ws: &Workspace<'cfg>,
     cx.build_used_in_plugin_map(&units)?;
     custom_build::build_map(&mut cx, &units)?;
 
+    if cx.build_config.build_plan {
+        exec.init(&cx);
+        let mut plan = BuildPlan::new(exec);
+        for unit in units.iter() {
+            plan.add(&mut cx, unit)?;
+        }
+        machine_message::emit(plan);
+        return Ok(cx.compilation)
+    }
+
     for unit in units.iter() {
         // Build up a list of pending jobs, each of which represent
         // compiling a particular package. No actual work is executed as
@@ -181,7 +213,7 @@ pub fn compile_targets<'a, 'cfg: 'a>(ws: &Workspace<'cfg>,
                 }));
         }
 
-        let feats = cx.resolve.features(&unit.pkg.package_id());
+        let feats = cx.unit_features(unit);
         cx.compilation.cfgs.entry(unit.pkg.package_id().clone())
             .or_insert_with(HashSet::new)
             .extend(feats.iter().map(|feat| format!("feature=\"{}\"", feat)));
@@ -202,6 +234,33 @@ pub fn compile_targets<'a, 'cfg: 'a>(ws: &Workspace<'cfg>,
     Ok(cx.compilation)
 }
 
+/// Fails if the package of `unit` declares a `rust-version` newer than the
+/// rustc in use, unless `--ignore-rust-version` was passed.
+fn check_rust_version(cx: &Context, unit: &Unit) -> CargoResult<()> {
+    let required = match unit.pkg.manifest().rust_version() {
+        Some(required) => required,
+        None => return Ok(()),
+    };
+    if cx.build_config.ignore_rust_version {
+        return Ok(())
+    }
+
+    // Pre-release compilers are considered to be the release they precede,
+    // and a `rust-version` without a patch version accepts any patch version.
+    let current = &cx.config.rustc()?.version;
+    let current_parts = [current.major, current.minor, current.patch];
+    let required_parts = required.split('.').map(|p| {
+        p.parse::<u64>().unwrap_or(0)
+    }).collect::<Vec<_>>();
+    if current_parts[..required_parts.len()] < required_parts[..] {
+        bail!("package `{}` cannot be built because it requires rustc {} or \
+               newer, while the currently active rustc version is {}\n\
+               Either upgrade rustc or pass `--ignore-rust-version` to build \
+               anyway", unit.pkg, required, current)
+    }
+    Ok(())
+}
+
 fn compile<'a, 'cfg: 'a>(cx: &mut Context<'a, 'cfg>,
                          jobs: &mut JobQueue<'a>,
                          unit: &Unit<'a>,
@@ -209,6 +268,7 @@ fn compile<'a, 'cfg: 'a>(cx: &mut Context<'a, 'cfg>,
     if !cx.compiled.insert(*unit) {
         return Ok(())
     }
+    check_rust_version(cx, unit)?;
 
     // Build up the work to be done to compile this unit, enqueuing it once
     // we've got everything constructed.
@@ -246,22 +306,10 @@ fn compile<'a, 'cfg: 'a>(cx: &mut Context<'a, 'cfg>,
 }
 
 fn rustc(cx: &mut Context, unit: &Unit, exec: Arc<Executor>) -> CargoResult<Work> {
-    let crate_types = unit.target.rustc_crate_types();
-    let mut rustc = prepare_rustc(cx, crate_types, unit)?;
+    let mut rustc = rustc_command(cx, unit)?;
 
     let name = unit.pkg.name().to_string();
 
-    // If this is an upstream dep we don't want warnings from, turn off all
-    // lints.
-    if !cx.show_warnings(unit.pkg.package_id()) {
-        rustc.arg("--cap-lints").arg("allow");
-
-    // If this is an upstream dep but we *do* want warnings, make sure that they
-    // don't fail compilation.
-    } else if !unit.pkg.package_id().source_id().is_path() {
-        rustc.arg("--cap-lints").arg("warn");
-    }
-
     let filenames = cx.target_filenames(unit)?;
     let root = cx.out_dir(unit);
 
@@ -270,10 +318,7 @@ fn rustc(cx: &mut Context, unit: &Unit, exec: Arc<Executor>) -> CargoResult<Work
     let current_id = unit.pkg.package_id().clone();
     let build_deps = load_build_deps(cx, unit);
 
-    // If we are a binary and the package also contains a library, then we
-    // don't pass the `-l` flags.
-    let pass_l_flag = unit.target.is_lib() ||
-                      !unit.pkg.targets().iter().any(|t| t.is_lib());
+    let pass_l_flag = pass_l_flag(unit);
     let do_rename = unit.target.allows_underscores() && !unit.profile.test;
     let real_name = unit.target.name().to_string();
     let crate_name = unit.target.crate_name();
@@ -287,9 +332,8 @@ fn rustc(cx: &mut Context, unit: &Unit, exec: Arc<Executor>) -> CargoResult<Work
     let dep_info_loc = fingerprint::dep_info_loc(cx, unit);
     let cwd = cx.config.cwd().to_path_buf();
 
-    rustc.args(&cx.incremental_args(unit)?);
-    rustc.args(&cx.rustflags_args(unit)?);
     let json_messages = cx.build_config.json_messages;
+    let pipelined = cx.pipelined(unit);
     let package_id = unit.pkg.package_id().clone();
     let target = unit.target.clone();
 
@@ -329,7 +373,7 @@ fn rustc(cx: &mut Context, unit: &Unit, exec: Arc<Executor>) -> CargoResult<Work
         }
 
         state.running(&rustc);
-        if json_messages {
+        if json_messages || pipelined {
             exec.exec_json(rustc, &package_id,
                 &mut |line| if !line.is_empty() {
                     Err(internal(&format!("compiler stdout is not empty: `{}`", line)))
@@ -340,15 +384,26 @@ fn rustc(cx: &mut Context, unit: &Unit, exec: Arc<Executor>) -> CargoResult<Work
                     // stderr from rustc can have a mix of JSON and non-JSON output
                     if line.starts_with('{') {
                         // Handle JSON lines
-                        let compiler_message = serde_json::from_str(line).map_err(|_| {
-                            internal(&format!("compiler produced invalid json: `{}`", line))
-                        })?;
-
-                        machine_message::emit(machine_message::FromCompiler {
-                            package_id: &package_id,
-                            target: &target,
-                            message: compiler_message,
-                        });
+                        let compiler_message: serde_json::Value =
+                            serde_json::from_str(line).map_err(|_| {
+                                internal(&format!("compiler produced invalid json: `{}`", line))
+                            })?;
+
+                        // Artifact notifications are only requested for
+                        // pipelining, the metadata one unblocks dependents.
+                        if compiler_message["artifact"].is_string() {
+                            if compiler_message["emit"].as_str() == Some("metadata") {
+                                state.metadata_produced();
+                            }
+                        } else if json_messages {
+                            machine_message::emit(machine_message::FromCompiler {
+                                package_id: &package_id,
+                                target: &target,
+                                message: compiler_message,
+                            });
+                        } else if let Some(rendered) = compiler_message["rendered"].as_str() {
+                            write!(io::stderr(), "{}", rendered)?;
+                        }
                     } else {
                         // Forward non-JSON to stderr
                         writeln!(io::stderr(), "{}", line)?;
@@ -400,24 +455,68 @@ fn rustc(cx: &mut Context, unit: &Unit, exec: Arc<Executor>) -> CargoResult<Work
                 internal(format!("couldn't find build state for {}/{:?}",
                                  key.0, key.1))
             })?;
-            for path in output.library_paths.iter() {
-                rustc.arg("-L").arg(path);
-            }
-            if key.0 == *current_id {
-                for cfg in &output.cfgs {
-                    rustc.arg("--cfg").arg(cfg);
-                }
-                if pass_l_flag {
-                    for name in output.library_links.iter() {
-                        rustc.arg("-l").arg(name);
-                    }
-                }
-            }
+            add_native_output(rustc, key, output, pass_l_flag, current_id);
         }
         Ok(())
     }
 }
 
+/// The rustc command compiling `unit`, without the flags which are only known
+/// once the build scripts it depends on have run.
+fn rustc_command(cx: &mut Context, unit: &Unit) -> CargoResult<ProcessBuilder> {
+    let crate_types = unit.target.rustc_crate_types();
+    let mut rustc = prepare_rustc(cx, crate_types, unit)?;
+
+    for (var, file) in cx.artifact_env(unit)? {
+        rustc.env(&var, file);
+    }
+
+    rustc.args(cx.lint_args(unit));
+
+    // If this is an upstream dep we don't want warnings from, turn off all
+    // lints.
+    if !cx.show_warnings(unit.pkg.package_id()) {
+        rustc.arg("--cap-lints").arg("allow");
+
+    // If this is an upstream dep but we *do* want warnings, make sure that they
+    // don't fail compilation.
+    } else if !unit.pkg.package_id().source_id().is_path() {
+        rustc.arg("--cap-lints").arg("warn");
+    }
+
+    rustc.args(&cx.incremental_args(unit)?);
+    rustc.args(&cx.rustflags_args(unit)?);
+    Ok(rustc)
+}
+
+// If we are a binary and the package also contains a library, then we don't
+// pass the `-l` flags.
+fn pass_l_flag(unit: &Unit) -> bool {
+    unit.target.is_lib() || !unit.pkg.targets().iter().any(|t| t.is_lib())
+}
+
+// Adds the -L, -l and --cfg flags resulting from the build script output
+// `output` of `key` to the command provided.
+fn add_native_output(rustc: &mut ProcessBuilder,
+                     key: &(PackageId, Kind),
+                     output: &BuildOutput,
+                     pass_l_flag: bool,
+                     current_id: &PackageId) {
+    for path in output.library_paths.iter() {
+        rustc.arg("-L").arg(path);
+    }
+    if key.0 == *current_id {
+        for cfg in &output.cfgs {
+            rustc.arg("--cfg").arg(cfg);
+        }
+        if pass_l_flag {
+            for name in output.library_links.iter() {
+                rustc.arg("-l").arg(name);
+            }
+        }
+    }
+}
+
 /// Link the compiled target (often of form foo-{metadata_hash}) to the
 /// final target. This must happen during both "Fresh" and "Compile"
 fn link_targets(cx: &mut Context, unit: &Unit, fresh: bool) -> CargoResult<Work> {
@@ -425,7 +524,7 @@ fn link_targets(cx: &mut Context, unit: &Unit, fresh: bool) -> CargoResult<Work>
     let package_id = unit.pkg.package_id().clone();
     let target = unit.target.clone();
     let profile = unit.profile.clone();
-    let features = cx.resolve.features_sorted(&package_id).into_iter()
+    let features = cx.unit_features_sorted(unit).into_iter()
         .map(|s| s.to_owned())
         .collect();
     let json_messages = cx.build_config.json_messages;
@@ -521,6 +620,33 @@ fn prepare_rustc(cx: &mut Context,
 
 
 fn rustdoc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
+    // Create the documentation directory ahead of time as rustdoc currently has
+    // a bug where concurrent invocations will race to create this directory if
+    // it doesn't already exist.
+    fs::create_dir_all(&cx.out_dir(unit))?;
+
+    let mut rustdoc = rustdoc_command(cx, unit)?;
+
+    let name = unit.pkg.name().to_string();
+    let build_state = cx.build_state.clone();
+    let key = (unit.pkg.package_id().clone(), unit.kind);
+
+    Ok(Work::new(move |state| {
+        if let Some(output) = build_state.outputs.lock().unwrap().get(&key) {
+            for cfg in output.cfgs.iter() {
+                rustdoc.arg("--cfg").arg(cfg);
+            }
+        }
+        state.running(&rustdoc);
+        rustdoc.exec().chain_error(|| {
+            human(format!("Could not document `{}`.", name))
+        })
+    }))
+}
+
+/// The rustdoc command documenting `unit`, without the `--cfg` flags from its
+/// build script.
+fn rustdoc_command(cx: &mut Context, unit: &Unit) -> CargoResult<ProcessBuilder> {
     let mut rustdoc = cx.compilation.rustdoc_process(unit.pkg)?;
     rustdoc.arg("--crate-name").arg(&unit.target.crate_name())
            .cwd(cx.config.cwd())
@@ -532,16 +658,9 @@ fn rustdoc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
         }
     }
 
-    let doc_dir = cx.out_dir(unit);
-
-    // Create the documentation directory ahead of time as rustdoc currently has
-    // a bug where concurrent invocations will race to create this directory if
-    // it doesn't already exist.
-    fs::create_dir_all(&doc_dir)?;
-
-    rustdoc.arg("-o").arg(doc_dir);
+    rustdoc.arg("-o").arg(cx.out_dir(unit));
 
-    for feat in cx.resolve.features(unit.pkg.package_id()) {
+    for feat in cx.unit_features(unit) {
         rustdoc.arg("--cfg").arg(&format!("feature=\"{}\"", feat));
     }
 
@@ -552,22 +671,7 @@ fn rustdoc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
     build_deps_args(&mut rustdoc, cx, unit)?;
 
     rustdoc.args(&cx.rustdocflags_args(unit)?);
-
-    let name = unit.pkg.name().to_string();
-    let build_state = cx.build_state.clone();
-    let key = (unit.pkg.package_id().clone(), unit.kind);
-
-    Ok(Work::new(move |state| {
-        if let Some(output) = build_state.outputs.lock().unwrap().get(&key) {
-            for cfg in output.cfgs.iter() {
-                rustdoc.arg("--cfg").arg(cfg);
-            }
-        }
-        state.running(&rustdoc);
-        rustdoc.exec().chain_error(|| {
-            human(format!("Could not document `{}`.", name))
-        })
-    }))
+    Ok(rustdoc)
 }
 
 // The path that we pass to rustc is actually fairly important because it will
@@ -614,10 +718,21 @@ fn build_base_args(cx: &mut Context,
         cmd.arg("--color").arg(&color_config.to_string());
     }
 
-    if cx.build_config.json_messages {
+    let pipelined = cx.pipelined(unit);
+    if cx.build_config.json_messages || pipelined {
         cmd.arg("--error-format").arg("json");
     }
 
+    // With pipelining rustc tells us when the metadata has been written, and
+    // its diagnostics are printed by us as rendered by rustc.
+    if pipelined {
+        if !cx.build_config.json_messages && cx.config.shell().err().colored() {
+            cmd.arg("--json").arg("artifacts,diagnostic-rendered-ansi");
+        } else {
+            cmd.arg("--json").arg("artifacts");
+        }
+    }
+
     if !test {
         for crate_type in crate_types.iter() {
             cmd.arg("--crate-type").arg(crate_type);
@@ -626,6 +741,8 @@ fn build_base_args(cx: &mut Context,
 
     if check {
         cmd.arg("--emit=dep-info,metadata");
+    } else if pipelined {
+        cmd.arg("--emit=dep-info,metadata,link");
     } else {
         cmd.arg("--emit=dep-info,link");
     }
@@ -707,7 +824,7 @@ fn build_base_args(cx: &mut Context,
         cmd.arg("--cfg").arg("test");
     }
 
-    for feat in cx.resolve.features(unit.pkg.package_id()).iter() {
+    for feat in cx.unit_features(unit).iter() {
         cmd.arg("--cfg").arg(&format!("feature=\"{}\"", feat));
     }
 
@@ -763,25 +880,44 @@ fn build_deps_args(cmd: &mut ProcessBuilder, cx: &mut Context, unit: &Unit)
         });
     }
 
-    for unit in cx.dep_targets(unit)?.iter() {
-        if unit.profile.run_custom_build {
-            cmd.env("OUT_DIR", &cx.build_script_out_dir(unit));
+    // Packages declaring public dependencies have the others passed as
+    // private, so that the compiler can warn when their types show up in the
+    // package's public API. The compiler only accepts this on nightly.
+    let mark_private = unit.pkg.dependencies().iter().any(|d| d.is_public()) &&
+                       cx.config.rustc()?.is_nightly();
+    if mark_private {
+        cmd.arg("-Z").arg("unstable-options");
+    }
+
+    for dep in cx.dep_targets(unit)?.iter() {
+        if dep.profile.run_custom_build {
+            cmd.env("OUT_DIR", &cx.build_script_out_dir(dep));
         }
-        if unit.target.linkable() && !unit.profile.doc {
-            link_to(cmd, cx, unit)?;
+        if dep.target.linkable() && !dep.profile.doc {
+            let private = mark_private && is_private(cx.resolve, unit, dep);
+            for name in extern_crate_names(cx.resolve, unit, dep) {
+                link_to(cmd, cx, unit, dep, &name, private)?;
+            }
         }
     }
 
     return Ok(());
 
-    fn link_to(cmd: &mut ProcessBuilder, cx: &mut Context, unit: &Unit)
-               -> CargoResult<()> {
+    fn link_to(cmd: &mut ProcessBuilder, cx: &mut Context, parent: &Unit,
+               unit: &Unit, name: &str, private: bool) -> CargoResult<()> {
+        // The rlib of a dependency which is only waited on for its metadata
+        // may still be written to while `parent` compiles.
+        let rmeta = cx.only_requires_rmeta(parent, unit);
         for (dst, _link_dst, linkable) in cx.target_filenames(unit)? {
             if !linkable {
                 continue
             }
+            let dst = if rmeta { dst.with_extension("rmeta") } else { dst };
             let mut v = OsString::new();
-            v.push(&unit.target.crate_name());
+            if private {
+                v.push("priv:");
+            }
+            v.push(name);
             v.push("=");
             v.push(cx.out_dir(unit));
             v.push(&path::MAIN_SEPARATOR.to_string());
@@ -790,6 +926,53 @@ fn build_deps_args(cmd: &mut ProcessBuilder, cx: &mut Context, unit: &Unit)
         }
         Ok(())
     }
+
+    // Returns whether `dep` is listed in the manifest of `parent`, but never
+    // as a public dependency.
+    fn is_private(resolve: &Resolve, parent: &Unit, dep: &Unit) -> bool {
+        let id = dep.pkg.package_id();
+        let listed = parent.pkg.dependencies().iter().filter(|d| {
+            resolve.dep_matches_id(d, id)
+        }).collect::<Vec<_>>();
+        !listed.is_empty() && listed.iter().all(|d| !d.is_public())
+    }
+
+    // Returns the names that `dep` should be linked under when compiling
+    // `parent`. A dependency may be renamed in the manifest with the `package`
+    // key, and the same package may even be depended on under several names,
+    // in which case it's passed once per name.
+    fn extern_crate_names(resolve: &Resolve, parent: &Unit, dep: &Unit)
+                          -> Vec<String> {
+        let id = dep.pkg.package_id();
+        let mut names = Vec::new();
+        let mut plain = false;
+        let mut listed = false;
+        for d in parent.pkg.dependencies() {
+            if !resolve.dep_matches_id(d, id) {
+                continue
+            }
+            listed = true;
+            // Artifact dependencies are never linked.
+            if d.artifact().is_some() {
+                continue
+            }
+            match d.rename() {
+                Some(rename) => {
+                    let name = rename.replace("-", "_");
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                None => plain = true,
+            }
+        }
+        // Dependencies which aren't listed in the manifest, such as a
+        // package's own library, are always linked under their crate name.
+        if plain || !listed {
+            names.push(dep.target.crate_name());
+        }
+        names
+    }
 }
 
 fn envify(s: &str) -> String {
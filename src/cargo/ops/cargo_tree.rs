@@ -0,0 +1,391 @@
+//! Printing of the resolved dependency graph for `cargo tree`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::{self, FromStr};
+
+use core::{Dependency, Package, PackageId, PackageSet, Workspace};
+use core::dependency::Kind;
+use core::resolver::Resolve;
+use ops::{self, Packages};
+use util::{Cfg, CargoResult, ChainError, human};
+
+pub struct TreeOptions {
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+    /// Packages to print the tree of. The current package, or every member of
+    /// a virtual workspace, is used if this is empty.
+    pub packages: Vec<String>,
+    /// Only show dependencies which are built for this platform.
+    pub target: TreeTarget,
+    /// Print the dependents of this package instead of its dependencies.
+    pub invert: Option<String>,
+    /// Print the dependents of every package which is resolved to more than
+    /// one version.
+    pub duplicates: bool,
+    pub edges: EdgeKinds,
+    pub prefix: Prefix,
+}
+
+/// The platform dependencies are filtered by.
+pub enum TreeTarget {
+    Host,
+    Triple(String),
+    All,
+}
+
+/// Which dependency edges are followed, and whether the features enabled on
+/// each edge are displayed.
+pub struct EdgeKinds {
+    pub normal: bool,
+    pub build: bool,
+    pub dev: bool,
+    pub features: bool,
+}
+
+/// What to print in front of each package.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Prefix {
+    /// Draw the tree with indentation and lines.
+    Indent,
+    /// Print the depth of the package, which is easy to diff and sort.
+    Depth,
+    /// Print the package only.
+    None,
+}
+
+impl TreeTarget {
+    pub fn parse(s: Option<&str>) -> TreeTarget {
+        match s {
+            None => TreeTarget::Host,
+            Some("all") => TreeTarget::All,
+            Some(triple) => TreeTarget::Triple(triple.to_string()),
+        }
+    }
+}
+
+impl EdgeKinds {
+    pub fn parse(s: &str) -> CargoResult<EdgeKinds> {
+        let mut ret = EdgeKinds {
+            normal: false,
+            build: false,
+            dev: false,
+            features: false,
+        };
+        for kind in s.split(',').map(|s| s.trim()) {
+            match kind {
+                "normal" => ret.normal = true,
+                "build" => ret.build = true,
+                "dev" => ret.dev = true,
+                "features" => ret.features = true,
+                "all" => {
+                    ret.normal = true;
+                    ret.build = true;
+                    ret.dev = true;
+                }
+                _ => bail!("invalid edge kind `{}`, expected one of `normal`, \
+                            `build`, `dev`, `features` or `all`", kind),
+            }
+        }
+        // Only asking for features still shows all dependencies.
+        if !ret.normal && !ret.build && !ret.dev {
+            ret.normal = true;
+            ret.build = true;
+            ret.dev = true;
+        }
+        Ok(ret)
+    }
+}
+
+impl Prefix {
+    pub fn parse(s: &str) -> CargoResult<Prefix> {
+        match s {
+            "indent" => Ok(Prefix::Indent),
+            "depth" => Ok(Prefix::Depth),
+            "none" => Ok(Prefix::None),
+            _ => bail!("invalid prefix `{}`, expected one of `indent`, \
+                        `depth` or `none`", s),
+        }
+    }
+}
+
+struct Edge<'a> {
+    kind: Kind,
+    to: &'a PackageId,
+    features: Vec<String>,
+}
+
+type Graph<'a> = HashMap<&'a PackageId, Vec<Edge<'a>>>;
+
+const KINDS: [(Kind, &'static str); 3] = [
+    (Kind::Normal, ""),
+    (Kind::Build, "[build-dependencies]"),
+    (Kind::Development, "[dev-dependencies]"),
+];
+
+pub fn tree(ws: &Workspace, opts: &TreeOptions) -> CargoResult<()> {
+    let specs = Packages::All.into_package_id_specs(ws)?;
+    let (packages, resolve) = ops::resolve_ws_precisely(ws,
+                                                        None,
+                                                        &opts.features,
+                                                        opts.all_features,
+                                                        opts.no_default_features,
+                                                        &specs)?;
+
+    let target = match opts.target {
+        TreeTarget::All => None,
+        TreeTarget::Host => {
+            let host = ws.config().rustc()?.host.clone();
+            let cfg = target_cfg(ws, None)?;
+            Some((host, cfg))
+        }
+        TreeTarget::Triple(ref triple) => {
+            Some((triple.clone(), target_cfg(ws, Some(&triple[..]))?))
+        }
+    };
+    let graph = build_graph(ws, &resolve, &packages, opts, &target)?;
+
+    let roots = if !opts.packages.is_empty() {
+        opts.packages.iter().map(|spec| resolve.query(spec))
+            .collect::<CargoResult<Vec<_>>>()?
+    } else if let Some(current) = ws.current_opt() {
+        vec![current.package_id()]
+    } else {
+        ws.members().map(|p| p.package_id()).collect()
+    };
+
+    // Several trees are separated by an empty line.
+    if opts.duplicates {
+        let inverted = invert(&graph);
+        for (i, id) in duplicates(&graph, &roots).into_iter().enumerate() {
+            if i > 0 {
+                println!("");
+            }
+            print_tree(&inverted, id, opts);
+        }
+    } else if let Some(ref spec) = opts.invert {
+        let inverted = invert(&graph);
+        print_tree(&inverted, resolve.query(spec)?, opts);
+    } else {
+        for (i, root) in roots.into_iter().enumerate() {
+            if i > 0 {
+                println!("");
+            }
+            print_tree(&graph, root, opts);
+        }
+    }
+    Ok(())
+}
+
+fn target_cfg(ws: &Workspace, triple: Option<&str>) -> CargoResult<Vec<Cfg>> {
+    let mut process = ws.config().rustc()?.process();
+    process.arg("-")
+           .arg("--crate-name").arg("___")
+           .arg("--print=cfg")
+           .env_remove("RUST_LOG");
+    if let Some(triple) = triple {
+        process.arg("--target").arg(triple);
+    }
+    let output = process.exec_with_output().chain_error(|| {
+        human(format!("failed to run `rustc` to learn about \
+                       target-specific information"))
+    })?;
+    let output = str::from_utf8(&output.stdout).map_err(|_| {
+        human("output of `rustc --print=cfg` was not valid utf-8")
+    })?;
+    output.lines().map(Cfg::from_str).collect()
+}
+
+fn build_graph<'a>(ws: &Workspace,
+                   resolve: &'a Resolve,
+                   packages: &'a PackageSet,
+                   opts: &TreeOptions,
+                   target: &Option<(String, Vec<Cfg>)>)
+                   -> CargoResult<Graph<'a>> {
+    let members = ws.members().map(|p| p.package_id().clone())
+                    .collect::<HashSet<_>>();
+    let mut graph = HashMap::new();
+    for id in resolve.iter() {
+        let pkg = packages.get(id)?;
+        let mut edges: Vec<Edge> = Vec::new();
+        for dep_id in resolve.deps(id) {
+            let deps = pkg.dependencies().iter().filter(|d| {
+                resolve.dep_matches_id(d, dep_id)
+            }).filter(|d| {
+                match d.kind() {
+                    Kind::Normal => opts.edges.normal,
+                    Kind::Build => opts.edges.build,
+                    // Dev-dependencies are only resolved for members.
+                    Kind::Development => opts.edges.dev && members.contains(id),
+                }
+            }).filter(|d| {
+                !d.is_optional() ||
+                    resolve.is_optional_dep_activated(id, d.name_in_toml())
+            }).filter(|d| {
+                match (d.platform(), target.as_ref()) {
+                    (Some(p), Some(&(ref name, ref cfg))) => {
+                        p.matches(name, Some(&cfg[..]))
+                    }
+                    _ => true,
+                }
+            });
+            for dep in deps {
+                let features = edge_features(resolve, pkg, dep,
+                                             packages.get(dep_id)?);
+                match edges.iter_mut().find(|e| e.kind == dep.kind() &&
+                                                e.to == dep_id) {
+                    Some(edge) => {
+                        edge.features.extend(features);
+                        edge.features.sort();
+                        edge.features.dedup();
+                    }
+                    None => {
+                        edges.push(Edge {
+                            kind: dep.kind(),
+                            to: dep_id,
+                            features: features,
+                        });
+                    }
+                }
+            }
+        }
+        edges.sort_by(|a, b| a.to.cmp(b.to));
+        graph.insert(id, edges);
+    }
+    Ok(graph)
+}
+
+/// Returns the features which the dependency `dep` of `parent` enables on
+/// `child`, either directly or through features of `parent`.
+fn edge_features(resolve: &Resolve, parent: &Package, dep: &Dependency,
+                 child: &Package) -> Vec<String> {
+    let mut features = dep.features().iter().cloned().collect::<BTreeSet<_>>();
+    if dep.uses_default_features() &&
+       child.summary().features().contains_key("default") {
+        features.insert("default".to_string());
+    }
+    let parent_features = parent.summary().features();
+    for feature in resolve.features(parent.package_id()) {
+        let values = match parent_features.get(feature) {
+            Some(values) => values,
+            None => continue,
+        };
+        for value in values {
+            let mut parts = value.splitn(2, '/');
+            let name = parts.next().unwrap().trim_right_matches('?');
+            match parts.next() {
+                Some(f) if name == dep.name_in_toml() => {
+                    features.insert(f.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+    features.into_iter().collect()
+}
+
+fn invert<'a>(graph: &Graph<'a>) -> Graph<'a> {
+    let mut ret: Graph<'a> = HashMap::new();
+    for (&from, edges) in graph {
+        ret.entry(from).or_insert(Vec::new());
+        for edge in edges {
+            ret.entry(edge.to).or_insert(Vec::new()).push(Edge {
+                kind: edge.kind,
+                to: from,
+                features: edge.features.clone(),
+            });
+        }
+    }
+    for edges in ret.values_mut() {
+        edges.sort_by(|a, b| a.to.cmp(b.to));
+    }
+    ret
+}
+
+/// Returns all packages reachable from `roots` whose name is shared with
+/// another reachable package.
+fn duplicates<'a>(graph: &Graph<'a>, roots: &[&'a PackageId]) -> Vec<&'a PackageId> {
+    let mut reachable = HashSet::new();
+    let mut stack = roots.to_vec();
+    while let Some(id) = stack.pop() {
+        if !reachable.insert(id) {
+            continue
+        }
+        for edge in graph.get(id).into_iter().flat_map(|e| e) {
+            stack.push(edge.to);
+        }
+    }
+
+    let mut by_name = BTreeMap::new();
+    for id in reachable {
+        by_name.entry(id.name()).or_insert(Vec::new()).push(id);
+    }
+    let mut ret = Vec::new();
+    for (_, mut ids) in by_name {
+        if ids.len() > 1 {
+            ids.sort();
+            ret.extend(ids);
+        }
+    }
+    ret
+}
+
+fn print_tree<'a>(graph: &Graph<'a>, root: &'a PackageId, opts: &TreeOptions) {
+    let mut visited = HashSet::new();
+    let mut levels_continue = Vec::new();
+    print_package(graph, root, &[], opts, &mut visited, &mut levels_continue);
+}
+
+fn print_package<'a>(graph: &Graph<'a>,
+                     id: &'a PackageId,
+                     features: &[String],
+                     opts: &TreeOptions,
+                     visited: &mut HashSet<&'a PackageId>,
+                     levels_continue: &mut Vec<bool>) {
+    let new = visited.insert(id);
+    let mut line = String::new();
+    match opts.prefix {
+        Prefix::Indent => {
+            if let Some((&last_continues, rest)) = levels_continue.split_last() {
+                for &continues in rest {
+                    line.push_str(if continues { "│   " } else { "    " });
+                }
+                line.push_str(if last_continues { "├── " } else { "└── " });
+            }
+        }
+        Prefix::Depth => line.push_str(&levels_continue.len().to_string()),
+        Prefix::None => {}
+    }
+    line.push_str(&id.to_string());
+    if opts.edges.features && !features.is_empty() {
+        line.push_str(&format!(" [features: {}]", features.join(", ")));
+    }
+
+    let edges = graph.get(id).map(|e| &e[..]).unwrap_or(&[]);
+    if !new && !edges.is_empty() {
+        // Packages are only expanded the first time they're printed.
+        println!("{} (*)", line);
+        return
+    }
+    println!("{}", line);
+
+    for &(ref kind, header) in KINDS.iter() {
+        let edges = edges.iter().filter(|e| e.kind == *kind).collect::<Vec<_>>();
+        if edges.is_empty() {
+            continue
+        }
+        if !header.is_empty() && opts.prefix == Prefix::Indent {
+            let mut line = String::new();
+            for &continues in levels_continue.iter() {
+                line.push_str(if continues { "│   " } else { "    " });
+            }
+            println!("{}{}", line, header);
+        }
+        for (i, edge) in edges.iter().enumerate() {
+            levels_continue.push(i + 1 < edges.len());
+            print_package(graph, edge.to, &edge.features, opts, visited,
+                          levels_continue);
+            levels_continue.pop();
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use std::io::prelude::*;
+use std::path::Path;
 
 use toml;
 
 use core::{Resolve, resolver, Workspace};
-use core::resolver::WorkspaceResolve;
+use core::resolver::{WorkspaceResolve, ResolveVersion};
 use util::{CargoResult, ChainError, human, Filesystem};
 use util::toml as cargo_toml;
 
@@ -21,14 +22,78 @@ pub fn load_pkg_lockfile(ws: &Workspace) -> CargoResult<Option<Resolve>> {
     })?;
 
     (|| {
-        let resolve = cargo_toml::parse(&s, f.path(), ws.config())?;
-        let v: resolver::EncodableResolve = resolve.try_into()?;
-        Ok(Some(v.into_resolve(ws)?))
+        let (ours, theirs) = match split_conflict(&s)? {
+            Some(sides) => sides,
+            None => return Ok(Some(parse_resolve(&s, f.path(), ws)?)),
+        };
+        let ours = parse_resolve(&ours, f.path(), ws).chain_error(|| {
+            human("failed to parse our side of the merge conflict")
+        })?;
+        let theirs = parse_resolve(&theirs, f.path(), ws).chain_error(|| {
+            human("failed to parse their side of the merge conflict")
+        })?;
+        let merged = Resolve::merge_conflict(&ours, &theirs).chain_error(|| {
+            human("the two sides of the merge conflict cannot be reconciled")
+        })?;
+        ws.config().shell().warn("Cargo.lock contains merge conflicts, \
+                                  resolving them by combining both sides")?;
+        Ok(Some(merged))
     }).chain_error(|| {
         human(format!("failed to parse lock file at: {}", f.path().display()))
     })
 }
 
+fn parse_resolve(s: &str, path: &Path, ws: &Workspace) -> CargoResult<Resolve> {
+    let resolve = cargo_toml::parse(s, path, ws.config())?;
+    let v: resolver::EncodableResolve = resolve.try_into()?;
+    v.into_resolve(ws)
+}
+
+/// Splits a lock file containing git merge conflict markers into our and
+/// their side of the conflict, or returns `None` if there are no markers.
+///
+/// The common ancestor section written with `merge.conflictStyle = diff3` is
+/// dropped.
+fn split_conflict(s: &str) -> CargoResult<Option<(String, String)>> {
+    #[derive(PartialEq)]
+    enum Section { Both, Ours, Base, Theirs }
+
+    if !s.lines().any(|l| l.starts_with("<<<<<<<")) {
+        return Ok(None)
+    }
+
+    let mut section = Section::Both;
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    for line in s.lines() {
+        let next = if line.starts_with("<<<<<<<") && section == Section::Both {
+            Section::Ours
+        } else if line.starts_with("|||||||") && section == Section::Ours {
+            Section::Base
+        } else if line.starts_with("=======") &&
+                  (section == Section::Ours || section == Section::Base) {
+            Section::Theirs
+        } else if line.starts_with(">>>>>>>") && section == Section::Theirs {
+            Section::Both
+        } else {
+            if section == Section::Both || section == Section::Ours {
+                ours.push_str(line);
+                ours.push_str("\n");
+            }
+            if section == Section::Both || section == Section::Theirs {
+                theirs.push_str(line);
+                theirs.push_str("\n");
+            }
+            continue
+        };
+        section = next;
+    }
+    if section != Section::Both {
+        bail!("merge conflict markers in the lock file are incomplete")
+    }
+    Ok(Some((ours, theirs)))
+}
+
 pub fn write_pkg_lockfile(ws: &Workspace, resolve: &Resolve) -> CargoResult<()> {
     // Load the original lockfile if it exists.
     let ws_root = Filesystem::new(ws.root().to_path_buf());
@@ -37,14 +102,51 @@ pub fn write_pkg_lockfile(ws: &Workspace, resolve: &Resolve) -> CargoResult<()>
         let mut s = String::new();
         f.read_to_string(&mut s)?;
         Ok(s)
-    });
+    }).ok();
+    let orig = orig.as_ref().map(|s| &s[..]);
+
+    let mut out = serialize_resolve(ws, resolve, orig);
+
+    // If the lockfile contents haven't changed so don't rewrite it. This is
+    // helpful on read-only filesystems.
+    if let Some(orig) = orig {
+        if out == orig {
+            return Ok(())
+        }
+    }
+
+    // As the lockfile is rewritten anyway, take the opportunity to move it to
+    // the current format.
+    if resolve.version() < ResolveVersion::default() {
+        let mut resolve = resolve.clone();
+        resolve.set_version(ResolveVersion::default());
+        out = serialize_resolve(ws, &resolve, orig);
+    }
+
+    if !ws.config().lock_update_allowed() {
+        let flag = if ws.config().network_allowed() {"--frozen"} else {"--locked"};
+        bail!("the lock file needs to be updated but {} was passed to \
+               prevent this", flag);
+    }
+
+    // Ok, if that didn't work just write it out
+    ws_root.open_rw("Cargo.lock", ws.config(), "Cargo.lock file").and_then(|mut f| {
+        f.file().set_len(0)?;
+        f.write_all(out.as_bytes())?;
+        Ok(())
+    }).chain_error(|| {
+        human(format!("failed to write {}",
+                      ws.root().join("Cargo.lock").display()))
+    })
+}
 
+fn serialize_resolve(ws: &Workspace, resolve: &Resolve, orig: Option<&str>)
+                     -> String {
     // Forward compatibility: if `orig` uses rootless format
-    // from the future, do the same.
-    let use_root_key = if let Ok(ref orig) = orig {
-        !orig.starts_with("[[package]]")
-    } else {
-        true
+    // from the future, do the same. The current format never has a root.
+    let use_root_key = resolve.version() == ResolveVersion::V1 && match orig {
+        Some(orig) => !orig.starts_with("[[package]]"),
+        None => true,
     };
 
     let toml = toml::Value::try_from(WorkspaceResolve {
@@ -55,6 +157,10 @@ pub fn write_pkg_lockfile(ws: &Workspace, resolve: &Resolve) -> CargoResult<()>
 
     let mut out = String::new();
 
+    if let Some(version) = toml.get("version") {
+        out.push_str(&format!("version = {}\n\n", version));
+    }
+
     // Note that we do not use e.toml.to_string() as we want to control the
     // exact format the toml is in to ensure pretty diffs between updates to the
     // lockfile.
@@ -71,37 +177,26 @@ pub fn write_pkg_lockfile(ws: &Workspace, resolve: &Resolve) -> CargoResult<()>
         emit_package(dep, &mut out);
     }
 
+    if let Some(patch) = toml.get("patch") {
+        let list = patch["unused"].as_array().unwrap();
+        for entry in list {
+            out.push_str("[[patch.unused]]\n");
+            emit_package(entry.as_table().unwrap(), &mut out);
+            out.push_str("\n");
+        }
+    }
+
     if let Some(meta) = toml.get("metadata") {
         out.push_str("[metadata]\n");
         out.push_str(&meta.to_string());
     }
 
-    // If the lockfile contents haven't changed so don't rewrite it. This is
-    // helpful on read-only filesystems.
-    if let Ok(orig) = orig {
-        if has_crlf_line_endings(&orig) {
+    if let Some(orig) = orig {
+        if has_crlf_line_endings(orig) {
             out = out.replace("\n", "\r\n");
         }
-        if out == orig {
-            return Ok(())
-        }
-    }
-
-    if !ws.config().lock_update_allowed() {
-        let flag = if ws.config().network_allowed() {"--frozen"} else {"--locked"};
-        bail!("the lock file needs to be updated but {} was passed to \
-               prevent this", flag);
     }
-
-    // Ok, if that didn't work just write it out
-    ws_root.open_rw("Cargo.lock", ws.config(), "Cargo.lock file").and_then(|mut f| {
-        f.file().set_len(0)?;
-        f.write_all(out.as_bytes())?;
-        Ok(())
-    }).chain_error(|| {
-        human(format!("failed to write {}",
-                      ws.root().join("Cargo.lock").display()))
-    })
+    out
 }
 
 fn has_crlf_line_endings(s: &str) -> bool {
@@ -121,6 +216,10 @@ fn emit_package(dep: &toml::value::Table, out: &mut String) {
         out.push_str(&format!("source = {}\n", &dep["source"]));
     }
 
+    if dep.contains_key("checksum") {
+        out.push_str(&format!("checksum = {}\n", &dep["checksum"]));
+    }
+
     if let Some(ref s) = dep.get("dependencies") {
         let slice = s.as_array().unwrap();
 
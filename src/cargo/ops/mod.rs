@@ -12,7 +12,8 @@ pub use self::cargo_new::{new, init, NewOptions, VersionControl};
 pub use self::cargo_doc::{doc, DocOptions};
 pub use self::cargo_generate_lockfile::{generate_lockfile};
 pub use self::cargo_generate_lockfile::{update_lockfile};
-pub use self::cargo_generate_lockfile::UpdateOptions;
+pub use self::cargo_generate_lockfile::{UpdateOptions, PackageChange, ChangeKind};
+pub use self::cargo_generate_lockfile::resolve_changes;
 pub use self::lockfile::{load_pkg_lockfile, write_pkg_lockfile};
 pub use self::cargo_test::{run_tests, run_benches, TestOptions};
 pub use self::cargo_package::{package, PackageOpts};
@@ -23,6 +24,7 @@ pub use self::cargo_fetch::fetch;
 pub use self::cargo_pkgid::pkgid;
 pub use self::resolve::{resolve_ws, resolve_ws_precisely, resolve_with_previous};
 pub use self::cargo_output_metadata::{output_metadata, OutputMetadataOptions, ExportInfo};
+pub use self::cargo_tree::{tree, TreeOptions, TreeTarget, EdgeKinds, Prefix};
 
 mod cargo_clean;
 mod cargo_compile;
@@ -38,6 +40,7 @@ mod cargo_read_manifest;
 mod cargo_run;
 mod cargo_rustc;
 mod cargo_test;
+mod cargo_tree;
 mod lockfile;
 mod registry;
 mod resolve;
@@ -38,6 +38,7 @@ pub struct PublishOpts<'cfg> {
     pub allow_dirty: bool,
     pub jobs: Option<u32>,
     pub dry_run: bool,
+    pub registry: Option<String>,
 }
 
 pub fn publish(ws: &Workspace, opts: &PublishOpts) -> CargoResult<()> {
@@ -48,10 +49,12 @@ pub fn publish(ws: &Workspace, opts: &PublishOpts) -> CargoResult<()> {
                `{}` is marked as unpublishable", pkg.name());
     }
 
-    let (mut registry, reg_id) = registry(opts.config,
-                                               opts.token.clone(),
-                                               opts.index.clone())?;
-    verify_dependencies(pkg, &reg_id)?;
+    let (mut registry, reg_id, allowed_registries) =
+        registry(opts.config,
+                 opts.token.clone(),
+                 opts.index.clone(),
+                 opts.registry.clone())?;
+    verify_dependencies(pkg, &reg_id, allowed_registries.as_ref())?;
 
     // Prepare a tarball, with a non-surpressable warning if metadata
     // is missing since this is being put online.
@@ -66,14 +69,33 @@ pub fn publish(ws: &Workspace, opts: &PublishOpts) -> CargoResult<()> {
 
     // Upload said tarball to the specified destination
     opts.config.shell().status("Uploading", pkg.package_id().to_string())?;
-    transmit(opts.config, pkg, tarball.file(), &mut registry, opts.dry_run)?;
+    transmit(opts.config, pkg, tarball.file(), &mut registry, &reg_id,
+             opts.dry_run)?;
 
     Ok(())
 }
 
-fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
+fn verify_dependencies(pkg: &Package,
+                       registry_src: &SourceId,
+                       allowed_registries: Option<&Vec<String>>)
                        -> CargoResult<()> {
+    let registry_name = if registry_src.is_default_registry() {
+        "crates.io".to_string()
+    } else {
+        format!("registry `{}`", registry_src.url())
+    };
     for dep in pkg.dependencies().iter() {
+        if let Some(rename) = dep.rename() {
+            bail!("crates cannot be published with renamed dependencies \
+                   as the registry index doesn't support them yet\n\
+                   dependency `{}` is a renamed copy of `{}`",
+                  rename, dep.name())
+        }
+        if dep.artifact().is_some() {
+            bail!("crates cannot be published with artifact dependencies \
+                   as the registry index doesn't support them yet\n\
+                   dependency `{}` is an artifact dependency", dep.name())
+        }
         if dep.source_id().is_path() {
             if !dep.specified_req() {
                 bail!("all path dependencies must have a version specified \
@@ -81,11 +103,31 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
                        a version", dep.name())
             }
         } else if dep.source_id() != registry_src {
-            bail!("crates cannot be published to crates.io with dependencies sourced from \
-                   a repository\neither publish `{}` as its own crate on crates.io and \
-                   specify a crates.io version as a dependency or pull it into this \
-                   repository and specify it with a path and version\n(crate `{}` has \
-                   repository path `{}`)", dep.name(), dep.name(),  dep.source_id());
+            if dep.source_id().is_registry() {
+                // Registries record where each dependency comes from in their
+                // index, and list which other registries they allow in their
+                // configuration. Without such a list crates.io only allows
+                // crates to depend on other crates.io crates.
+                let allowed = match allowed_registries {
+                    Some(allowed) => allowed.iter().any(|r| {
+                        r.to_url().map(|u| u == *dep.source_id().url())
+                                  .unwrap_or(false)
+                    }),
+                    None => !registry_src.is_default_registry(),
+                };
+                if !allowed {
+                    bail!("crates cannot be published to {} with \
+                           dependencies from registries it does not allow\n\
+                           dependency `{}` is from registry `{}`",
+                          registry_name, dep.name(), dep.source_id().url())
+                }
+                continue
+            }
+            bail!("crates cannot be published to {0} with dependencies sourced from \
+                   a repository\neither publish `{1}` as its own crate on {0} and \
+                   specify a version from it as a dependency or pull it into this \
+                   repository and specify it with a path and version\n(crate `{1}` has \
+                   repository path `{2}`)", registry_name, dep.name(), dep.source_id());
         }
     }
     Ok(())
@@ -95,10 +137,12 @@ fn transmit(config: &Config,
             pkg: &Package,
             tarball: &File,
             registry: &mut Registry,
+            registry_id: &SourceId,
             dry_run: bool) -> CargoResult<()> {
     let deps = pkg.dependencies().iter().map(|dep| {
         NewCrateDependency {
             optional: dep.is_optional(),
+            public: dep.is_public(),
             default_features: dep.uses_default_features(),
             name: dep.name().to_string(),
             features: dep.features().to_vec(),
@@ -109,6 +153,12 @@ fn transmit(config: &Config,
                 Kind::Build => "build",
                 Kind::Development => "dev",
             }.to_string(),
+            registry: if dep.source_id().is_registry() &&
+                         dep.source_id() != registry_id {
+                Some(dep.source_id().url().to_string())
+            } else {
+                None
+            },
         }
     }).collect::<Vec<NewCrateDependency>>();
     let manifest = pkg.manifest();
@@ -149,6 +199,7 @@ fn transmit(config: &Config,
         license: license.clone(),
         license_file: license_file.clone(),
         badges: badges.clone(),
+        rust_version: manifest.rust_version().map(|s| s.to_string()),
     }, tarball);
 
     match publish {
@@ -179,34 +230,58 @@ fn transmit(config: &Config,
     }
 }
 
-pub fn registry_configuration(config: &Config) -> CargoResult<RegistryConfig> {
-    let index = config.get_string("registry.index")?.map(|p| p.val);
-    let token = config.get_string("registry.token")?.map(|p| p.val);
+pub fn registry_configuration(config: &Config,
+                              registry: Option<String>)
+                              -> CargoResult<RegistryConfig> {
+    let (index, token) = match registry {
+        Some(registry) => {
+            let index = config.get_registry_index(&registry)?.to_string();
+            let token = config.get_string(&format!("registries.{}.token",
+                                                   registry))?;
+            (Some(index), token.map(|p| p.val))
+        }
+        None => {
+            let index = config.get_string("registry.index")?;
+            let token = config.get_string("registry.token")?;
+            (index.map(|p| p.val), token.map(|p| p.val))
+        }
+    };
     Ok(RegistryConfig { index: index, token: token })
 }
 
+/// Returns a handle to the API of the registry selected by `index` or
+/// `registry`, along with its source and the other registries which its
+/// index allows dependencies from.
 pub fn registry(config: &Config,
                 token: Option<String>,
-                index: Option<String>) -> CargoResult<(Registry, SourceId)> {
+                index: Option<String>,
+                registry: Option<String>)
+                -> CargoResult<(Registry, SourceId, Option<Vec<String>>)> {
+    if index.is_some() && registry.is_some() {
+        bail!("both `--index` and `--registry` should not be set at the same time")
+    }
+
     // Parse all configuration options
     let RegistryConfig {
         token: token_config,
         index: _index_config,
-    } = registry_configuration(config)?;
+    } = registry_configuration(config, registry.clone())?;
     let token = token.or(token_config);
-    let sid = match index {
-        Some(index) => SourceId::for_registry(&index.to_url()?),
-        None => SourceId::crates_io(config)?,
+    let sid = match (index, registry) {
+        (Some(index), _) => SourceId::for_registry(&index.to_url()?),
+        (None, Some(registry)) => SourceId::alt_registry(config, &registry)?,
+        (None, None) => SourceId::crates_io(config)?,
     };
-    let api_host = {
+    let index_config = {
         let mut src = RegistrySource::remote(&sid, config);
         src.update().chain_error(|| {
             human(format!("failed to update {}", sid))
         })?;
-        (src.config()?).unwrap().api
+        (src.config()?).unwrap()
     };
     let handle = http_handle(config)?;
-    Ok((Registry::new_handle(api_host, token, handle), sid))
+    Ok((Registry::new_handle(index_config.api, token, handle), sid,
+        index_config.allowed_registries))
 }
 
 /// Create a new HTTP handle with appropriate global configuration for cargo.
@@ -283,8 +358,19 @@ pub fn http_timeout(config: &Config) -> CargoResult<Option<i64>> {
     Ok(env::var("HTTP_TIMEOUT").ok().and_then(|s| s.parse().ok()))
 }
 
-pub fn registry_login(config: &Config, token: String) -> CargoResult<()> {
-    let RegistryConfig { index, token: _ } = registry_configuration(config)?;
+pub fn registry_login(config: &Config,
+                      token: String,
+                      registry: Option<String>) -> CargoResult<()> {
+    // Alternate registries keep their index in `[registries]`, so only the
+    // token needs to be written.
+    if let Some(registry) = registry {
+        let key = format!("registries.{}.token", registry);
+        let p = config.cwd().to_path_buf();
+        return config::set_config(config, Location::Global, &key,
+                                  ConfigValue::String(token, p))
+    }
+
+    let RegistryConfig { index, token: _ } = registry_configuration(config, None)?;
     let mut map = HashMap::new();
     let p = config.cwd().to_path_buf();
     if let Some(index) = index {
@@ -303,6 +389,7 @@ pub struct OwnersOptions {
     pub to_add: Option<Vec<String>>,
     pub to_remove: Option<Vec<String>>,
     pub list: bool,
+    pub registry: Option<String>,
 }
 
 pub fn modify_owners(config: &Config, opts: &OwnersOptions) -> CargoResult<()> {
@@ -315,8 +402,9 @@ pub fn modify_owners(config: &Config, opts: &OwnersOptions) -> CargoResult<()> {
         }
     };
 
-    let (mut registry, _) = registry(config, opts.token.clone(),
-                                          opts.index.clone())?;
+    let (mut registry, _, _) = registry(config, opts.token.clone(),
+                                        opts.index.clone(),
+                                        opts.registry.clone())?;
 
     if let Some(ref v) = opts.to_add {
         let v = v.iter().map(|s| &s[..]).collect::<Vec<_>>();
@@ -359,7 +447,8 @@ pub fn yank(config: &Config,
             version: Option<String>,
             token: Option<String>,
             index: Option<String>,
-            undo: bool) -> CargoResult<()> {
+            undo: bool,
+            reg: Option<String>) -> CargoResult<()> {
     let name = match krate {
         Some(name) => name,
         None => {
@@ -373,7 +462,7 @@ pub fn yank(config: &Config,
         None => bail!("a version must be specified to yank")
     };
 
-    let (mut registry, _) = registry(config, token, index)?;
+    let (mut registry, _, _) = registry(config, token, index, reg)?;
 
     if undo {
         config.shell().status("Unyank", format!("{}:{}", name, version))?;
@@ -393,7 +482,8 @@ pub fn yank(config: &Config,
 pub fn search(query: &str,
               config: &Config,
               index: Option<String>,
-              limit: u8) -> CargoResult<()> {
+              limit: u8,
+              reg: Option<String>) -> CargoResult<()> {
     fn truncate_with_ellipsis(s: &str, max_length: usize) -> String {
         if s.len() < max_length {
             s.to_string()
@@ -402,7 +492,7 @@ pub fn search(query: &str,
         }
     }
 
-    let (mut registry, _) = registry(config, None, index)?;
+    let (mut registry, _, _) = registry(config, None, index, reg)?;
     let (crates, total_crates) = registry.search(query, limit).map_err(|e| {
         human(format!("failed to retrieve search results from the registry: {}", e))
     })?;
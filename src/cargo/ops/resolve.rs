@@ -75,7 +75,7 @@ pub fn resolve_ws_precisely<'a>(ws: &Workspace<'a>,
     let resolved_with_overrides =
     ops::resolve_with_previous(&mut registry, ws,
                                method, resolve.as_ref(), None,
-                               specs)?;
+                               specs, resolve.is_none())?;
 
     let packages = get_resolved_packages(&resolved_with_overrides, registry);
 
@@ -87,7 +87,14 @@ fn resolve_with_registry(ws: &Workspace, registry: &mut PackageRegistry)
     let prev = ops::load_pkg_lockfile(ws)?;
     let resolve = resolve_with_previous(registry, ws,
                                         Method::Everything,
-                                        prev.as_ref(), None, &[])?;
+                                        prev.as_ref(), None, &[], true)?;
+
+    for id in resolve.unused_patches() {
+        ws.config().shell().warn(
+            format!("patch `{}` was not used in the crate graph", id)
+        )?;
+    }
+    warn_yanked(ws, registry, &resolve)?;
 
     if !ws.is_ephemeral() {
         ops::write_pkg_lockfile(ws, &resolve)?;
@@ -95,6 +102,28 @@ fn resolve_with_registry(ws: &Workspace, registry: &mut PackageRegistry)
     Ok(resolve)
 }
 
+/// Warns about every package in `resolve` which has been yanked. Yanked
+/// versions are never picked by the resolver, so these must all have been
+/// locked in `Cargo.lock`.
+fn warn_yanked(ws: &Workspace,
+               registry: &mut PackageRegistry,
+               resolve: &Resolve) -> CargoResult<()> {
+    for id in resolve.iter() {
+        let yanked = match registry.yanked(id)? {
+            Some(yanked) => yanked,
+            None => continue,
+        };
+        let reason = match yanked.reason {
+            Some(reason) => format!(" ({})", reason),
+            None => String::new(),
+        };
+        ws.config().shell().warn(format!(
+            "package `{}` in Cargo.lock has been yanked{}, consider running \
+             `cargo update --yanked` to move to a version which isn't",
+            id, reason))?;
+    }
+    Ok(())
+}
 
 /// Resolve all dependencies for a package using an optional previous instance
 /// of resolve to guide the resolution process.
@@ -105,12 +134,17 @@ fn resolve_with_registry(ws: &Workspace, registry: &mut PackageRegistry)
 ///
 /// The previous resolve normally comes from a lockfile. This function does not
 /// read or write lockfiles from the filesystem.
+///
+/// The `register_patches` flag indicates whether the `[patch]` section of the
+/// workspace should be registered with `registry`, which should only happen
+/// once per registry.
 pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
                                  ws: &Workspace,
                                  method: Method,
                                  previous: Option<&'a Resolve>,
                                  to_avoid: Option<&HashSet<&'a PackageId>>,
-                                 specs: &[PackageIdSpec])
+                                 specs: &[PackageIdSpec],
+                                 register_patches: bool)
                                  -> CargoResult<Resolve> {
     // Here we place an artificial limitation that all non-registry sources
     // cannot be locked at more than one revision. This means that if a git
@@ -155,11 +189,49 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
                         .cloned().collect();
             registry.register_lock(node.clone(), deps);
         }
+        for node in r.unused_patches() {
+            if keep(&node, to_avoid, &to_avoid_sources) {
+                registry.register_lock(node.clone(), Vec::new());
+            }
+        }
+    }
+
+    // Patches are registered with the registry up front so they're considered
+    // as candidates for all dependencies on the sources they patch. Each patch
+    // is locked to its previous version, if any, just like normal
+    // dependencies are.
+    if register_patches {
+        for (url, patches) in ws.root_patch() {
+            let patches = patches.iter().map(|dep| {
+                let previous = match previous {
+                    Some(r) => r,
+                    None => return dep.clone(),
+                };
+                let mut candidates = previous.iter()
+                                             .chain(previous.unused_patches());
+                let locked = candidates.find(|id| {
+                    keep(id, to_avoid, &to_avoid_sources) && dep.matches_id(id)
+                });
+                match locked {
+                    Some(id) => dep.clone().lock_to(id),
+                    None => dep.clone(),
+                }
+            }).collect::<Vec<_>>();
+            registry.patch(url, &patches)?;
+        }
     }
 
     let mut summaries = Vec::new();
     for member in ws.members() {
-        registry.add_sources(&[member.package_id().source_id().clone()])?;
+        // Members are already loaded, along with any fields they inherit from
+        // the workspace root, so they're handed over rather than read again.
+        let source_id = member.package_id().source_id();
+        if source_id.is_path() {
+            let source = PathSource::preload_with(member.clone(), ws.config());
+            registry.add_preloaded(Box::new(source));
+        } else {
+            registry.add_sources(&[source_id.clone()])?;
+        }
         let method_to_resolve = match method {
             // When everything for a workspace we want to be sure to resolve all
             // members in the workspace, so propagate the `Method::Everything`.
@@ -222,7 +294,12 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
         None => root_replace.to_vec(),
     };
 
-    let mut resolved = resolver::resolve(&summaries, &replace, registry)?;
+    let minimal_versions = ws.config().minimal_versions()?;
+    let mut resolved = resolver::resolve(&summaries, &replace, registry,
+                                         minimal_versions,
+                                         ws.resolve_behavior(),
+                                         ws.rust_version())?;
+    resolved.register_used_patches(registry.patches());
     if let Some(previous) = previous {
         resolved.merge_from(previous)?;
     }
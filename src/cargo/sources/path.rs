@@ -4,7 +4,7 @@ use std::path::{Path, PathBuf};
 
 use filetime::FileTime;
 use git2;
-use glob::Pattern;
+use glob::{Pattern, MatchOptions};
 
 use core::{Package, PackageId, Summary, SourceId, Source, Dependency, Registry};
 use ops;
@@ -53,6 +53,21 @@ impl<'cfg> PathSource<'cfg> {
         }
     }
 
+    /// Creates a new source holding only `pkg`, which has already been
+    /// loaded, such as a member of a workspace.
+    ///
+    /// The manifest isn't read again, which matters for members that inherit
+    /// fields from their workspace root.
+    pub fn preload_with(pkg: Package, config: &'cfg Config) -> PathSource<'cfg> {
+        let source_id = pkg.package_id().source_id().clone();
+        let path = pkg.root().to_path_buf();
+        PathSource {
+            updated: true,
+            packages: vec![pkg],
+            .. PathSource::new(&path, &source_id, config)
+        }
+    }
+
     pub fn root_package(&mut self) -> CargoResult<Package> {
         trace!("root_package; source={:?}", self);
 
@@ -86,6 +101,16 @@ impl<'cfg> PathSource<'cfg> {
     /// are relevant for building this package, but it also contains logic to
     /// use other methods like .gitignore to filter the list of files.
     pub fn list_files(&self, pkg: &Package) -> CargoResult<Vec<PathBuf>> {
+        self.list_files_and_changes(pkg).map(|(files, _)| files)
+    }
+
+    /// Like `list_files`, but also returns the files whose inclusion changed
+    /// when `include` and `exclude` started being matched like `.gitignore`
+    /// patterns rather than as plain globs, along with whether each is now
+    /// packaged.
+    pub fn list_files_and_changes(&self, pkg: &Package)
+                                  -> CargoResult<(Vec<PathBuf>,
+                                                  Vec<(PathBuf, bool)>)> {
         let root = pkg.root();
 
         let parse = |p: &String| {
@@ -94,26 +119,59 @@ impl<'cfg> PathSource<'cfg> {
             })
         };
 
-        let exclude = pkg.manifest()
-                         .exclude()
-                         .iter()
-                         .map(|p| parse(p))
-                         .collect::<Result<Vec<_>, _>>()?;
-
-        let include = pkg.manifest()
-                         .include()
-                         .iter()
-                         .map(|p| parse(p))
-                         .collect::<Result<Vec<_>, _>>()?;
-
-        let mut filter = |p: &Path| {
-            let relative_path = util::without_prefix(p, root).unwrap();
-            include.iter().any(|p| p.matches_path(relative_path)) || {
-                include.is_empty() &&
-                 !exclude.iter().any(|p| p.matches_path(relative_path))
+        let glob_exclude = pkg.manifest()
+                              .exclude()
+                              .iter()
+                              .map(|p| parse(p))
+                              .collect::<Result<Vec<_>, _>>()?;
+
+        let glob_include = pkg.manifest()
+                              .include()
+                              .iter()
+                              .map(|p| parse(p))
+                              .collect::<Result<Vec<_>, _>>()?;
+
+        let glob_should_package = |relative_path: &Path| {
+            glob_include.iter().any(|p| p.matches_path(relative_path)) || {
+                glob_include.is_empty() &&
+                 !glob_exclude.iter().any(|p| p.matches_path(relative_path))
+            }
+        };
+
+        let exclude = GitignoreList::new(pkg.manifest().exclude())?;
+        let include = GitignoreList::new(pkg.manifest().include())?;
+
+        let should_package = |relative_path: &Path| {
+            if include.is_empty() {
+                !exclude.matches(relative_path)
+            } else {
+                include.matches(relative_path)
             }
         };
 
+        // Patterns used to be matched as plain globs rather than like
+        // `.gitignore` patterns, so keep track of any file which is now
+        // treated differently than before.
+        let mut changes = Vec::new();
+        let files = {
+            let mut filter = |p: &Path| {
+                let relative_path = util::without_prefix(p, root).unwrap();
+                let packaged = should_package(relative_path);
+                if packaged != glob_should_package(relative_path) {
+                    changes.push((relative_path.to_path_buf(), packaged));
+                }
+                packaged
+            };
+            self.list_files_filtered(pkg, &mut filter)?
+        };
+        Ok((files, changes))
+    }
+
+    fn list_files_filtered(&self, pkg: &Package,
+                           filter: &mut FnMut(&Path) -> bool)
+                           -> CargoResult<Vec<PathBuf>> {
+        let root = pkg.root();
+
         // If this package is in a git repository, then we really do want to
         // query the git repository as it takes into account items such as
         // .gitignore. We're not quite sure where the git repository is,
@@ -133,7 +191,7 @@ impl<'cfg> PathSource<'cfg> {
                     let path = util::without_prefix(root, cur)
                                     .unwrap().join("Cargo.toml");
                     if index.get_path(&path, 0).is_some() {
-                        return self.list_files_git(pkg, repo, &mut filter);
+                        return self.list_files_git(pkg, repo, filter);
                     }
                 }
             }
@@ -146,7 +204,7 @@ impl<'cfg> PathSource<'cfg> {
                 None => break,
             }
         }
-        self.list_files_walk(pkg, &mut filter)
+        self.list_files_walk(pkg, filter)
     }
 
     fn list_files_git(&self, pkg: &Package, repo: git2::Repository,
@@ -310,6 +368,100 @@ impl<'cfg> PathSource<'cfg> {
     }
 }
 
+/// A list of `include` or `exclude` patterns from the manifest, matched with
+/// the semantics of `.gitignore` files:
+///
+/// * A leading `!` negates a pattern, so it matches again what an earlier
+///   pattern did. The last pattern matching a path wins.
+/// * A trailing `/` only matches directories.
+/// * A pattern containing any other `/` is anchored to the package root,
+///   while all others match a file or directory of that name at any depth.
+/// * A file is matched if any of its parent directories is, and negating a
+///   pattern can't match it again in that case.
+struct GitignoreList {
+    patterns: Vec<GitignorePattern>,
+}
+
+struct GitignorePattern {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl GitignoreList {
+    fn new(patterns: &[String]) -> CargoResult<GitignoreList> {
+        let patterns = patterns.iter().map(|p| {
+            let mut s = &p[..];
+            let negated = s.starts_with('!');
+            if negated {
+                s = &s[1..];
+            }
+            let dir_only = s.ends_with('/');
+            if dir_only {
+                s = &s[..s.len() - 1];
+            }
+            let anchored = s.contains('/');
+            if s.starts_with('/') {
+                s = &s[1..];
+            }
+            let pattern = Pattern::new(s).map_err(|e| {
+                human(format!("could not parse pattern `{}`: {}", p, e))
+            })?;
+            Ok(GitignorePattern {
+                pattern: pattern,
+                negated: negated,
+                dir_only: dir_only,
+                anchored: anchored,
+            })
+        }).collect::<CargoResult<Vec<_>>>()?;
+        Ok(GitignoreList { patterns: patterns })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns whether the file at `path`, relative to the package root, is
+    /// matched by this list.
+    fn matches(&self, path: &Path) -> bool {
+        let components = path.components().collect::<Vec<_>>();
+        let mut cur = PathBuf::new();
+        for (i, component) in components.iter().enumerate() {
+            cur.push(component.as_os_str());
+            if self.matches_one(&cur, i + 1 < components.len()) {
+                return true
+            }
+        }
+        false
+    }
+
+    fn matches_one(&self, path: &Path, is_dir: bool) -> bool {
+        let opts = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let mut matched = false;
+        for p in self.patterns.iter() {
+            if p.dir_only && !is_dir {
+                continue
+            }
+            let is_match = if p.anchored {
+                p.pattern.matches_path_with(path, &opts)
+            } else {
+                path.file_name().map(|name| {
+                    p.pattern.matches_path_with(Path::new(name), &opts)
+                }).unwrap_or(false)
+            };
+            if is_match {
+                matched = !p.negated;
+            }
+        }
+        matched
+    }
+}
+
 impl<'cfg> Debug for PathSource<'cfg> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "the paths source")
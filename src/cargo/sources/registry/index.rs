@@ -6,15 +6,16 @@ use std::path::Path;
 use serde_json;
 
 use core::dependency::{Dependency, DependencyInner, Kind};
-use core::{SourceId, Summary, PackageId, Registry};
+use core::{SourceId, Summary, PackageId, Registry, Yanked};
 use sources::registry::{RegistryPackage, RegistryDependency, INDEX_LOCK};
-use util::{CargoResult, ChainError, internal, Filesystem, Config};
+use util::{CargoResult, ChainError, internal, Filesystem, Config, ToUrl};
 
 pub struct RegistryIndex<'cfg> {
     source_id: SourceId,
     path: Filesystem,
     cache: HashMap<String, Vec<(Summary, bool)>>,
     hashes: HashMap<(String, String), String>, // (name, vers) => cksum
+    yank_reasons: HashMap<(String, String), String>, // (name, vers) => reason
     config: &'cfg Config,
     locked: bool,
 }
@@ -29,6 +30,7 @@ impl<'cfg> RegistryIndex<'cfg> {
             path: path.clone(),
             cache: HashMap::new(),
             hashes: HashMap::new(),
+            yank_reasons: HashMap::new(),
             config: config,
             locked: locked,
         }
@@ -47,6 +49,19 @@ impl<'cfg> RegistryIndex<'cfg> {
         }).map(|s| s.clone())
     }
 
+    /// Returns whether the specified PackageId has been yanked, along with the
+    /// reason listed in the index, if any.
+    pub fn yanked(&mut self, pkg: &PackageId) -> CargoResult<Option<Yanked>> {
+        let yanked = self.summaries(pkg.name())?.iter().any(|&(ref s, yanked)| {
+            yanked && s.package_id() == pkg
+        });
+        if !yanked {
+            return Ok(None)
+        }
+        let key = (pkg.name().to_string(), pkg.version().to_string());
+        Ok(Some(Yanked { reason: self.yank_reasons.get(&key).cloned() }))
+    }
+
     /// Parse the on-disk metadata for the package provided
     ///
     /// Returns a list of pairs of (summary, yanked) for the package name
@@ -115,7 +130,8 @@ impl<'cfg> RegistryIndex<'cfg> {
     fn parse_registry_package(&mut self, line: &str)
                               -> CargoResult<(Summary, bool)> {
         let RegistryPackage {
-            name, vers, cksum, deps, features, yanked
+            name, vers, cksum, deps, features, yanked, yanked_reason, links,
+            rust_version
         } = serde_json::from_str::<RegistryPackage>(line)?;
         let pkgid = PackageId::new(&name, &vers, &self.source_id)?;
         let deps: CargoResult<Vec<Dependency>> = deps.into_iter().map(|dep| {
@@ -123,7 +139,12 @@ impl<'cfg> RegistryIndex<'cfg> {
         }).collect();
         let deps = deps?;
         let summary = Summary::new(pkgid, deps, features)?;
-        let summary = summary.set_checksum(cksum.clone());
+        let summary = summary.set_checksum(cksum.clone())
+                             .set_links(links)
+                             .set_rust_version(rust_version);
+        if let Some(reason) = yanked_reason {
+            self.yank_reasons.insert((name.clone(), vers.clone()), reason);
+        }
         self.hashes.insert((name, vers), cksum);
         Ok((summary, yanked.unwrap_or(false)))
     }
@@ -132,10 +153,17 @@ impl<'cfg> RegistryIndex<'cfg> {
     fn parse_registry_dependency(&self, dep: RegistryDependency)
                                  -> CargoResult<Dependency> {
         let RegistryDependency {
-            name, req, features, optional, default_features, target, kind
+            name, req, features, optional, public, default_features, target,
+            kind, registry
         } = dep;
 
-        let dep = DependencyInner::parse(&name, Some(&req), &self.source_id, None)?;
+        // Dependencies from other registries list the index they come from,
+        // otherwise they're from this same registry.
+        let id = match registry {
+            Some(registry) => SourceId::for_registry(&registry.to_url()?),
+            None => self.source_id.clone(),
+        };
+        let dep = DependencyInner::parse(&name, Some(&req), &id, None)?;
         let kind = match kind.as_ref().map(|s| &s[..]).unwrap_or("") {
             "dev" => Kind::Development,
             "build" => Kind::Build,
@@ -155,6 +183,7 @@ impl<'cfg> RegistryIndex<'cfg> {
         let features = features.into_iter().filter(|s| !s.is_empty()).collect();
 
         Ok(dep.set_optional(optional)
+              .set_public(public.unwrap_or(false))
               .set_default_features(default_features)
               .set_features(features)
               .set_platform(platform)
@@ -107,7 +107,7 @@
 //!
 //! As new versions are published, new lines are appended to this file. The only
 //! modifications to this file that should happen over time are yanks of a
-//! particular version.
+//! particular version, optionally along with a `yanked_reason`.
 //!
 //! # Downloading Packages
 //!
@@ -165,7 +165,7 @@ use std::path::{PathBuf, Path};
 use flate2::read::GzDecoder;
 use tar::Archive;
 
-use core::{Source, SourceId, PackageId, Package, Summary, Registry};
+use core::{Source, SourceId, PackageId, Package, Summary, Registry, Yanked};
 use core::dependency::Dependency;
 use sources::PathSource;
 use util::{CargoResult, Config, internal, ChainError, FileLock, Filesystem};
@@ -194,6 +194,12 @@ pub struct RegistryConfig {
     /// API endpoint for the registry. This is what's actually hit to perform
     /// operations like yanks, owner modifications, publish new crates, etc.
     pub api: String,
+
+    /// Index URLs of the other registries which crates published here may
+    /// depend on. If missing, crates.io allows none of them and any other
+    /// registry allows all of them.
+    #[serde(rename = "allowed-registries", default)]
+    pub allowed_registries: Option<Vec<String>>,
 }
 
 #[derive(Deserialize)]
@@ -204,6 +210,9 @@ struct RegistryPackage {
     features: HashMap<String, Vec<String>>,
     cksum: String,
     yanked: Option<bool>,
+    yanked_reason: Option<String>,
+    links: Option<String>,
+    rust_version: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -212,9 +221,11 @@ struct RegistryDependency {
     req: String,
     features: Vec<String>,
     optional: bool,
+    public: Option<bool>,
     default_features: bool,
     target: Option<String>,
     kind: Option<String>,
+    registry: Option<String>,
 }
 
 pub trait RegistryData {
@@ -381,4 +392,8 @@ impl<'cfg> Source for RegistrySource<'cfg> {
     fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
         Ok(pkg.package_id().version().to_string())
     }
+
+    fn yanked(&mut self, pkg: &PackageId) -> CargoResult<Option<Yanked>> {
+        self.index.yanked(pkg)
+    }
 }
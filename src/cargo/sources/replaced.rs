@@ -1,4 +1,5 @@
 use core::{Source, Registry, PackageId, Package, Dependency, Summary, SourceId};
+use core::Yanked;
 use util::{CargoResult, ChainError, human};
 
 pub struct ReplacedSource<'cfg> {
@@ -61,4 +62,9 @@ impl<'cfg> Source for ReplacedSource<'cfg> {
         let id = id.with_source_id(&self.replace_with);
         self.inner.verify(&id)
     }
+
+    fn yanked(&mut self, id: &PackageId) -> CargoResult<Option<Yanked>> {
+        let id = id.with_source_id(&self.replace_with);
+        self.inner.yanked(&id)
+    }
 }
@@ -13,10 +13,11 @@ use std::str::FromStr;
 
 use rustc_serialize::{Encodable,Encoder};
 use toml;
+use url::Url;
 use core::shell::{Verbosity, ColorConfig};
 use core::MultiShell;
 use util::{CargoResult, CargoError, ChainError, Rustc, internal, human};
-use util::{Filesystem, LazyCell};
+use util::{Filesystem, LazyCell, ToUrl};
 use util::paths;
 
 use util::toml as cargo_toml;
@@ -34,6 +35,7 @@ pub struct Config {
     extra_verbose: Cell<bool>,
     frozen: Cell<bool>,
     locked: Cell<bool>,
+    minimal_versions: Cell<bool>,
 }
 
 impl Config {
@@ -51,6 +53,7 @@ impl Config {
             extra_verbose: Cell::new(false),
             frozen: Cell::new(false),
             locked: Cell::new(false),
+            minimal_versions: Cell::new(false),
         }
     }
 
@@ -234,6 +237,16 @@ impl Config {
         }
     }
 
+    /// Returns the index URL of the alternate registry `registry`, as
+    /// configured by `registries.<name>.index`.
+    pub fn get_registry_index(&self, registry: &str) -> CargoResult<Url> {
+        let key = format!("registries.{}.index", registry);
+        match self.get_string(&key)? {
+            Some(index) => index.val.to_url(),
+            None => bail!("no index found for registry: `{}`", registry),
+        }
+    }
+
     pub fn get_list(&self, key: &str)
                     -> CargoResult<Option<Value<Vec<(String, PathBuf)>>>> {
         match self.get(key)? {
@@ -389,6 +402,20 @@ impl Config {
         !self.frozen.get() && !self.locked.get()
     }
 
+    pub fn set_minimal_versions(&self, minimal: bool) {
+        self.minimal_versions.set(minimal);
+    }
+
+    /// Whether the resolver should prefer the lowest version of each
+    /// dependency, either because `--minimal-versions` was passed or because
+    /// `resolver.minimal-versions` is set in configuration.
+    pub fn minimal_versions(&self) -> CargoResult<bool> {
+        if self.minimal_versions.get() {
+            return Ok(true)
+        }
+        Ok(self.get_bool("resolver.minimal-versions")?.map(|v| v.val).unwrap_or(false))
+    }
+
     pub fn load_values(&self) -> CargoResult<HashMap<String, ConfigValue>> {
         let mut cfg = CV::Table(HashMap::new(), PathBuf::from("."));
 
@@ -768,9 +795,24 @@ pub fn set_config(cfg: &Config,
     let mut contents = String::new();
     let _ = file.read_to_string(&mut contents);
     let mut toml = cargo_toml::parse(&contents, file.path(), cfg)?;
-    toml.as_table_mut()
-        .unwrap()
-        .insert(key.to_string(), value.into_toml());
+    {
+        // Dotted keys such as `registries.foo.token` are set within nested
+        // tables, leaving any other values in those tables alone.
+        let mut parts = key.split('.').collect::<Vec<_>>();
+        let last = parts.pop().unwrap();
+        let mut table = toml.as_table_mut().unwrap();
+        for part in parts {
+            let parent = table;
+            let entry = parent.entry(part.to_string()).or_insert_with(|| {
+                toml::Value::Table(toml::value::Table::new())
+            });
+            table = entry.as_table_mut().chain_error(|| {
+                human(format!("expected `{}` to be a table in {}", part,
+                              file.path().display()))
+            })?;
+        }
+        table.insert(last.to_string(), value.into_toml());
+    }
 
     let contents = toml.to_string();
     file.seek(SeekFrom::Start(0))?;
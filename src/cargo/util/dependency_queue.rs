@@ -3,6 +3,10 @@
 //!
 //! This structure is used to store the dependency graph and dynamically update
 //! it to figure out when a dependency should be built.
+//!
+//! Each dependency edge is labeled with the artifact of the dependency that is
+//! waited on. A package may make some of its artifacts available before it has
+//! finished building, releasing the edges waiting on only those early.
 
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::{HashMap, HashSet};
@@ -11,20 +15,21 @@ use std::hash::Hash;
 pub use self::Freshness::{Fresh, Dirty};
 
 #[derive(Debug)]
-pub struct DependencyQueue<K: Eq + Hash, V> {
+pub struct DependencyQueue<K: Eq + Hash, A: Eq + Hash, V> {
     /// A list of all known keys to build.
     ///
-    /// The value of the hash map is list of dependencies which still need to be
-    /// built before the package can be built. Note that the set is dynamically
-    /// updated as more dependencies are built.
-    dep_map: HashMap<K, (HashSet<K>, V)>,
+    /// The value of the hash map is list of dependencies, along with the
+    /// artifact needed from each, which still need to be built before the
+    /// package can be built. Note that the set is dynamically updated as more
+    /// dependencies are built.
+    dep_map: HashMap<K, (HashSet<(K, A)>, V)>,
 
     /// A reverse mapping of a package to all packages that depend on that
-    /// package.
+    /// package, grouped by the artifact they need.
     ///
-    /// This map is statically known and does not get updated throughout the
-    /// lifecycle of the DependencyQueue.
-    reverse_dep_map: HashMap<K, HashSet<K>>,
+    /// Packages are removed from this map as the artifact they wait on becomes
+    /// available.
+    reverse_dep_map: HashMap<K, HashMap<A, HashSet<K>>>,
 
     /// A set of dirty packages.
     ///
@@ -52,9 +57,9 @@ impl Freshness {
     }
 }
 
-impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
+impl<K: Hash + Eq + Clone, A: Hash + Eq + Clone, V> DependencyQueue<K, A, V> {
     /// Creates a new dependency queue with 0 packages.
-    pub fn new() -> DependencyQueue<K, V> {
+    pub fn new() -> DependencyQueue<K, A, V> {
         DependencyQueue {
             dep_map: HashMap::new(),
             reverse_dep_map: HashMap::new(),
@@ -71,7 +76,7 @@ impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
                  fresh: Freshness,
                  key: K,
                  value: V,
-                 dependencies: &[K]) -> &mut V {
+                 dependencies: &[(K, A)]) -> &mut V {
         let slot = match self.dep_map.entry(key.clone()) {
             Occupied(v) => return &mut v.into_mut().1,
             Vacant(v) => v,
@@ -82,9 +87,11 @@ impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
         }
 
         let mut my_dependencies = HashSet::new();
-        for dep in dependencies {
-            assert!(my_dependencies.insert(dep.clone()));
+        for &(ref dep, ref artifact) in dependencies {
+            assert!(my_dependencies.insert((dep.clone(), artifact.clone())));
             let rev = self.reverse_dep_map.entry(dep.clone())
+                                          .or_insert_with(HashMap::new)
+                                          .entry(artifact.clone())
                                           .or_insert_with(HashSet::new);
             assert!(rev.insert(key.clone()));
         }
@@ -124,15 +131,39 @@ impl<K: Hash + Eq + Clone, V> DependencyQueue<K, V> {
     /// possibly allowing the next invocation of `dequeue` to return a package.
     pub fn finish(&mut self, key: &K, fresh: Freshness) {
         assert!(self.pending.remove(key));
-        let reverse_deps = match self.reverse_dep_map.get(key) {
+        let reverse_deps = match self.reverse_dep_map.remove(key) {
             Some(deps) => deps,
             None => return,
         };
-        for dep in reverse_deps.iter() {
+        for (artifact, deps) in reverse_deps {
+            self.release(key, &artifact, deps, fresh);
+        }
+    }
+
+    /// Indicate that the `artifact` of a package which is still being built
+    /// is available.
+    ///
+    /// Only the packages waiting on that artifact may be returned by `dequeue`
+    /// afterwards, the others still wait for `finish`.
+    pub fn finish_artifact(&mut self, key: &K, artifact: &A, fresh: Freshness) {
+        assert!(self.pending.contains(key));
+        let deps = match self.reverse_dep_map.get_mut(key) {
+            Some(deps) => deps.remove(artifact),
+            None => None,
+        };
+        if let Some(deps) = deps {
+            self.release(key, artifact, deps, fresh);
+        }
+    }
+
+    fn release(&mut self, key: &K, artifact: &A, deps: HashSet<K>,
+               fresh: Freshness) {
+        let edge = (key.clone(), artifact.clone());
+        for dep in deps {
+            assert!(self.dep_map.get_mut(&dep).unwrap().0.remove(&edge));
             if fresh == Dirty {
-                self.dirty.insert(dep.clone());
+                self.dirty.insert(dep);
             }
-            assert!(self.dep_map.get_mut(dep).unwrap().0.remove(key));
         }
     }
 }
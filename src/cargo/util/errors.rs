@@ -8,6 +8,7 @@ use std::str;
 use std::string;
 
 use core::TargetKind;
+use core::resolver::ConflictTree;
 
 use curl;
 use git2;
@@ -26,6 +27,7 @@ pub trait CargoError: Error + Send + 'static {
     fn is_human(&self) -> bool { false }
     fn cargo_cause(&self) -> Option<&CargoError>{ None }
     fn as_error(&self) -> &Error where Self: Sized { self as &Error }
+    fn conflict_tree(&self) -> Option<&ConflictTree> { None }
 }
 
 impl Error for Box<CargoError> {
@@ -36,6 +38,7 @@ impl Error for Box<CargoError> {
 impl CargoError for Box<CargoError> {
     fn is_human(&self) -> bool { (**self).is_human() }
     fn cargo_cause(&self) -> Option<&CargoError> { (**self).cargo_cause() }
+    fn conflict_tree(&self) -> Option<&ConflictTree> { (**self).conflict_tree() }
 }
 
 // =============================================================================
@@ -266,6 +269,7 @@ impl<E: fmt::Display> fmt::Display for Human<E> {
 impl<E: CargoError> CargoError for Human<E> {
     fn is_human(&self) -> bool { true }
     fn cargo_cause(&self) -> Option<&CargoError> { self.0.cargo_cause() }
+    fn conflict_tree(&self) -> Option<&ConflictTree> { self.0.conflict_tree() }
 }
 
 // =============================================================================
@@ -2,6 +2,8 @@ use serde::ser;
 use serde_json::{self, Value};
 
 use core::{PackageId, Target, Profile};
+use core::resolver::ConflictTree;
+use ops::PackageChange;
 
 pub trait Message: ser::Serialize {
     fn reason(&self) -> &str;
@@ -55,3 +57,27 @@ impl<'a> Message for BuildScript<'a> {
         "build-script-executed"
     }
 }
+
+#[derive(Serialize)]
+pub struct ResolveConflict<'a> {
+    pub message: String,
+    pub conflict: &'a ConflictTree,
+}
+
+impl<'a> Message for ResolveConflict<'a> {
+    fn reason(&self) -> &str {
+        "resolve-conflict"
+    }
+}
+
+#[derive(Serialize)]
+pub struct LockfileDiff<'a> {
+    pub changes: &'a [PackageChange],
+    pub dry_run: bool,
+}
+
+impl<'a> Message for LockfileDiff<'a> {
+    fn reason(&self) -> &str {
+        "lockfile-diff"
+    }
+}
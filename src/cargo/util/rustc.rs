@@ -1,11 +1,14 @@
 use std::path::PathBuf;
 
+use semver::Version;
+
 use util::{self, CargoResult, internal, ChainError, ProcessBuilder};
 
 pub struct Rustc {
     pub path: PathBuf,
     pub wrapper: Option<PathBuf>,
     pub verbose_version: String,
+    pub version: Version,
     pub host: String,
 }
 
@@ -35,10 +38,24 @@ impl Rustc {
             triple.to_string()
         };
 
+        let version = {
+            let release = verbose_version.lines().find(|l| {
+                l.starts_with("release: ")
+            }).map(|l| &l[9..]);
+            let release = release.chain_error(|| {
+                internal("rustc -v didn't have a line for `release:`")
+            })?;
+            Version::parse(release).map_err(|_| {
+                internal(format!("rustc -v reported an invalid release: {}",
+                                 release))
+            })?
+        };
+
         Ok(Rustc {
             path: path,
             wrapper: wrapper,
             verbose_version: verbose_version,
+            version: version,
             host: host,
         })
     }
@@ -54,4 +71,25 @@ impl Rustc {
             util::process(&self.path)
         }
     }
+
+    /// Returns whether this compiler accepts unstable `-Z` flags.
+    pub fn is_nightly(&self) -> bool {
+        self.version.pre.iter().any(|id| {
+            let id = id.to_string();
+            id == "nightly" || id == "dev"
+        })
+    }
+
+    /// Returns whether this compiler can report the artifacts it writes with
+    /// `--json artifacts`, which pipelined builds rely on.
+    pub fn supports_json_artifacts(&self) -> bool {
+        let mut cmd = util::process(&self.path);
+        cmd.arg("-")
+           .arg("--crate-name").arg("___")
+           .arg("--print=file-names")
+           .arg("--error-format").arg("json")
+           .arg("--json").arg("artifacts")
+           .env_remove("RUST_LOG");
+        cmd.exec_with_output().is_ok()
+    }
 }
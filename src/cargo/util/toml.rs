@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, BTreeSet};
+use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet};
 use std::default::Default;
 use std::fmt;
 use std::fs;
@@ -9,12 +9,14 @@ use toml;
 use semver::{self, VersionReq};
 use serde::de::{self, Deserialize};
 use serde_ignored;
+use url::Url;
 
 use core::{SourceId, Profiles, PackageIdSpec, GitReference, WorkspaceConfig};
 use core::{Summary, Manifest, Target, Dependency, DependencyInner, PackageId};
 use core::{EitherManifest, VirtualManifest};
-use core::dependency::{Kind, Platform};
+use core::dependency::{ArtifactKind, Kind, Platform};
 use core::manifest::{LibKind, Profile, ManifestMetadata};
+use core::resolver::ResolveBehavior;
 use sources::CRATES_IO;
 use util::{self, CargoResult, human, ToUrl, ToSemver, ChainError, Config};
 
@@ -91,9 +93,15 @@ fn try_add_files(files: &mut Vec<PathBuf>, root: PathBuf) {
     /* else just don't add anything if the directory doesn't exist, etc. */
 }
 
+/// Parses the manifest `contents` of the package at `layout.root`.
+///
+/// Fields inherited from the workspace are taken from `inherit` if given,
+/// which should be the fields of the workspace root the package belongs to.
+/// Otherwise the workspace root is searched for on the filesystem.
 pub fn to_manifest(contents: &str,
                    source_id: &SourceId,
                    layout: Layout,
+                   inherit: Option<&InheritableFields>,
                    config: &Config)
                    -> CargoResult<(EitherManifest, Vec<PathBuf>)> {
     let manifest = layout.root.join("Cargo.toml");
@@ -101,7 +109,9 @@ pub fn to_manifest(contents: &str,
         Some(path) => path.to_path_buf(),
         None => manifest.clone(),
     };
-    let root = parse(contents, &manifest, config)?;
+    let mut root = parse(contents, &manifest, config)?;
+    let inheritable = InheritableFields::new(&layout.root, root.get("workspace"));
+    inherit_workspace_fields(&mut root, &layout.root, inherit, config)?;
     let mut unused = BTreeSet::new();
     let manifest: TomlManifest = serde_ignored::deserialize(root, |path| {
         let mut key = String::new();
@@ -111,7 +121,8 @@ pub fn to_manifest(contents: &str,
         }
     })?;
 
-    return match manifest.to_real_manifest(source_id, &layout, config) {
+    return match manifest.to_real_manifest(source_id, &layout, &inheritable,
+                                           config) {
         Ok((mut manifest, paths)) => {
             for key in unused {
                 manifest.add_warning(format!("unused manifest key: {}", key));
@@ -124,7 +135,8 @@ pub fn to_manifest(contents: &str,
             Ok((EitherManifest::Real(manifest), paths))
         }
         Err(e) => {
-            match manifest.to_virtual_manifest(source_id, &layout, config) {
+            match manifest.to_virtual_manifest(source_id, &layout,
+                                               &inheritable, config) {
                 Ok((m, paths)) => Ok((EitherManifest::Virtual(m), paths)),
                 Err(..) => Err(e),
             }
@@ -185,6 +197,388 @@ in the future.", file.display());
     })
 }
 
+/// Fields of `[package]` which a workspace member may inherit from the
+/// `[workspace.package]` table of its workspace root.
+const INHERITABLE_PACKAGE_FIELDS: &'static [&'static str] = &[
+    "version", "authors", "description", "homepage", "documentation",
+    "keywords", "categories", "license", "repository", "publish",
+    "rust-version",
+];
+
+const DEPENDENCY_TABLES: &'static [&'static str] = &[
+    "dependencies",
+    "dev-dependencies", "dev_dependencies",
+    "build-dependencies", "build_dependencies",
+];
+
+/// The `[workspace.package]`, `[workspace.dependencies]` and
+/// `[workspace.lints]` tables of a workspace root, which its members may
+/// inherit fields from with `workspace = true`.
+#[derive(Clone, Debug)]
+pub struct InheritableFields {
+    root: PathBuf,
+    package: Option<toml::value::Table>,
+    dependencies: Option<toml::value::Table>,
+    lints: Option<toml::Value>,
+}
+
+impl InheritableFields {
+    /// Collects the inheritable tables of `workspace`, the `[workspace]`
+    /// table of the manifest in `root`, if there is one.
+    fn new(root: &Path, workspace: Option<&toml::Value>) -> InheritableFields {
+        let table = |key: &str| workspace.and_then(|ws| ws.get(key));
+        InheritableFields {
+            root: root.to_path_buf(),
+            package: table("package").and_then(|p| p.as_table()).cloned(),
+            dependencies: table("dependencies").and_then(|d| d.as_table())
+                                               .cloned(),
+            lints: table("lints").cloned(),
+        }
+    }
+}
+
+/// Replaces all fields of a manifest marked with `workspace = true` with the
+/// values from the `[workspace.package]`, `[workspace.dependencies]` and
+/// `[workspace.lints]` tables of the workspace root, returning whether
+/// anything was inherited.
+///
+/// This works on the raw TOML so that the rest of manifest parsing, as well as
+/// `cargo package`, sees inherited values as if they had been written out.
+fn inherit_workspace_fields(toml: &mut toml::Value,
+                            package_root: &Path,
+                            inherit: Option<&InheritableFields>,
+                            config: &Config) -> CargoResult<bool> {
+    if !uses_inheritance(toml) {
+        return Ok(false)
+    }
+    // A workspace root inherits from its own `[workspace]` table.
+    let found;
+    let ws = match toml.get("workspace") {
+        Some(ws) => {
+            found = InheritableFields::new(package_root, Some(ws));
+            &found
+        }
+        None => {
+            match inherit {
+                Some(inherit) => inherit,
+                None => {
+                    found = find_workspace_root(toml, package_root, config)?;
+                    &found
+                }
+            }
+        }
+    };
+    let ws_root = &ws.root;
+    let ws_manifest = ws_root.join("Cargo.toml");
+    let ws_package = ws.package.as_ref();
+    let ws_deps = ws.dependencies.as_ref();
+    let ws_lints = ws.lints.as_ref();
+
+    for section in ["package", "project"].iter() {
+        let table = toml.as_table_mut().unwrap();
+        let package = match table.get_mut(*section).and_then(|p| p.as_table_mut()) {
+            Some(package) => package,
+            None => continue,
+        };
+        let keys = package.iter()
+                          .filter(|&(_, v)| is_inherited(v))
+                          .map(|(k, _)| k.clone())
+                          .collect::<Vec<_>>();
+        for key in keys {
+            if !INHERITABLE_PACKAGE_FIELDS.contains(&&key[..]) {
+                bail!("`{}.{}` cannot be inherited from the workspace",
+                      section, key)
+            }
+            let value = ws_package.and_then(|p| p.get(&key)).chain_error(|| {
+                human(format!("`{}.{}` was inherited from the workspace, but \
+                               `workspace.package.{}` is not defined in `{}`",
+                              section, key, key, ws_manifest.display()))
+            })?;
+            package.insert(key, value.clone());
+        }
+    }
+
+    for deps in dependency_tables(toml) {
+        inherit_dependencies(deps, ws_deps, ws_root, package_root)?;
+    }
+
+    // Lints are inherited as a whole rather than per tool or per lint.
+    let table = toml.as_table_mut().unwrap();
+    if table.get("lints").map(is_inherited) == Some(true) {
+        if let Some(key) = table["lints"].as_table().unwrap().keys()
+                                         .find(|k| *k != "workspace") {
+            bail!("`lints` was inherited from the workspace, so it cannot \
+                   also specify `{}`", key)
+        }
+        let lints = ws_lints.chain_error(|| {
+            human(format!("`lints` was inherited from the workspace, but \
+                           `workspace.lints` is not defined in `{}`",
+                          ws_manifest.display()))
+        })?;
+        table.insert("lints".to_string(), lints.clone());
+    }
+    return Ok(true);
+
+    fn is_inherited(value: &toml::Value) -> bool {
+        value.as_table()
+             .and_then(|t| t.get("workspace"))
+             .and_then(|w| w.as_bool()) == Some(true)
+    }
+
+    fn uses_inheritance(toml: &toml::Value) -> bool {
+        let table = match toml.as_table() {
+            Some(table) => table,
+            None => return false,
+        };
+        let any_dep = |t: &toml::value::Table| {
+            DEPENDENCY_TABLES.iter().filter_map(|name| {
+                t.get(*name).and_then(|d| d.as_table())
+            }).any(|deps| deps.values().any(is_inherited))
+        };
+        ["package", "project"].iter().filter_map(|s| {
+            table.get(*s).and_then(|p| p.as_table())
+        }).any(|p| p.values().any(is_inherited)) ||
+            any_dep(table) ||
+            table.get("lints").map(is_inherited).unwrap_or(false) ||
+            table.get("target").and_then(|t| t.as_table()).map(|targets| {
+                targets.values().filter_map(|p| p.as_table()).any(any_dep)
+            }).unwrap_or(false)
+    }
+
+    fn inherit_dependencies(deps: &mut toml::value::Table,
+                            ws_deps: Option<&toml::value::Table>,
+                            ws_root: &Path,
+                            package_root: &Path) -> CargoResult<()> {
+        for (name, dep) in deps.iter_mut() {
+            if !is_inherited(dep) {
+                continue
+            }
+            let mut inherited = match ws_deps.and_then(|d| d.get(name)) {
+                Some(&toml::Value::String(ref version)) => {
+                    let mut t = toml::value::Table::new();
+                    t.insert("version".to_string(),
+                             toml::Value::String(version.clone()));
+                    t
+                }
+                Some(&toml::Value::Table(ref t)) => t.clone(),
+                _ => {
+                    bail!("dependency `{}` was inherited from the workspace, \
+                           but it is not listed in `[workspace.dependencies]`",
+                          name)
+                }
+            };
+
+            // Paths in `[workspace.dependencies]` are relative to the
+            // workspace root, so rewrite them to be relative to this package.
+            let path = inherited.get("path").and_then(|p| p.as_str())
+                                .map(|p| p.to_string());
+            if let Some(path) = path {
+                let path = match util::without_prefix(package_root, ws_root) {
+                    Some(suffix) => {
+                        let mut rebased = PathBuf::new();
+                        for _ in suffix.components() {
+                            rebased.push("..");
+                        }
+                        rebased.join(path)
+                    }
+                    None => ws_root.join(path),
+                };
+                let path = path.to_str().chain_error(|| {
+                    human(format!("non-utf8 path for dependency `{}`: {}",
+                                  name, path.display()))
+                })?.to_string();
+                inherited.insert("path".to_string(), toml::Value::String(path));
+            }
+
+            for (key, value) in dep.as_table().unwrap() {
+                match &key[..] {
+                    "workspace" => {}
+                    "optional" | "public" => {
+                        inherited.insert(key.clone(), value.clone());
+                    }
+                    "features" => {
+                        let mut features = inherited.get("features")
+                                                    .and_then(|f| f.as_array())
+                                                    .cloned()
+                                                    .unwrap_or(Vec::new());
+                        if let Some(extra) = value.as_array() {
+                            features.extend(extra.iter().cloned());
+                        }
+                        inherited.insert(key.clone(),
+                                         toml::Value::Array(features));
+                    }
+                    _ => {
+                        bail!("dependency `{}` was inherited from the \
+                               workspace, so it may only also specify \
+                               `features`, `optional` or `public`, but `{}` \
+                               was found",
+                              name, key)
+                    }
+                }
+            }
+            *dep = toml::Value::Table(inherited);
+        }
+        Ok(())
+    }
+}
+
+/// Returns all tables of dependencies in the manifest `toml`, including those
+/// for specific targets.
+fn dependency_tables(toml: &mut toml::Value) -> Vec<&mut toml::value::Table> {
+    let mut ret = Vec::new();
+    let table = match toml.as_table_mut() {
+        Some(table) => table,
+        None => return ret,
+    };
+    for (key, value) in table.iter_mut() {
+        if DEPENDENCY_TABLES.contains(&&key[..]) {
+            ret.extend(value.as_table_mut());
+        } else if key == "target" {
+            let platforms = value.as_table_mut().into_iter().flat_map(|t| {
+                t.values_mut()
+            });
+            for platform in platforms.filter_map(|p| p.as_table_mut()) {
+                for (key, value) in platform.iter_mut() {
+                    if DEPENDENCY_TABLES.contains(&&key[..]) {
+                        ret.extend(value.as_table_mut());
+                    }
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// Replaces `registry = "name"` in all dependencies with the index of that
+/// registry, so the manifest no longer relies on the local `.cargo/config`,
+/// returning whether anything was replaced.
+fn resolve_registry_names(toml: &mut toml::Value, config: &Config)
+                          -> CargoResult<bool> {
+    let mut replaced = false;
+    for deps in dependency_tables(toml) {
+        for (name, dep) in deps.iter_mut() {
+            let dep = match dep.as_table_mut() {
+                Some(dep) => dep,
+                None => continue,
+            };
+            let registry = match dep.remove("registry") {
+                Some(registry) => registry,
+                None => continue,
+            };
+            let index = {
+                let registry = registry.as_str().chain_error(|| {
+                    human(format!("`registry` of dependency `{}` must be a \
+                                   string", name))
+                })?;
+                config.get_registry_index(registry)?
+            };
+            dep.insert("registry-index".to_string(),
+                       toml::Value::String(index.to_string()));
+            replaced = true;
+        }
+    }
+    Ok(replaced)
+}
+
+/// Returns the workspace configuration of the manifest `toml`, located in
+/// `package_root`, without loading the rest of the manifest.
+///
+/// This is enough to find the root of the workspace a package belongs to
+/// before fields inherited from that root can be filled in.
+pub fn to_workspace_config(toml: &toml::Value, package_root: &Path)
+                           -> WorkspaceConfig {
+    if let Some(ws) = toml.get("workspace") {
+        let strings = |key: &str| {
+            ws.get(key).and_then(|v| v.as_array()).map(|v| {
+                v.iter().filter_map(|s| s.as_str()).map(|s| s.to_string())
+                 .collect::<Vec<_>>()
+            })
+        };
+        return WorkspaceConfig::Root {
+            members: strings("members"),
+            exclude: strings("exclude").unwrap_or(Vec::new()),
+            inheritable: InheritableFields::new(package_root, Some(ws)),
+        }
+    }
+    let root = ["package", "project"].iter().filter_map(|s| toml.get(*s))
+        .filter_map(|p| p.get("workspace").and_then(|w| w.as_str()))
+        .next();
+    WorkspaceConfig::Member { root: root.map(|s| s.to_string()) }
+}
+
+/// Locates the root of the workspace that `toml`, the manifest of the package
+/// at `package_root`, belongs to, for manifests read outside of a
+/// `Workspace`.
+///
+/// This follows the same rules as `Workspace::find_root`, so a package which
+/// the `[workspace]` of an ancestor excludes doesn't inherit from it.
+fn find_workspace_root(toml: &toml::Value,
+                       package_root: &Path,
+                       config: &Config) -> CargoResult<InheritableFields> {
+    let read = |manifest: &Path| -> CargoResult<WorkspaceConfig> {
+        let contents = util::paths::read(manifest)?;
+        let toml = parse(&contents, manifest, config)?;
+        Ok(to_workspace_config(&toml, manifest.parent().unwrap()))
+    };
+    let pointer = |dir: &Path, root: &str| {
+        util::normalize_path(&dir.join(root).join("Cargo.toml"))
+    };
+
+    let manifest_path = package_root.join("Cargo.toml");
+    let mut root_manifest = None;
+    if let WorkspaceConfig::Member { root: Some(ref root) } =
+           to_workspace_config(toml, package_root) {
+        root_manifest = Some(pointer(package_root, root));
+    } else {
+        for dir in util::paths::ancestors(package_root).skip(1) {
+            let manifest = dir.join("Cargo.toml");
+            if !manifest.exists() {
+                continue
+            }
+            let ws_config = read(&manifest)?;
+            if ws_config.is_root_of(dir, &manifest_path) {
+                root_manifest = Some(manifest);
+                break
+            }
+            if let WorkspaceConfig::Member { root: Some(ref root) } = ws_config {
+                root_manifest = Some(pointer(dir, root));
+                break
+            }
+        }
+    }
+
+    if let Some(root_manifest) = root_manifest {
+        if root_manifest.exists() {
+            if let WorkspaceConfig::Root { inheritable, .. } = read(&root_manifest)? {
+                return Ok(inheritable)
+            }
+        }
+    }
+    bail!("manifest at `{}` inherits fields from the workspace, but no \
+           workspace root could be found", manifest_path.display())
+}
+
+/// Returns the contents of the manifest at `manifest_path` with all fields
+/// inherited from the workspace written out literally and registries of
+/// dependencies referred to by their index, or `None` if the manifest can be
+/// used as-is.
+pub fn to_packaged_manifest(manifest_path: &Path,
+                            inherit: Option<&InheritableFields>,
+                            config: &Config)
+                            -> CargoResult<Option<String>> {
+    let contents = util::paths::read(manifest_path)?;
+    let mut toml = parse(&contents, manifest_path, config)?;
+    let inherited = inherit_workspace_fields(&mut toml,
+                                             manifest_path.parent().unwrap(),
+                                             inherit, config)?;
+    let resolved = resolve_registry_names(&mut toml, config)?;
+    if inherited || resolved {
+        Ok(Some(toml.to_string()))
+    } else {
+        Ok(None)
+    }
+}
+
 type TomlLibTarget = TomlTarget;
 type TomlBinTarget = TomlTarget;
 type TomlExampleTarget = TomlTarget;
@@ -238,10 +632,17 @@ pub struct DetailedTomlDependency {
     rev: Option<String>,
     features: Option<Vec<String>>,
     optional: Option<bool>,
+    public: Option<bool>,
     #[serde(rename = "default-features")]
     default_features: Option<bool>,
     #[serde(rename = "default_features")]
     default_features2: Option<bool>,
+    package: Option<String>,
+    registry: Option<String>,
+    #[serde(rename = "registry-index")]
+    registry_index: Option<String>,
+    artifact: Option<String>,
+    target: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -266,19 +667,35 @@ pub struct TomlManifest {
     features: Option<HashMap<String, Vec<String>>>,
     target: Option<HashMap<String, TomlPlatform>>,
     replace: Option<HashMap<String, TomlDependency>>,
+    patch: Option<HashMap<String, HashMap<String, TomlDependency>>>,
     workspace: Option<TomlWorkspace>,
     badges: Option<HashMap<String, HashMap<String, String>>>,
+    lints: Option<TomlLints>,
 }
 
-#[derive(Deserialize, Clone, Default)]
-pub struct TomlProfiles {
-    test: Option<TomlProfile>,
-    doc: Option<TomlProfile>,
-    bench: Option<TomlProfile>,
-    dev: Option<TomlProfile>,
-    release: Option<TomlProfile>,
+/// The `[profile]` table, containing both the built-in profiles and any custom
+/// ones defined with `inherits`.
+#[derive(Clone, Default)]
+pub struct TomlProfiles(HashMap<String, TomlProfile>);
+
+impl de::Deserialize for TomlProfiles {
+    fn deserialize<D>(d: D) -> Result<TomlProfiles, D::Error>
+        where D: de::Deserializer
+    {
+        HashMap::deserialize(d).map(TomlProfiles)
+    }
+}
+
+impl TomlProfiles {
+    fn get(&self, name: &str) -> Option<&TomlProfile> {
+        self.0.get(name)
+    }
 }
 
+const BUILTIN_PROFILES: &'static [&'static str] = &[
+    "dev", "release", "test", "bench", "doc",
+];
+
 #[derive(Clone)]
 pub struct TomlOptLevel(String);
 
@@ -373,6 +790,74 @@ pub struct TomlProfile {
     panic: Option<String>,
     #[serde(rename = "overflow-checks")]
     overflow_checks: Option<bool>,
+    package: Option<HashMap<String, TomlProfile>>,
+    #[serde(rename = "build-override")]
+    build_override: Option<Box<TomlProfile>>,
+    inherits: Option<String>,
+}
+
+/// The `[lints]` table, mapping each tool (`rust` or `clippy`) to the levels
+/// of its lints.
+type TomlLints = BTreeMap<String, BTreeMap<String, TomlLint>>;
+
+#[derive(Clone, Debug)]
+pub enum TomlLint {
+    Level(String),
+    Detailed(DetailedTomlLint),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DetailedTomlLint {
+    level: String,
+    priority: Option<i8>,
+}
+
+impl TomlLint {
+    fn level(&self) -> &str {
+        match *self {
+            TomlLint::Level(ref level) => level,
+            TomlLint::Detailed(ref lint) => &lint.level,
+        }
+    }
+
+    fn priority(&self) -> i8 {
+        match *self {
+            TomlLint::Level(_) => 0,
+            TomlLint::Detailed(ref lint) => lint.priority.unwrap_or(0),
+        }
+    }
+}
+
+impl de::Deserialize for TomlLint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: de::Deserializer
+    {
+        struct TomlLintVisitor;
+
+        impl de::Visitor for TomlLintVisitor {
+            type Value = TomlLint;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a lint level like \"warn\" or a \
+                                     detailed lint like { level = \"warn\" }")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+                where E: de::Error
+            {
+                Ok(TomlLint::Level(s.to_owned()))
+            }
+
+            fn visit_map<V>(self, map: V) -> Result<Self::Value, V::Error>
+                where V: de::MapVisitor
+            {
+                let mvd = de::value::MapVisitorDeserializer::new(map);
+                DetailedTomlLint::deserialize(mvd).map(TomlLint::Detailed)
+            }
+        }
+
+        deserializer.deserialize(TomlLintVisitor)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -422,6 +907,11 @@ pub struct TomlProject {
     include: Option<Vec<String>>,
     publish: Option<bool>,
     workspace: Option<String>,
+    #[serde(rename = "rust-version")]
+    rust_version: Option<String>,
+    #[serde(rename = "default-run")]
+    default_run: Option<String>,
+    resolver: Option<String>,
 
     // package metadata
     description: Option<String>,
@@ -440,6 +930,12 @@ pub struct TomlProject {
 pub struct TomlWorkspace {
     members: Option<Vec<String>>,
     exclude: Option<Vec<String>>,
+    // Only used for inheritance through `InheritableFields`, but listed
+    // here to validate them and avoid warnings about unused keys.
+    package: Option<toml::value::Table>,
+    dependencies: Option<HashMap<String, TomlDependency>>,
+    lints: Option<TomlLints>,
+    resolver: Option<String>,
 }
 
 pub struct TomlVersion {
@@ -566,6 +1062,7 @@ impl TomlManifest {
     fn to_real_manifest(&self,
                         source_id: &SourceId,
                         layout: &Layout,
+                        inheritable: &InheritableFields,
                         config: &Config)
                         -> CargoResult<(Manifest, Vec<PathBuf>)> {
         let mut nested_paths = vec![];
@@ -695,6 +1192,7 @@ impl TomlManifest {
 
         let mut deps = Vec::new();
         let replace;
+        let patch;
 
         {
 
@@ -750,6 +1248,7 @@ impl TomlManifest {
             }
 
             replace = self.replace(&mut cx)?;
+            patch = self.patch(&mut cx)?;
         }
 
         {
@@ -770,6 +1269,8 @@ impl TomlManifest {
 
         let summary = Summary::new(pkgid, deps, self.features.clone()
             .unwrap_or_else(HashMap::new))?;
+        let summary = summary.set_links(project.links.clone())
+                             .set_rust_version(project.rust_version.clone());
         let metadata = ManifestMetadata {
             description: project.description.clone(),
             homepage: project.homepage.clone(),
@@ -790,6 +1291,7 @@ impl TomlManifest {
                 WorkspaceConfig::Root {
                     members: config.members.clone(),
                     exclude: config.exclude.clone().unwrap_or(Vec::new()),
+                    inheritable: inheritable.clone(),
                 }
             }
             (None, root) => {
@@ -800,8 +1302,26 @@ impl TomlManifest {
                        `[workspace]`, only one can be specified")
             }
         };
-        let profiles = build_profiles(&self.profile);
+        if let Some(ref rust_version) = project.rust_version {
+            validate_rust_version(rust_version)?;
+        }
+        if let Some(ref default_run) = project.default_run {
+            if !targets.iter().any(|t| t.is_bin() && t.name() == default_run) {
+                bail!("default-run target `{}` was not found, it must name \
+                       one of the package's binary targets", default_run)
+            }
+        }
+
+        let lints = lint_args(self.lints.as_ref(), &mut warnings)?;
+        let profiles = build_profiles(&self.profile, source_id)?;
         let publish = project.publish.unwrap_or(true);
+        let resolver = project.resolver.as_ref().or_else(|| {
+            self.workspace.as_ref().and_then(|ws| ws.resolver.as_ref())
+        });
+        let resolve_behavior = match resolver {
+            Some(r) => Some(ResolveBehavior::from_manifest(r)?),
+            None => None,
+        };
         let mut manifest = Manifest::new(summary,
                                          targets,
                                          exclude,
@@ -810,8 +1330,13 @@ impl TomlManifest {
                                          metadata,
                                          profiles,
                                          publish,
+                                         project.rust_version.clone(),
+                                         project.default_run.clone(),
+                                         lints,
                                          replace,
-                                         workspace_config);
+                                         patch,
+                                         workspace_config,
+                                         resolve_behavior);
         if project.license_file.is_some() && project.license.is_some() {
             manifest.add_warning("only one of `license` or \
                                  `license-file` is necessary".to_string());
@@ -826,6 +1351,7 @@ impl TomlManifest {
     fn to_virtual_manifest(&self,
                            source_id: &SourceId,
                            layout: &Layout,
+                           inheritable: &InheritableFields,
                            config: &Config)
                            -> CargoResult<(VirtualManifest, Vec<PathBuf>)> {
         if self.project.is_some() {
@@ -853,29 +1379,40 @@ impl TomlManifest {
         let mut nested_paths = Vec::new();
         let mut warnings = Vec::new();
         let mut deps = Vec::new();
-        let replace = self.replace(&mut Context {
-            pkgid: None,
-            deps: &mut deps,
-            source_id: source_id,
-            nested_paths: &mut nested_paths,
-            config: config,
-            warnings: &mut warnings,
-            platform: None,
-            layout: layout,
-        })?;
-        let profiles = build_profiles(&self.profile);
+        let (replace, patch) = {
+            let mut cx = Context {
+                pkgid: None,
+                deps: &mut deps,
+                source_id: source_id,
+                nested_paths: &mut nested_paths,
+                config: config,
+                warnings: &mut warnings,
+                platform: None,
+                layout: layout,
+            };
+            (self.replace(&mut cx)?, self.patch(&mut cx)?)
+        };
+        let profiles = build_profiles(&self.profile, source_id)?;
         let workspace_config = match self.workspace {
             Some(ref config) => {
                 WorkspaceConfig::Root {
                     members: config.members.clone(),
                     exclude: config.exclude.clone().unwrap_or(Vec::new()),
+                    inheritable: inheritable.clone(),
                 }
             }
             None => {
                 bail!("virtual manifests must be configured with [workspace]");
             }
         };
-        Ok((VirtualManifest::new(replace, workspace_config, profiles), nested_paths))
+        let resolve_behavior = match self.workspace.as_ref()
+                                             .and_then(|ws| ws.resolver.as_ref()) {
+            Some(r) => Some(ResolveBehavior::from_manifest(r)?),
+            None => None,
+        };
+        Ok((VirtualManifest::new(replace, patch, workspace_config, profiles,
+                                 resolve_behavior),
+            nested_paths))
     }
 
     fn replace(&self, cx: &mut Context)
@@ -916,6 +1453,21 @@ impl TomlManifest {
         Ok(replace)
     }
 
+    fn patch(&self, cx: &mut Context)
+             -> CargoResult<HashMap<Url, Vec<Dependency>>> {
+        let mut patch = HashMap::new();
+        for (url, deps) in self.patch.iter().flat_map(|x| x) {
+            let url = match &url[..] {
+                "crates-io" => CRATES_IO.parse().unwrap(),
+                _ => url.to_url()?,
+            };
+            patch.insert(url, deps.iter().map(|(name, dep)| {
+                dep.to_dependency(name, cx, None)
+            }).collect::<CargoResult<Vec<_>>>()?);
+        }
+        Ok(patch)
+    }
+
     fn maybe_custom_build(&self,
                           build: &Option<StringOrBool>,
                           project_dir: &Path)
@@ -1001,6 +1553,26 @@ impl TomlDependency {
             }
         }
 
+        // A `registry` key names one of the registries configured in
+        // `.cargo/config`, while `registry-index` is what `cargo package`
+        // writes out so that the package doesn't depend on that configuration.
+        let registry_id = match (details.registry.as_ref(),
+                                 details.registry_index.as_ref()) {
+            (Some(_), Some(_)) => {
+                bail!("dependency ({}) specification is ambiguous. Only one \
+                       of `registry` or `registry-index` is allowed.", name)
+            }
+            (Some(registry), None) => {
+                Some(SourceId::alt_registry(cx.config, registry)?)
+            }
+            (None, Some(index)) => Some(SourceId::for_registry(&index.to_url()?)),
+            (None, None) => None,
+        };
+        if registry_id.is_some() && details.git.is_some() {
+            bail!("dependency ({}) specification is ambiguous. Only one of \
+                   `git` or `registry` is allowed.", name)
+        }
+
         let new_source_id = match (details.git.as_ref(), details.path.as_ref()) {
             (Some(git), maybe_path) => {
                 if maybe_path.is_some() {
@@ -1044,26 +1616,58 @@ impl TomlDependency {
                     let path = util::normalize_path(&path);
                     SourceId::for_path(&path)?
                 } else {
-                    cx.source_id.clone()
+                    registry_id.unwrap_or_else(|| cx.source_id.clone())
                 }
             },
-            (None, None) => SourceId::crates_io(cx.config)?,
+            (None, None) => match registry_id {
+                Some(id) => id,
+                None => SourceId::crates_io(cx.config)?,
+            },
+        };
+
+        let artifact = match details.artifact.as_ref().map(|s| &s[..]) {
+            Some("bin") => Some(ArtifactKind::Bin),
+            Some("cdylib") => Some(ArtifactKind::Cdylib),
+            Some(other) => {
+                bail!("dependency ({}) has an invalid `artifact` of `{}`, \
+                       expected `bin` or `cdylib`", name, other)
+            }
+            None => None,
+        };
+        if details.target.is_some() && artifact.is_none() {
+            bail!("dependency ({}) specifies `target`, which is only allowed \
+                   together with `artifact`", name)
+        }
+        let public = details.public.unwrap_or(false);
+        if public && kind.is_some() {
+            bail!("dependency ({}) is marked `public`, which is only allowed \
+                   for normal dependencies", name)
+        }
+
+        // A `package` key means the dependency is known by `name` locally but
+        // refers to a package with a different name.
+        let (pkg_name, rename) = match details.package {
+            Some(ref package) => (&package[..], Some(name.to_string())),
+            None => (name, None),
         };
 
         let version = details.version.as_ref().map(|v| &v[..]);
         let mut dep = match cx.pkgid {
             Some(id) => {
-                DependencyInner::parse(name, version, &new_source_id,
+                DependencyInner::parse(pkg_name, version, &new_source_id,
                                             Some((id, cx.config)))?
             }
-            None => DependencyInner::parse(name, version, &new_source_id, None)?,
+            None => DependencyInner::parse(pkg_name, version, &new_source_id, None)?,
         };
-        dep = dep.set_features(details.features.unwrap_or(Vec::new()))
+        dep = dep.set_rename(rename)
+                 .set_features(details.features.unwrap_or(Vec::new()))
                  .set_default_features(details.default_features
                                               .or(details.default_features2)
                                               .unwrap_or(true))
                  .set_optional(details.optional.unwrap_or(false))
-                 .set_platform(cx.platform.clone());
+                 .set_public(public)
+                 .set_platform(cx.platform.clone())
+                 .set_artifact(artifact, details.target.clone());
         if let Some(kind) = kind {
             dep = dep.set_kind(kind);
         }
@@ -1438,40 +2042,284 @@ fn inferred_bin_path(bin: &TomlBinTarget,
     Path::new("src").join("bin").join(&format!("{}.rs", bin.name())).to_path_buf()
 }
 
-fn build_profiles(profiles: &Option<TomlProfiles>) -> Profiles {
+/// Checks that `rust-version` is a bare `major.minor` or `major.minor.patch`
+/// version, without any pre-release or build metadata.
+fn validate_rust_version(rust_version: &str) -> CargoResult<()> {
+    let parts = rust_version.split('.').collect::<Vec<_>>();
+    let valid = (parts.len() == 2 || parts.len() == 3) &&
+                parts.iter().all(|p| p.parse::<u64>().is_ok());
+    if !valid {
+        bail!("`rust-version` must be a version such as \"1.17\" or \
+               \"1.17.0\", but found `{}`", rust_version)
+    }
+    Ok(())
+}
+
+/// Converts the `[lints]` table into the flags passed to rustc.
+///
+/// Lints are ordered by priority and then by name, so that a lower priority
+/// group like `warnings` can be overridden by individual lints in it.
+fn lint_args(lints: Option<&TomlLints>, warnings: &mut Vec<String>)
+             -> CargoResult<Vec<String>> {
+    let mut levels = Vec::new();
+    for (tool, lints) in lints.into_iter().flat_map(|l| l) {
+        let prefix = match &tool[..] {
+            "rust" => "",
+            "clippy" => "clippy::",
+            _ => {
+                warnings.push(format!("unrecognized lint tool `lints.{}`, \
+                                       ignoring", tool));
+                continue
+            }
+        };
+        for (name, lint) in lints {
+            if name.contains("::") {
+                bail!("`lints.{}.{}` is not a valid lint name, the tool is \
+                       taken from the name of the table", tool, name)
+            }
+            let flag = match lint.level() {
+                "forbid" => "--forbid",
+                "deny" => "--deny",
+                "warn" => "--warn",
+                "allow" => "--allow",
+                level => {
+                    bail!("`lints.{}.{}` has an invalid level `{}`, expected \
+                           one of `forbid`, `deny`, `warn` or `allow`",
+                          tool, name, level)
+                }
+            };
+            levels.push((lint.priority(), format!("{}{}", prefix, name), flag));
+        }
+    }
+    levels.sort();
+
+    let mut args = Vec::new();
+    for (_, name, flag) in levels {
+        args.push(flag.to_string());
+        args.push(name);
+    }
+    Ok(args)
+}
+
+fn build_profiles(profiles: &Option<TomlProfiles>,
+                  source_id: &SourceId) -> CargoResult<Profiles> {
     let profiles = profiles.as_ref();
-    let mut profiles = Profiles {
-        release: merge(Profile::default_release(),
-                       profiles.and_then(|p| p.release.as_ref())),
-        dev: merge(Profile::default_dev(),
-                   profiles.and_then(|p| p.dev.as_ref())),
-        test: merge(Profile::default_test(),
-                    profiles.and_then(|p| p.test.as_ref())),
-        test_deps: merge(Profile::default_dev(),
-                         profiles.and_then(|p| p.dev.as_ref())),
-        bench: merge(Profile::default_bench(),
-                     profiles.and_then(|p| p.bench.as_ref())),
-        bench_deps: merge(Profile::default_release(),
-                          profiles.and_then(|p| p.release.as_ref())),
-        doc: merge(Profile::default_doc(),
-                   profiles.and_then(|p| p.doc.as_ref())),
-        custom_build: Profile::default_custom_build(),
-        check: merge(Profile::default_check(),
-                     profiles.and_then(|p| p.dev.as_ref())),
-        doctest: Profile::default_doctest(),
-    };
-    // The test/bench targets cannot have panic=abort because they'll all get
-    // compiled with --test which requires the unwind runtime currently
-    profiles.test.panic = None;
-    profiles.bench.panic = None;
-    profiles.test_deps.panic = None;
-    profiles.bench_deps.panic = None;
-    return profiles;
+
+    // Only the profiles of the workspace root are ever used, so custom
+    // profiles of packages from registries or git are ignored rather than
+    // validated. Crates published with a stray custom profile must keep
+    // building as dependencies.
+    let local = source_id.is_path();
+    let mut names = profiles.iter().flat_map(|p| p.0.keys()).filter(|name| {
+        local || BUILTIN_PROFILES.contains(&&name[..])
+    }).collect::<Vec<_>>();
+    names.sort();
+
+    // Each package mentioned in an override gets a complete set of profiles,
+    // with the overrides merged on top of the normal profiles. Packages are
+    // sorted to keep the order in which they're matched deterministic.
+    let mut specs = Vec::new();
+    let mut build_override = false;
+    for name in names.iter() {
+        let toml = profiles.and_then(|p| p.get(name)).unwrap();
+        let builtin = BUILTIN_PROFILES.contains(&&name[..]);
+        if local && builtin && toml.inherits.is_some() {
+            bail!("`inherits` may only be specified for custom profiles, \
+                   but it was found in the built-in profile `{}`", name)
+        }
+        if !builtin {
+            validate_profile_name(name)?;
+        }
+        for (spec, toml) in toml.package.iter().flat_map(|p| p) {
+            validate_override(toml, "package")?;
+            if !specs.contains(spec) {
+                specs.push(spec.clone());
+            }
+        }
+        if let Some(ref toml) = toml.build_override {
+            validate_override(toml, "build-override")?;
+            build_override = true;
+        }
+    }
+    specs.sort();
+
+    let mut ret = complete_set(profiles, None, &specs, build_override)?;
+    for name in names {
+        if !BUILTIN_PROFILES.contains(&&name[..]) {
+            let set = complete_set(profiles, Some(name.as_str()), &specs,
+                                   build_override)?;
+            ret.named.insert(name.clone(), set);
+        }
+    }
+    return Ok(ret);
+
+    // Builds the profiles for a custom profile `name`, or the built-in ones,
+    // along with any overrides for packages or build scripts.
+    fn complete_set(profiles: Option<&TomlProfiles>,
+                    name: Option<&str>,
+                    specs: &[String],
+                    build_override: bool) -> CargoResult<Profiles> {
+        let mut ret = profile_set(profiles, name, &|_| None)?;
+        for spec in specs {
+            let id_spec = PackageIdSpec::parse(spec).chain_error(|| {
+                human(format!("invalid package id specification `{}` in \
+                               profile override", spec))
+            })?;
+            let set = profile_set(profiles, name, &|p| {
+                p.package.as_ref().and_then(|m| m.get(spec))
+            })?;
+            ret.package_overrides.push((id_spec, set));
+        }
+        if build_override {
+            let set = profile_set(profiles, name, &|p| {
+                p.build_override.as_ref().map(|b| &**b)
+            })?;
+            ret.build_override = Some(Box::new(set));
+        }
+        Ok(ret)
+    }
+
+    fn profile_set(profiles: Option<&TomlProfiles>,
+                   name: Option<&str>,
+                   select: &Fn(&TomlProfile) -> Option<&TomlProfile>)
+                   -> CargoResult<Profiles> {
+        // Merges the profile from the manifest, and then the selected
+        // override within it, on top of a default profile.
+        let m = |profile: Profile, toml: Option<&TomlProfile>| {
+            let overridden = toml.and_then(|t| select(t));
+            merge(merge(profile, toml), overridden)
+        };
+        let get = |name: &str| profiles.and_then(|p| p.get(name));
+
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let mut profiles = Profiles {
+                    release: m(Profile::default_release(), get("release")),
+                    dev: m(Profile::default_dev(), get("dev")),
+                    test: m(Profile::default_test(), get("test")),
+                    test_deps: m(Profile::default_dev(), get("dev")),
+                    bench: m(Profile::default_bench(), get("bench")),
+                    bench_deps: m(Profile::default_release(), get("release")),
+                    doc: m(Profile::default_doc(), get("doc")),
+                    custom_build: Profile::default_custom_build(),
+                    check: m(Profile::default_check(), get("dev")),
+                    doctest: Profile::default_doctest(),
+                    package_overrides: Vec::new(),
+                    build_override: None,
+                    named: HashMap::new(),
+                };
+                // The test/bench targets cannot have panic=abort because
+                // they'll all get compiled with --test which requires the
+                // unwind runtime currently
+                profiles.test.panic = None;
+                profiles.bench.panic = None;
+                profiles.test_deps.panic = None;
+                profiles.bench_deps.panic = None;
+                return Ok(profiles)
+            }
+        };
+
+        // Walk up the `inherits` chain until reaching `dev` or `release`, and
+        // then apply each profile on the way back down.
+        let mut chain = Vec::new();
+        let mut cur = name;
+        while cur != "dev" && cur != "release" {
+            if BUILTIN_PROFILES.contains(&cur) {
+                bail!("profile `{}` cannot inherit from `{}`, custom \
+                       profiles may only inherit from `dev`, `release` or \
+                       another custom profile", name, cur)
+            }
+            if chain.iter().any(|&(n, _)| n == cur) {
+                bail!("profile `{}` inherits from itself", cur)
+            }
+            let toml = get(cur).chain_error(|| {
+                human(format!("profile `{}` inherits from `{}`, but that \
+                               profile is not defined", name, cur))
+            })?;
+            let parent = toml.inherits.as_ref().chain_error(|| {
+                human(format!("profile `{}` is missing an `inherits` \
+                               directive (`inherits` is required for all \
+                               profiles except `dev` or `release`)", cur))
+            })?;
+            chain.push((cur, toml));
+            cur = parent;
+        }
+        let mut base = if cur == "release" {
+            m(Profile::default_release(), get("release"))
+        } else {
+            m(Profile::default_dev(), get("dev"))
+        };
+        for &(_, toml) in chain.iter().rev() {
+            base = m(base, Some(toml));
+        }
+
+        // A custom profile is used for everything that'd otherwise use the
+        // `dev` or `release` profiles, including tests and benchmarks.
+        let test = Profile { test: true, panic: None, ..base.clone() };
+        let deps = Profile { panic: None, ..base.clone() };
+        Ok(Profiles {
+            release: base.clone(),
+            dev: base.clone(),
+            test: test.clone(),
+            test_deps: deps.clone(),
+            bench: test,
+            bench_deps: deps,
+            doc: m(Profile::default_doc(), get("doc")),
+            custom_build: Profile::default_custom_build(),
+            check: Profile { check: true, ..base },
+            doctest: Profile::default_doctest(),
+            package_overrides: Vec::new(),
+            build_override: None,
+            named: HashMap::new(),
+        })
+    }
+
+    // Custom profiles are used as directory names in the target directory, so
+    // they can't clash with the directories Cargo already creates there.
+    fn validate_profile_name(name: &str) -> CargoResult<()> {
+        if name.is_empty() || !name.chars().all(|c| {
+            c.is_alphanumeric() || c == '-' || c == '_'
+        }) {
+            bail!("invalid profile name `{}`, profile names may only \
+                   contain alphanumeric characters, `-` and `_`", name)
+        }
+        let reserved = ["debug", "build", "deps", "examples", "native",
+                        "package", "incremental"];
+        if reserved.contains(&name) {
+            bail!("profile name `{}` is reserved by Cargo", name)
+        }
+        Ok(())
+    }
+
+    // Settings which apply to the final artifact as a whole can't be changed
+    // for just a part of the crate graph.
+    fn validate_override(toml: &TomlProfile, kind: &str) -> CargoResult<()> {
+        if toml.package.is_some() {
+            bail!("package-specific profiles cannot be nested")
+        }
+        if toml.build_override.is_some() {
+            bail!("build-override profiles cannot be nested")
+        }
+        if toml.inherits.is_some() {
+            bail!("`inherits` may not be specified in a `{}` profile", kind)
+        }
+        if toml.panic.is_some() {
+            bail!("`panic` may not be specified in a `{}` profile", kind)
+        }
+        if toml.lto.is_some() {
+            bail!("`lto` may not be specified in a `{}` profile", kind)
+        }
+        if toml.rpath.is_some() {
+            bail!("`rpath` may not be specified in a `{}` profile", kind)
+        }
+        Ok(())
+    }
 
     fn merge(profile: Profile, toml: Option<&TomlProfile>) -> Profile {
         let &TomlProfile {
             ref opt_level, lto, codegen_units, ref debug, debug_assertions, rpath,
-            ref panic, ref overflow_checks,
+            ref panic, ref overflow_checks, ..
         } = match toml {
             Some(toml) => toml,
             None => return profile,
@@ -1485,7 +2333,7 @@ fn build_profiles(profiles: &Option<TomlProfiles>) -> Profiles {
         Profile {
             opt_level: opt_level.clone().unwrap_or(TomlOptLevel(profile.opt_level)).0,
             lto: lto.unwrap_or(profile.lto),
-            codegen_units: codegen_units,
+            codegen_units: codegen_units.or(profile.codegen_units),
             rustc_args: None,
             rustdoc_args: None,
             debuginfo: debug.unwrap_or(profile.debuginfo),
@@ -77,17 +77,20 @@ pub struct NewCrate {
     pub license_file: Option<String>,
     pub repository: Option<String>,
     pub badges: HashMap<String, HashMap<String, String>>,
+    pub rust_version: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct NewCrateDependency {
     pub optional: bool,
+    pub public: bool,
     pub default_features: bool,
     pub name: String,
     pub features: Vec<String>,
     pub version_req: String,
     pub target: Option<String>,
     pub kind: String,
+    pub registry: Option<String>,
 }
 
 #[derive(Deserialize)]
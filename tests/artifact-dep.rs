@@ -0,0 +1,189 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+#[test]
+fn build_script_uses_bin_artifact() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [build-dependencies]
+            bar = { path = "bar", artifact = "bin" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            use std::env;
+            use std::process::Command;
+
+            fn main() {
+                let bin = env::var("CARGO_BIN_FILE_BAR_bar").unwrap();
+                let out = Command::new(&bin).output().unwrap();
+                assert_eq!(String::from_utf8(out.stdout).unwrap(), "hello\n");
+            }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar bar[/]src[/]main.rs [..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name build_script_build [..]--extern bar[..]`"));
+}
+
+#[test]
+fn test_uses_bin_artifact() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dev-dependencies]
+            bar = { path = "bar", artifact = "bin" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("tests/server.rs", r#"
+            use std::process::Command;
+
+            #[test]
+            fn runs_server() {
+                let out = Command::new(env!("CARGO_BIN_FILE_BAR_server"))
+                                  .output().unwrap();
+                assert_eq!(String::from_utf8(out.stdout).unwrap(), "serving\n");
+            }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [[bin]]
+            name = "server"
+            path = "src/main.rs"
+        "#)
+        .file("bar/src/main.rs", r#"
+            fn main() { println!("serving"); }
+        "#);
+
+    assert_that(p.cargo_process("test"),
+                execs().with_status(0)
+                       .with_stdout_contains("test runs_server ... ok"));
+}
+
+#[test]
+fn build_script_uses_cdylib_of_mixed_lib() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [build-dependencies]
+            bar = { path = "bar", artifact = "cdylib" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            use std::env;
+            use std::path::Path;
+
+            fn main() {
+                let lib = env::var("CARGO_CDYLIB_FILE_BAR").unwrap();
+                assert!(lib.ends_with(env::consts::DLL_SUFFIX), "{}", lib);
+                assert!(Path::new(&lib).exists(), "{}", lib);
+            }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [lib]
+            crate-type = ["staticlib", "cdylib"]
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn missing_artifact() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", artifact = "cdylib" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr("\
+[ERROR] dependency `bar` of `foo v0.0.1 ([..])` requires a `cdylib` artifact, \
+but package `bar v0.0.1 ([..])` does not have any such target
+"));
+}
+
+#[test]
+fn invalid_artifact_kind() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar", artifact = "staticlib" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  dependency (bar) has an invalid `artifact` of `staticlib`, expected `bin` \
+or `cdylib`
+"));
+}
@@ -971,6 +971,30 @@ Caused by:
 "));
 }
 
+#[test]
+fn public_dev_dependency() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [dev-dependencies]
+            bar = { path = "bar", public = true }
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+error: failed to parse manifest at `[..]`
+
+Caused by:
+  dependency (bar) is marked `public`, which is only allowed for normal \
+dependencies
+"));
+}
+
 #[test]
 fn bad_debuginfo() {
     let p = project("foo")
@@ -0,0 +1,76 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::{project, execs};
+use hamcrest::{assert_that, existing_file, is_not};
+
+#[test]
+fn build_script_is_a_node() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("build.rs", r#"
+            fn main() {
+                panic!("build scripts are not run for a build plan");
+            }
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--build-plan"),
+                execs().with_status(0).with_json(r#"
+                    {
+                        "reason": "build-plan",
+                        "invocations": [
+                            {
+                                "package_id": "foo 0.0.1 ([..])",
+                                "target": "{...}",
+                                "profile": "{...}",
+                                "kind": "host",
+                                "run_custom_build": false,
+                                "program": "[..]",
+                                "args": "{...}",
+                                "env": "{...}",
+                                "cwd": "[..]",
+                                "outputs": "{...}",
+                                "links": "{...}",
+                                "deps": []
+                            },
+                            {
+                                "package_id": "foo 0.0.1 ([..])",
+                                "target": "{...}",
+                                "profile": "{...}",
+                                "kind": "host",
+                                "run_custom_build": true,
+                                "program": "[..]build-script-build[..]",
+                                "args": [],
+                                "env": "{...}",
+                                "cwd": "[..]foo",
+                                "outputs": [],
+                                "links": {},
+                                "deps": [0]
+                            },
+                            {
+                                "package_id": "foo 0.0.1 ([..])",
+                                "target": "{...}",
+                                "profile": "{...}",
+                                "kind": "host",
+                                "run_custom_build": false,
+                                "program": "[..]",
+                                "args": "{...}",
+                                "env": "{...}",
+                                "cwd": "[..]",
+                                "outputs": ["[..]libfoo[..].rlib"],
+                                "links": "{...}",
+                                "deps": [1]
+                            }
+                        ]
+                    }
+                "#));
+
+    assert_that(&p.root().join("target/debug/libfoo.rlib"), is_not(existing_file()));
+}
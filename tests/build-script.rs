@@ -248,11 +248,13 @@ fn links_duplicates() {
     assert_that(p.cargo_process("build"),
                 execs().with_status(101)
                        .with_stderr("\
-[ERROR] native library `a` is being linked to by more than one package, and can only be \
-linked to by one package
+[ERROR] failed to select a version for `a` (required by `foo`):
+all possible versions conflict with previously selected packages
 
-  [..] v0.5.0 (file://[..])
-  [..] v0.5.0 (file://[..])
+  required by package `foo v0.5.0 ([..])`
+
+  previously selected package `foo v0.5.0 ([..])`
+  which links to the native library `a`, as do rejected versions of `a`: 0.5.0
 "));
 }
 
@@ -6,6 +6,7 @@ extern crate tempdir;
 use std::env;
 use std::fs::{self, File};
 use std::io::prelude::*;
+use std::path::Path;
 
 use cargo::util::process;
 use cargotest::{is_nightly, rustc_host, sleep_ms};
@@ -2909,3 +2910,125 @@ fn rustc_wrapper() {
                     "[RUNNING] `/usr/bin/env rustc --crate-name foo [..]")
                 .with_status(0));
 }
+
+fn pipelined_project() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file("src/main.rs", "extern crate foo; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "")
+}
+
+#[test]
+fn pipelined_libs_only_wait_for_metadata() {
+    // Needs a rustc supporting `--json artifacts`.
+    if !is_nightly() { return }
+
+    let p = pipelined_project();
+
+    assert_that(p.cargo_process("build").arg("-v")
+                 .env("CARGO_BUILD_PIPELINING", "true"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar [..]--emit=dep-info,metadata,link [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name foo src[/]lib.rs [..]\
+--extern bar=[..]libbar-[..].rmeta`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name foo src[/]main.rs [..]\
+--extern bar=[..]libbar-[..].rlib[..]`"));
+}
+
+#[test]
+fn pipelining_disabled_by_config() {
+    let p = pipelined_project();
+
+    assert_that(p.cargo_process("build").arg("-v")
+                 .env("CARGO_BUILD_PIPELINING", "false"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar [..]--emit=dep-info,link [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name foo src[/]lib.rs [..]\
+--extern bar=[..]libbar-[..].rlib`"));
+}
+
+#[test]
+fn pipelined_bin_waits_for_transitive_rlibs() {
+    // The wrapper is a shell script.
+    if cfg!(windows) || !is_nightly() { return }
+
+    // The binary only reaches `baz` through `bar`, and `baz` keeps running
+    // for a while after writing its metadata. Linking the binary before
+    // `baz` is done fails.
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { path = "../baz" }
+        "#)
+        .file("bar/src/lib.rs", "extern crate baz;")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "")
+        .file("wrapper.sh", r#"#!/bin/sh
+            case "$*" in
+                *"--crate-name foo "*) test -f "$BAZ_DONE" || exit 1 ;;
+            esac
+            "$@" || exit $?
+            case "$*" in
+                *"--crate-name baz "*) sleep 2; touch "$BAZ_DONE" ;;
+            esac
+        "#);
+    p.build();
+    set_executable(&p.root().join("wrapper.sh"));
+
+    assert_that(p.cargo("build").arg("-j4")
+                 .env("CARGO_BUILD_PIPELINING", "true")
+                 .env("RUSTC_WRAPPER", p.root().join("wrapper.sh"))
+                 .env("BAZ_DONE", p.root().join("baz-done")),
+                execs().with_status(0));
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(path, perms).unwrap();
+}
+
+#[cfg(windows)]
+fn set_executable(_path: &Path) {}
@@ -805,6 +805,8 @@ fn substitute_macros(input: &str) -> String {
         ("[UPDATING]",    "    Updating"),
         ("[ADDING]",      "      Adding"),
         ("[REMOVING]",    "    Removing"),
+        ("[DOWNGRADING]", " Downgrading"),
+        ("[HOLDING]",     "     Holding"),
         ("[DOCTEST]",     "   Doc-tests"),
         ("[PACKAGING]",   "   Packaging"),
         ("[DOWNLOADING]", " Downloading"),
@@ -18,6 +18,10 @@ pub fn registry_path() -> PathBuf { paths::root().join("registry") }
 pub fn registry() -> Url { Url::from_file_path(&*registry_path()).ok().unwrap() }
 pub fn dl_path() -> PathBuf { paths::root().join("dl") }
 pub fn dl_url() -> Url { Url::from_file_path(&*dl_path()).ok().unwrap() }
+pub fn alt_registry_path() -> PathBuf { paths::root().join("alternative-registry") }
+pub fn alt_registry() -> Url { Url::from_file_path(&*alt_registry_path()).ok().unwrap() }
+pub fn alt_dl_path() -> PathBuf { paths::root().join("alt_dl") }
+pub fn alt_dl_url() -> Url { Url::from_file_path(&*alt_dl_path()).ok().unwrap() }
 
 pub struct Package {
     name: String,
@@ -25,8 +29,12 @@ pub struct Package {
     deps: Vec<Dependency>,
     files: Vec<(String, String)>,
     yanked: bool,
+    yanked_reason: Option<String>,
     features: HashMap<String, Vec<String>>,
     local: bool,
+    alternative: bool,
+    links: Option<String>,
+    rust_version: Option<String>,
 }
 
 struct Dependency {
@@ -35,6 +43,7 @@ struct Dependency {
     kind: String,
     target: Option<String>,
     features: Vec<String>,
+    public: bool,
 }
 
 pub fn init() {
@@ -53,7 +62,10 @@ pub fn init() {
 
         [source.dummy-registry]
         registry = '{reg}'
-    "#, reg = registry()).as_bytes()));
+
+        [registries.alternative]
+        index = '{alt}'
+    "#, reg = registry(), alt = alt_registry()).as_bytes()));
 
     // Init a new registry
     repo(&registry_path())
@@ -62,6 +74,14 @@ pub fn init() {
         "#, dl_url()))
         .build();
     fs::create_dir_all(dl_path().join("api/v1/crates")).unwrap();
+
+    // Init an alternate registry
+    repo(&alt_registry_path())
+        .file("config.json", &format!(r#"
+            {{"dl":"{0}","api":"{0}"}}
+        "#, alt_dl_url()))
+        .build();
+    fs::create_dir_all(alt_dl_path().join("api/v1/crates")).unwrap();
 }
 
 impl Package {
@@ -73,8 +93,12 @@ impl Package {
             deps: Vec::new(),
             files: Vec::new(),
             yanked: false,
+            yanked_reason: None,
             features: HashMap::new(),
             local: false,
+            alternative: false,
+            links: None,
+            rust_version: None,
         }
     }
 
@@ -83,6 +107,21 @@ impl Package {
         self
     }
 
+    pub fn alternative(&mut self, alternative: bool) -> &mut Package {
+        self.alternative = alternative;
+        self
+    }
+
+    pub fn links(&mut self, links: &str) -> &mut Package {
+        self.links = Some(links.to_string());
+        self
+    }
+
+    pub fn rust_version(&mut self, rust_version: &str) -> &mut Package {
+        self.rust_version = Some(rust_version.to_string());
+        self
+    }
+
     pub fn file(&mut self, name: &str, contents: &str) -> &mut Package {
         self.files.push((name.to_string(), contents.to_string()));
         self
@@ -110,6 +149,16 @@ impl Package {
         self.full_dep(name, vers, None, "dev", &[])
     }
 
+    pub fn build_dep(&mut self, name: &str, vers: &str) -> &mut Package {
+        self.full_dep(name, vers, None, "build", &[])
+    }
+
+    pub fn public_dep(&mut self, name: &str, vers: &str) -> &mut Package {
+        self.full_dep(name, vers, None, "normal", &[]);
+        self.deps.last_mut().unwrap().public = true;
+        self
+    }
+
     fn full_dep(&mut self,
                 name: &str,
                 vers: &str,
@@ -122,15 +171,28 @@ impl Package {
             kind: kind.to_string(),
             target: target.map(|s| s.to_string()),
             features: features.iter().map(|s| s.to_string()).collect(),
+            public: false,
         });
         self
     }
 
+    pub fn feature(&mut self, name: &str, deps: &[&str]) -> &mut Package {
+        self.features.insert(name.to_string(),
+                             deps.iter().map(|s| s.to_string()).collect());
+        self
+    }
+
     pub fn yanked(&mut self, yanked: bool) -> &mut Package {
         self.yanked = yanked;
         self
     }
 
+    pub fn yanked_reason(&mut self, reason: &str) -> &mut Package {
+        self.yanked = true;
+        self.yanked_reason = Some(reason.to_string());
+        self
+    }
+
     pub fn publish(&self) -> String {
         self.make_archive();
 
@@ -143,6 +205,7 @@ impl Package {
                 "default_features": true,
                 "target": dep.target,
                 "optional": false,
+                "public": dep.public,
                 "kind": dep.kind,
             })
         }).collect::<Vec<_>>();
@@ -158,6 +221,9 @@ impl Package {
             "cksum": cksum,
             "features": self.features,
             "yanked": self.yanked,
+            "yanked_reason": self.yanked_reason,
+            "links": self.links,
+            "rust_version": self.rust_version,
         }).to_string();
 
         let file = match self.name.len() {
@@ -167,11 +233,17 @@ impl Package {
             _ => format!("{}/{}/{}", &self.name[0..2], &self.name[2..4], self.name),
         };
 
+        let registry_path = if self.alternative {
+            alt_registry_path()
+        } else {
+            registry_path()
+        };
+
         // Write file/line in the index
         let dst = if self.local {
-            registry_path().join("index").join(&file)
+            registry_path.join("index").join(&file)
         } else {
-            registry_path().join(&file)
+            registry_path.join(&file)
         };
         let mut prev = String::new();
         let _ = File::open(&dst).and_then(|mut f| f.read_to_string(&mut prev));
@@ -181,7 +253,7 @@ impl Package {
 
         // Add the new file to the index
         if !self.local {
-            let repo = t!(git2::Repository::open(&registry_path()));
+            let repo = t!(git2::Repository::open(&registry_path));
             let mut index = t!(repo.index());
             t!(index.add_path(Path::new(&file)));
             t!(index.write());
@@ -207,6 +279,17 @@ impl Package {
             version = "{}"
             authors = []
         "#, self.name, self.vers);
+        if let Some(ref links) = self.links {
+            manifest.push_str(&format!(r#"
+                links = "{}"
+                build = "build.rs"
+            "#, links));
+        }
+        if let Some(ref rust_version) = self.rust_version {
+            manifest.push_str(&format!(r#"
+                rust-version = "{}"
+            "#, rust_version));
+        }
         for dep in self.deps.iter() {
             let target = match dep.target {
                 None => String::new(),
@@ -220,7 +303,14 @@ impl Package {
             manifest.push_str(&format!(r#"
                 [{}{}dependencies.{}]
                 version = "{}"
-            "#, target, kind, dep.name, dep.vers));
+                public = {}
+            "#, target, kind, dep.name, dep.vers, dep.public));
+        }
+        if !self.features.is_empty() {
+            manifest.push_str("\n[features]\n");
+            for (name, deps) in self.features.iter() {
+                manifest.push_str(&format!("{} = {:?}\n", name, deps));
+            }
         }
 
         let dst = self.archive_dst();
@@ -228,6 +318,9 @@ impl Package {
         let f = t!(File::create(&dst));
         let mut a = Builder::new(GzEncoder::new(f, Default));
         self.append(&mut a, "Cargo.toml", &manifest);
+        if self.links.is_some() {
+            self.append(&mut a, "build.rs", "fn main() {}");
+        }
         if self.files.is_empty() {
             self.append(&mut a, "src/lib.rs", "");
         } else {
@@ -251,7 +344,8 @@ impl Package {
             registry_path().join(format!("{}-{}.crate", self.name,
                                          self.vers))
         } else {
-            dl_path().join(&self.name).join(&self.vers).join("download")
+            let dl_path = if self.alternative { alt_dl_path() } else { dl_path() };
+            dl_path.join(&self.name).join(&self.vers).join("download")
         }
     }
 }
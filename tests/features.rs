@@ -6,6 +6,7 @@ use std::fs::File;
 use std::io::prelude::*;
 
 use cargotest::support::paths::CargoPathExt;
+use cargotest::support::registry::Package;
 use cargotest::support::{project, execs};
 use hamcrest::assert_that;
 
@@ -1007,3 +1008,529 @@ fn all_features_flag_enables_all_features() {
     assert_that(p.cargo_process("build").arg("--all-features"),
                 execs().with_status(0));
 }
+
+#[test]
+fn namespaced_same_name() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            bar = ["dep:bar", "extra"]
+            extra = []
+
+            [dependencies.bar]
+            path = "bar"
+            optional = true
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(feature = "bar")]
+            extern crate bar;
+
+            #[cfg(all(feature = "bar", feature = "extra"))]
+            fn main() { bar::bar() }
+
+            #[cfg(not(feature = "bar"))]
+            fn main() {}
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn bar() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stderr_does_not_contain("[COMPILING] bar [..]"));
+    assert_that(p.cargo("build").arg("--features").arg("bar"),
+                execs().with_status(0)
+                       .with_stderr_contains("[COMPILING] bar [..]"));
+}
+
+#[test]
+fn namespaced_no_implicit_feature() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            fancy = ["dep:baz"]
+
+            [dependencies.baz]
+            path = "baz"
+            optional = true
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(feature = "fancy")]
+            extern crate baz;
+
+            #[cfg(feature = "baz")]
+            pub fn broken() { not_enabled() }
+
+            fn main() {}
+        "#)
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--features").arg("fancy"),
+                execs().with_status(0));
+    assert_that(p.cargo("build").arg("--features").arg("baz"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] Package `foo v0.0.1 ([..])` does not have the feature `baz`.
+It has an optional dependency with that name, but that dependency is enabled \
+with `dep:baz` in its features, so it has no implicit feature with that name"));
+}
+
+#[test]
+fn namespaced_requires_dep_prefix() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            fancy = ["dep:baz"]
+            plain = ["baz"]
+
+            [dependencies.baz]
+            path = "baz"
+            optional = true
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  Feature `plain` includes `baz`, but `baz` is an optional dependency without \
+an implicit feature.
+Use `dep:baz` to enable the dependency
+"));
+}
+
+#[test]
+fn namespaced_non_optional() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            fancy = ["dep:baz"]
+
+            [dependencies.baz]
+            path = "baz"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  Feature `fancy` includes `dep:baz`, but `baz` is not an optional dependency.
+Consider adding `optional = true` to the dependency
+"));
+}
+
+#[test]
+fn weak_dep_feature() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            a = ["bar?/feat"]
+            b = ["bar"]
+
+            [dependencies.bar]
+            path = "bar"
+            optional = true
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(feature = "b")]
+            extern crate bar;
+
+            #[cfg(all(feature = "a", feature = "b"))]
+            fn main() { bar::feat() }
+
+            #[cfg(not(all(feature = "a", feature = "b")))]
+            fn main() {}
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            feat = []
+        "#)
+        .file("bar/src/lib.rs", r#"
+            #[cfg(feature = "feat")]
+            pub fn feat() {}
+        "#);
+
+    assert_that(p.cargo_process("build").arg("--features").arg("a"),
+                execs().with_status(0)
+                       .with_stderr_does_not_contain("[COMPILING] bar [..]"));
+    assert_that(p.cargo("build").arg("--features").arg("a b"),
+                execs().with_status(0)
+                       .with_stderr_contains("[COMPILING] bar [..]"));
+}
+
+#[test]
+fn weak_dep_feature_from_another_dependent() {
+    // `a` asks for `bar?/feat` before `b` enables `bar`, so the weak feature
+    // has to be remembered until then.
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = { path = "a" }
+            b = { path = "b" }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate a;
+            extern crate b;
+
+            fn main() { b::feat() }
+        "#)
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            common = { path = "../common", features = ["weak"] }
+        "#)
+        .file("a/src/lib.rs", "")
+        .file("b/Cargo.toml", r#"
+            [package]
+            name = "b"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            common = { path = "../common", features = ["bar"] }
+        "#)
+        .file("b/src/lib.rs", r#"
+            extern crate common;
+
+            pub fn feat() { common::feat() }
+        "#)
+        .file("common/Cargo.toml", r#"
+            [package]
+            name = "common"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            weak = ["bar?/feat"]
+
+            [dependencies.bar]
+            path = "../bar"
+            optional = true
+        "#)
+        .file("common/src/lib.rs", r#"
+            extern crate bar;
+
+            pub fn feat() { bar::feat() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            feat = []
+        "#)
+        .file("bar/src/lib.rs", r#"
+            #[cfg(feature = "feat")]
+            pub fn feat() {}
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn weak_dep_feature_non_optional() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            a = ["bar?/feat"]
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            feat = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  Feature `a` includes `bar?/feat` with a `?`, but `bar` is not an optional \
+dependency.
+A non-optional dependency is always enabled, so `bar/...` can be used instead
+"));
+}
+
+#[test]
+fn resolver2_build_dep_features_not_unified() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+            resolver = "2"
+
+            [dependencies]
+            common = { path = "common" }
+
+            [build-dependencies]
+            common = { path = "common", features = ["std"] }
+        "#)
+        .file("build.rs", r#"
+            extern crate common;
+            fn main() { assert!(common::STD); }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate common;
+            fn main() { println!("std: {}", common::STD); }
+        "#)
+        .file("common/Cargo.toml", r#"
+            [project]
+            name = "common"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            std = []
+        "#)
+        .file("common/src/lib.rs", r#"
+            #[cfg(feature = "std")]
+            pub const STD: bool = true;
+            #[cfg(not(feature = "std"))]
+            pub const STD: bool = false;
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0).with_stdout("std: false\n"));
+}
+
+#[test]
+fn resolver1_build_dep_features_unified() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [dependencies]
+            common = { path = "common" }
+
+            [build-dependencies]
+            common = { path = "common", features = ["std"] }
+        "#)
+        .file("build.rs", r#"
+            extern crate common;
+            fn main() { assert!(common::STD); }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate common;
+            fn main() { println!("std: {}", common::STD); }
+        "#)
+        .file("common/Cargo.toml", r#"
+            [project]
+            name = "common"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            std = []
+        "#)
+        .file("common/src/lib.rs", r#"
+            #[cfg(feature = "std")]
+            pub const STD: bool = true;
+            #[cfg(not(feature = "std"))]
+            pub const STD: bool = false;
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0).with_stdout("std: true\n"));
+}
+
+#[test]
+fn resolver2_dev_dep_features_only_for_dev_units() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            resolver = "2"
+
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            common = { path = "common" }
+
+            [dev-dependencies]
+            common = { path = "common", features = ["std"] }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate common;
+            fn main() { println!("std: {}", common::STD); }
+        "#)
+        .file("tests/std.rs", r#"
+            extern crate common;
+            #[test]
+            fn std_enabled() { assert!(common::STD); }
+        "#)
+        .file("common/Cargo.toml", r#"
+            [project]
+            name = "common"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            std = []
+        "#)
+        .file("common/src/lib.rs", r#"
+            #[cfg(feature = "std")]
+            pub const STD: bool = true;
+            #[cfg(not(feature = "std"))]
+            pub const STD: bool = false;
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0).with_stdout("std: false\n"));
+    assert_that(p.cargo("test"),
+                execs().with_status(0)
+                       .with_stdout_contains("test std_enabled ... ok"));
+}
+
+#[test]
+fn invalid_resolver() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            resolver = "3"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  `resolver` setting `3` is not valid, valid options are \"1\" or \"2\"
+"));
+}
+
+#[test]
+fn resolver2_patched_dep_features() {
+    Package::new("common", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            resolver = "2"
+
+            [dependencies]
+            common = { version = "0.1.0", features = ["std"] }
+
+            [patch.crates-io]
+            common = { path = "common" }
+        "#)
+        .file("src/main.rs", r#"
+            extern crate common;
+            fn main() { println!("std: {}", common::STD); }
+        "#)
+        .file("common/Cargo.toml", r#"
+            [project]
+            name = "common"
+            version = "0.1.0"
+            authors = []
+
+            [features]
+            std = []
+        "#)
+        .file("common/src/lib.rs", r#"
+            #[cfg(feature = "std")]
+            pub const STD: bool = true;
+            #[cfg(not(feature = "std"))]
+            pub const STD: bool = false;
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0).with_stdout("std: true\n"));
+}
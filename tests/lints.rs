@@ -0,0 +1,185 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::File;
+use std::io::prelude::*;
+
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+#[test]
+fn lints_are_passed_to_rustc() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [lints.rust]
+            unsafe_code = "forbid"
+            missing_docs = { level = "warn", priority = 1 }
+        "#)
+        .file("src/lib.rs", "
+            pub fn foo() -> u32 { unsafe { 0 } }
+        ");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101)
+                       .with_stderr_contains("\
+[RUNNING] `rustc [..]--forbid unsafe_code --warn missing_docs[..]`")
+                       .with_stderr_contains("[..]usage of an `unsafe` block[..]"));
+}
+
+#[test]
+fn lints_are_not_passed_to_dependencies() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+
+            [lints.rust]
+            unsafe_code = "forbid"
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "
+            pub fn bar() -> u32 { unsafe { 0 } }
+        ");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name foo [..]--forbid unsafe_code[..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name bar [..]--forbid[..]`"));
+}
+
+#[test]
+fn lints_of_path_dependencies_outside_workspace_are_ignored() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/lib.rs", "extern crate bar;")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [lints.rust]
+            unsafe_code = "forbid"
+        "#)
+        .file("bar/src/lib.rs", "
+            pub fn bar() -> u32 { unsafe { 0 } }
+        ");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name bar [..]--forbid[..]`"));
+}
+
+#[test]
+fn lints_inherited_from_workspace() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+
+            [workspace.lints.rust]
+            unsafe_code = "deny"
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [lints]
+            workspace = true
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar [..]--deny unsafe_code[..]`"));
+}
+
+#[test]
+fn changing_lints_rebuilds() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [lints.rust]
+            unsafe_code = "warn"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+    assert_that(p.cargo("build"),
+                execs().with_status(0)
+                       .with_stderr_does_not_contain("[COMPILING] foo [..]"));
+
+    File::create(p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [lints.rust]
+        unsafe_code = "deny"
+    "#).unwrap();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0)
+                       .with_stderr_contains("[COMPILING] foo [..]"));
+}
+
+#[test]
+fn invalid_lint_level() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [lints.rust]
+            unsafe_code = "loud"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  `lints.rust.unsafe_code` has an invalid level `loud`, expected one of \
+`forbid`, `deny`, `warn` or `allow`
+"));
+}
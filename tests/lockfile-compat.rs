@@ -41,8 +41,34 @@ source = "registry+https://github.com/rust-lang/crates.io-index"
     assert_that(p.cargo("build"),
                 execs().with_status(0));
 
-    let lock = p.read_lockfile();
-    assert!(lock.starts_with(lockfile.trim()));
+    // The checksum of `foo` has to be added, so the lock file is rewritten in
+    // the current format.
+    let expected = "\
+version = 2
+
+[[package]]
+name = \"bar\"
+version = \"0.0.1\"
+dependencies = [
+ \"foo\",
+]
+
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+checksum = \"[..]\"
+
+";
+    assert_lockfile(&p.read_lockfile(), expected);
+}
+
+fn assert_lockfile(actual: &str, expected: &str) {
+    for (l, r) in expected.lines().zip(actual.lines()) {
+        assert!(lines_match(l, r), "Lines differ:\n{}\n\n{}", l, r);
+    }
+
+    assert_eq!(actual.lines().count(), expected.lines().count());
 }
 
 #[test]
@@ -85,20 +111,21 @@ source = "registry+https://github.com/rust-lang/crates.io-index"
 
     let lock = p.read_lockfile();
     assert!(lock.starts_with(r#"
-[root]
+version = 2
+
+[[package]]
 name = "bar"
 version = "0.0.1"
 dependencies = [
- "foo 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+ "foo",
 ]
 
 [[package]]
 name = "foo"
 version = "0.1.0"
 source = "registry+https://github.com/rust-lang/crates.io-index"
-
-[metadata]
 "#.trim()));
+    assert!(!lock.contains("[metadata]"), "{}", lock);
 }
 
 #[test]
@@ -284,29 +311,24 @@ fn current_lockfile_format() {
 
     assert_that(p.cargo("build"), execs().with_status(0));
 
-    let actual = p.read_lockfile();
-
     let expected = "\
-[root]
+version = 2
+
+[[package]]
 name = \"bar\"
 version = \"0.0.1\"
 dependencies = [
- \"foo 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)\",
+ \"foo\",
 ]
 
 [[package]]
 name = \"foo\"
 version = \"0.1.0\"
 source = \"registry+https://github.com/rust-lang/crates.io-index\"
+checksum = \"[..]\"
 
-[metadata]
-\"checksum foo 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)\" = \"[..]\"";
-
-    for (l, r) in expected.lines().zip(actual.lines()) {
-        assert!(lines_match(l, r), "Lines differ:\n{}\n\n{}", l, r);
-    }
-
-    assert_eq!(actual.lines().count(), expected.lines().count());
+";
+    assert_lockfile(&p.read_lockfile(), expected);
 }
 
 #[test]
@@ -344,5 +366,241 @@ source = "registry+https://github.com/rust-lang/crates.io-index"
     assert_that(p.cargo("build"), execs().with_status(0));
 
     let lock = p.read_lockfile();
-    assert!(lock.starts_with(lockfile.trim()));
+    assert!(lock.starts_with("version = 2\n\n[[package]]\nname = \"bar\""), "{}", lock);
+}
+
+#[test]
+fn v1_lockfile_kept_if_unchanged() {
+    let lockfile = r#"[[package]]
+name = "bar"
+version = "0.0.1"
+dependencies = [
+ "baz 0.0.1",
+]
+
+[[package]]
+name = "baz"
+version = "0.0.1"
+
+"#;
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { path = "baz" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "")
+        .file("Cargo.lock", lockfile);
+
+    p.build();
+
+    assert_that(p.cargo("build"), execs().with_status(0));
+
+    assert_eq!(p.read_lockfile(), lockfile);
+}
+
+#[test]
+fn v2_lockfile_lists_ambiguous_versions() {
+    Package::new("foo", "0.1.0").publish();
+    Package::new("foo", "0.2.0").publish();
+    Package::new("baz", "0.1.0").dep("foo", "0.1.0").publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "0.1.0"
+            foo = "0.2.0"
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    assert_that(p.cargo("generate-lockfile"), execs().with_status(0));
+
+    let expected = "\
+version = 2
+
+[[package]]
+name = \"bar\"
+version = \"0.0.1\"
+dependencies = [
+ \"baz\",
+ \"foo 0.2.0\",
+]
+
+[[package]]
+name = \"baz\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+checksum = \"[..]\"
+dependencies = [
+ \"foo 0.1.0\",
+]
+
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+checksum = \"[..]\"
+
+[[package]]
+name = \"foo\"
+version = \"0.2.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+checksum = \"[..]\"
+
+";
+    let lock = p.read_lockfile();
+    assert_lockfile(&lock, expected);
+
+    // Loading the abbreviated ids again doesn't change anything.
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert_eq!(p.read_lockfile(), lock);
+}
+
+#[test]
+fn unknown_lockfile_version() {
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", r#"
+version = 3
+
+[[package]]
+name = "bar"
+version = "0.0.1"
+"#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]lock file version `3` was found, but this version of Cargo does not \
+understand it, perhaps Cargo needs to be updated?"));
+}
+
+#[test]
+fn merge_conflict_combines_both_sides() {
+    Package::new("foo", "0.1.0").publish();
+    Package::new("foo", "0.1.1").publish();
+    Package::new("foo", "0.1.2").publish();
+    Package::new("baz", "0.1.0").publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "0.1"
+            foo = "0.1"
+        "#)
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", r#"version = 2
+
+[[package]]
+name = "bar"
+version = "0.0.1"
+dependencies = [
+<<<<<<< HEAD
+ "foo",
+]
+
+[[package]]
+name = "foo"
+version = "0.1.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+=======
+ "baz",
+ "foo",
+]
+
+[[package]]
+name = "baz"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+>>>>>>> other
+"#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr_contains("\
+[WARNING] Cargo.lock contains merge conflicts, resolving them by combining \
+both sides"));
+
+    let lock = p.read_lockfile();
+    assert!(!lock.contains("<<<<<<<"), "{}", lock);
+    assert!(lock.contains("name = \"baz\"\nversion = \"0.1.0\""), "{}", lock);
+    assert!(lock.contains("name = \"foo\"\nversion = \"0.1.1\""), "{}", lock);
+    assert!(!lock.contains("version = \"0.1.2\""), "{}", lock);
+}
+
+#[test]
+fn merge_conflict_with_differing_checksums() {
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1"
+        "#)
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", r#"version = 2
+
+[[package]]
+name = "bar"
+version = "0.0.1"
+dependencies = [
+ "foo",
+]
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+<<<<<<< HEAD
+checksum = "aaaa"
+=======
+checksum = "bbbb"
+>>>>>>> other
+"#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse lock file at: [..]
+
+Caused by:
+  the two sides of the merge conflict cannot be reconciled
+
+Caused by:
+  checksum for `foo v0.1.0` differs between the two sides of the conflict
+"));
 }
@@ -183,6 +183,7 @@ fn cargo_metadata_with_deps_and_version() {
                         "kind": null,
                         "name": "baz",
                         "optional": false,
+                        "public": false,
                         "req": "^0.0.1",
                         "source": "registry+[..]",
                         "target": null,
@@ -218,6 +219,7 @@ fn cargo_metadata_with_deps_and_version() {
                         "kind": null,
                         "name": "bar",
                         "optional": false,
+                        "public": false,
                         "req": "*",
                         "source": "registry+[..]",
                         "target": null,
@@ -667,3 +669,51 @@ fn multiple_features() {
                  .arg("--features").arg("a b"),
                 execs().with_status(0));
 }
+
+#[test]
+fn cargo_metadata_default_run() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            default-run = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("metadata").arg("--no-deps"),
+                execs().with_json(r#"
+    {
+        "packages": [
+            {
+                "name": "foo",
+                "version": "0.5.0",
+                "id": "foo[..]",
+                "source": null,
+                "dependencies": [],
+                "license": null,
+                "license_file": null,
+                "description": null,
+                "targets": [
+                    {
+                        "kind": [
+                            "bin"
+                        ],
+                        "crate_types": [
+                            "bin"
+                        ],
+                        "name": "foo",
+                        "src_path": "[..][/]foo[/]src[/]main.rs"
+                    }
+                ],
+                "features": {},
+                "manifest_path": "[..]Cargo.toml",
+                "default_run": "foo"
+            }
+        ],
+        "workspace_members": ["foo 0.5.0 (path+file:[..]foo)"],
+        "resolve": null,
+        "version": 1
+    }"#));
+}
@@ -295,6 +295,133 @@ See http://doc.crates.io/manifest.html#package-metadata for more info.
 "));
 }
 
+#[test]
+fn exclude_gitignore_patterns() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            exclude = [
+                "*.txt",
+                "!keep.txt",
+                "/notes.md",
+                "fixtures/",
+            ]
+        "#)
+        .file("src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#)
+        .file("bar.txt", "")
+        .file("src/bar.txt", "")
+        .file("src/keep.txt", "")
+        .file("notes.md", "")
+        .file("src/notes.md", "")
+        .file("src/fixtures/data.rs", "")
+        .file("fixtures", "");
+
+    assert_that(p.cargo_process("package").arg("-l"),
+                execs().with_status(0).with_stdout("\
+Cargo.toml
+fixtures
+src[/]keep.txt
+src[/]main.rs
+src[/]notes.md
+"));
+}
+
+#[test]
+fn include_gitignore_patterns_warn_about_changes() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            include = ["Cargo.toml", "src/*.rs"]
+        "#)
+        .file("src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#)
+        .file("src/nested/mod.rs", "");
+
+    assert_that(p.cargo_process("package").arg("-l"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[WARNING] the patterns of `package.include` are now matched like `.gitignore` \
+patterns rather than as plain globs, which changes whether these files are \
+packaged:
+  src[/]nested[/]mod.rs (no longer included)")
+                       .with_stdout("\
+Cargo.toml
+src[/]main.rs
+"));
+}
+
+#[test]
+fn include_gitignore_patterns_warn_once() {
+    let p = git::repo(&paths::root().join("foo"))
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            include = ["Cargo.toml", "src/*.rs"]
+        "#)
+        .file("src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#)
+        .file("src/nested/mod.rs", "");
+    p.build();
+
+    // The files are listed both to check that they're committed and to
+    // archive them.
+    assert_that(cargo_process().arg("package").arg("--no-verify").cwd(p.root()),
+                execs().with_status(0).with_stderr("\
+[WARNING] manifest has no description[..]
+See http://doc.crates.io/manifest.html#package-metadata for more info.
+[WARNING] the patterns of `package.include` are now matched like `.gitignore` \
+patterns rather than as plain globs, which changes whether these files are \
+packaged:
+  src[/]nested[/]mod.rs (no longer included)
+[PACKAGING] foo v0.0.1 ([..])
+"));
+}
+
+#[test]
+fn include_gitignore_patterns_no_warning_when_building() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+            include = ["Cargo.toml", "src/*.rs"]
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("bar/src/nested/mod.rs", "");
+
+    // Only packaging warns about the changed patterns, so path dependencies
+    // don't warn on every build.
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+[COMPILING] bar v0.0.1 ([..])
+[COMPILING] foo v0.0.1 ([..])
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+"));
+}
+
 #[test]
 fn package_lib_with_bin() {
     let p = project("foo")
@@ -588,3 +715,51 @@ Cargo.toml
 to proceed despite this, pass the `--allow-dirty` flag
 "));
 }
+
+#[test]
+fn package_inherited_fields() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+
+            [workspace.package]
+            version = "1.2.3"
+            license = "MIT"
+            description = "bar"
+            documentation = "https://example.com/"
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = { workspace = true }
+            authors = []
+            license = { workspace = true }
+            description = { workspace = true }
+            documentation = { workspace = true }
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    assert_that(p.cargo("package").cwd(p.root().join("bar")),
+                execs().with_status(0));
+
+    let f = File::open(&p.root().join("target/package/bar-1.2.3.crate")).unwrap();
+    let mut rdr = GzDecoder::new(f).unwrap();
+    let mut contents = Vec::new();
+    rdr.read_to_end(&mut contents).unwrap();
+    let mut ar = Archive::new(&contents[..]);
+    let mut manifest = None;
+    for f in ar.entries().unwrap() {
+        let mut f = f.unwrap();
+        if &*f.header().path_bytes() == b"bar-1.2.3/Cargo.toml" {
+            let mut s = String::new();
+            f.read_to_string(&mut s).unwrap();
+            manifest = Some(s);
+        }
+    }
+    let manifest = manifest.expect("Cargo.toml not packaged");
+    assert!(manifest.contains("version = \"1.2.3\""), "{}", manifest);
+    assert!(manifest.contains("license = \"MIT\""), "{}", manifest);
+    assert!(!manifest.contains("workspace"), "{}", manifest);
+}
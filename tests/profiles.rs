@@ -2,10 +2,11 @@ extern crate cargotest;
 extern crate hamcrest;
 
 use std::env;
+use std::io::Write;
 
 use cargotest::is_nightly;
-use cargotest::support::{project, execs};
-use hamcrest::assert_that;
+use cargotest::support::{git, paths, project, execs};
+use hamcrest::{assert_that, existing_dir, existing_file, is_not};
 
 #[test]
 fn profile_overrides() {
@@ -283,3 +284,365 @@ fn profile_in_virtual_manifest_works() {
 [RUNNING] `rustc [..]`
 [FINISHED] dev [optimized] target(s) in [..]"));
 }
+
+#[test]
+fn package_profile_override() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+            baz = { path = "baz" }
+
+            [profile.dev.package.bar]
+            opt-level = 3
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar bar[/]src[/]lib.rs [..]-C opt-level=3 [..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name baz [..]-C opt-level=3 [..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name foo [..]-C opt-level=3 [..]`"));
+}
+
+#[test]
+fn build_override() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [build-dependencies]
+            bar = { path = "bar" }
+
+            [profile.dev.build-override]
+            opt-level = 2
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar [..]-C opt-level=2 [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name build_script_build [..]-C opt-level=2 [..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name foo [..]-C opt-level=2 [..]`"));
+}
+
+#[test]
+fn build_override_not_applied_to_normal_deps() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [dependencies]
+            baz = { path = "baz" }
+
+            [build-dependencies]
+            bar = { path = "bar" }
+
+            [profile.dev.build-override]
+            opt-level = 2
+        "#)
+        .file("src/lib.rs", "extern crate baz;")
+        .file("build.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    // Without `--target` everything is built for the host, but only the build
+    // script and its dependencies use `build-override`.
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar [..]-C opt-level=2 [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name baz baz[/]src[/]lib.rs [..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name baz [..]-C opt-level=2 [..]`")
+                       .with_stderr_does_not_contain("\
+[RUNNING] `rustc --crate-name foo [..]-C opt-level=2 [..]`"));
+}
+
+#[test]
+fn profile_override_changes_rebuild() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+
+    ::std::fs::File::create(p.root().join("Cargo.toml")).unwrap()
+        .write_all(br#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+
+            [profile.dev.package.bar]
+            opt-level = 1
+        "#).unwrap();
+
+    assert_that(p.cargo("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name bar [..]-C opt-level=1 [..]`"));
+}
+
+#[test]
+fn profile_override_bad_settings() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.dev.package.bar]
+            panic = "abort"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]`panic` may not be specified in a `package` profile"));
+}
+
+#[test]
+fn custom_profile_inherits() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.release-checked]
+            inherits = "release"
+            debug-assertions = true
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v").arg("--profile=release-checked"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] foo v0.0.1 ({url})
+[RUNNING] `rustc --crate-name foo src[/]lib.rs --crate-type lib \
+        --emit=dep-info,link \
+        -C opt-level=3 \
+        -C debug-assertions=on \
+        -C metadata=[..] \
+        --out-dir [..] \
+        -L dependency={dir}[/]target[/]release-checked[/]deps`
+[FINISHED] release-checked [optimized] target(s) in [..]
+",
+dir = p.root().display(),
+url = p.url(),
+)));
+    assert_that(&p.root().join("target/release-checked/libfoo.rlib"),
+                existing_file());
+    assert_that(&p.root().join("target/release"), is_not(existing_dir()));
+}
+
+#[test]
+fn custom_profile_inherits_custom() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.fast]
+            inherits = "dev"
+            opt-level = 2
+
+            [profile.fast-checked]
+            inherits = "fast"
+            overflow-checks = false
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v").arg("--profile=fast-checked"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc --crate-name foo [..]-C opt-level=2 [..]-C overflow-checks=off [..]`")
+                       .with_stderr_contains("\
+[FINISHED] fast-checked [optimized + debuginfo] target(s) in [..]"));
+}
+
+#[test]
+fn custom_profile_not_defined() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--profile=nope"),
+                execs().with_status(101).with_stderr("\
+[ERROR] profile `nope` is not defined
+"));
+}
+
+#[test]
+fn custom_profile_missing_inherits() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.fast]
+            opt-level = 2
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]profile `fast` is missing an `inherits` directive [..]"));
+}
+
+#[test]
+fn custom_profile_inherits_cycle() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.a]
+            inherits = "b"
+
+            [profile.b]
+            inherits = "a"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]profile `a` inherits from itself"));
+}
+
+#[test]
+fn custom_profile_conflicts_with_release() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [profile.fast]
+            inherits = "release"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--release").arg("--profile=fast"),
+                execs().with_status(101).with_stderr("\
+[ERROR] the `--release` flag cannot be combined with `--profile`
+"));
+}
+
+#[test]
+fn custom_profile_of_git_dep_ignored() {
+    let bar = git::repo(&paths::root().join("bar"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [profile.fast]
+            opt-level = 2
+        "#)
+        .file("src/lib.rs", "");
+    bar.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = {{ git = '{}' }}
+        "#, bar.url()))
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
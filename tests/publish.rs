@@ -115,9 +115,9 @@ fn git_deps() {
                  .arg("--host").arg(registry().to_string()),
                 execs().with_status(101).with_stderr("\
 [UPDATING] registry [..]
-[ERROR] crates cannot be published to crates.io with dependencies sourced from \
-a repository\neither publish `foo` as its own crate on crates.io and \
-specify a crates.io version as a dependency or pull it into this \
+[ERROR] crates cannot be published to registry `[..]` with dependencies \
+sourced from a repository\neither publish `foo` as its own crate on \
+registry `[..]` and specify a version from it as a dependency or pull it into this \
 repository and specify it with a path and version\n\
 (crate `foo` has repository path `git://path/to/nowhere`)\
 "));
@@ -386,3 +386,41 @@ See [..]
     // Ensure the API request wasn't actually made
     assert!(!upload_path().join("api/v1/crates/new").exists());
 }
+
+#[test]
+fn registry_dep_not_allowed() {
+    setup();
+
+    let restricted = paths::root().join("restricted-registry");
+    repo(&restricted)
+        .file("config.json", &format!(r#"{{
+            "dl": "{0}",
+            "api": "{0}",
+            "allowed-registries": ["file:///allowed"]
+        }}"#, upload()))
+        .build();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry-index = "file:///other"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--host").arg(Url::from_file_path(&restricted).unwrap().to_string()),
+                execs().with_status(101).with_stderr("\
+[UPDATING] registry [..]
+[ERROR] crates cannot be published to registry `[..]restricted-registry` with \
+dependencies from registries it does not allow
+dependency `bar` is from registry `file:///other`
+"));
+}
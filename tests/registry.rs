@@ -438,6 +438,84 @@ version required: *
 "));
 }
 
+#[test]
+fn yanks_in_lockfiles_warn() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "0.0.1").publish();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0));
+
+    registry::registry_path().join("3").rm_rf();
+    paths::home().join(".cargo/registry").rm_rf();
+
+    Package::new("bar", "0.0.1").yanked_reason("broken build").publish();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr_contains("\
+[WARNING] package `bar v0.0.1` in Cargo.lock has been yanked (broken build), \
+consider running `cargo update --yanked` to move to a version which isn't
+"));
+}
+
+#[test]
+fn update_yanked() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "1"
+            baz = "1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").publish();
+
+    assert_that(p.cargo("generate-lockfile"),
+                execs().with_status(0));
+
+    registry::registry_path().join("3").rm_rf();
+
+    Package::new("bar", "1.0.0").yanked(true).publish();
+    Package::new("bar", "1.0.1").publish();
+    Package::new("bar", "1.1.0").publish();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("baz", "1.0.1").publish();
+
+    assert_that(p.cargo("update").arg("--yanked"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry `[..]`
+[UPDATING] bar v1.0.0 -> v1.0.1
+"));
+
+    let lock = p.read_lockfile();
+    assert!(lock.contains("name = \"baz\"\nversion = \"1.0.0\""), "{}", lock);
+    assert!(!lock.contains("version = \"1.1.0\""), "{}", lock);
+
+    assert_that(p.cargo("update").arg("--yanked").arg("-p").arg("baz"),
+                execs().with_status(101).with_stderr("\
+[ERROR] cannot specify `--yanked` together with packages to update
+"));
+}
+
 #[test]
 fn update_with_lockfile_if_packages_missing() {
     let p = project("foo")
@@ -548,6 +626,86 @@ fn update_lockfile() {
 "));
 }
 
+#[test]
+fn update_dry_run() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "0.0.1").publish();
+    assert_that(p.cargo("generate-lockfile"),
+                execs().with_status(0));
+
+    Package::new("bar", "0.0.2").publish();
+    Package::new("baz", "0.1.0").publish();
+    assert_that(p.cargo("update").arg("--dry-run"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry `[..]`
+[UPDATING] bar v0.0.1 -> v0.0.2
+[WARNING] not updating lockfile due to dry run
+"));
+
+    assert_that(p.cargo("update").arg("--dry-run")
+                 .arg("--message-format").arg("json"),
+                execs().with_status(0).with_json(r#"
+    {
+        "reason": "lockfile-diff",
+        "dry_run": true,
+        "changes": [
+            {
+                "kind": "upgraded",
+                "name": "bar",
+                "source": "registry+[..]",
+                "old_version": "0.0.1",
+                "new_version": "0.0.2"
+            }
+        ]
+    }"#));
+
+    let mut lock = String::new();
+    File::open(p.root().join("Cargo.lock")).unwrap()
+        .read_to_string(&mut lock).unwrap();
+    assert!(lock.contains("version = \"0.0.1\""));
+    assert!(!lock.contains("version = \"0.0.2\""));
+}
+
+#[test]
+fn update_reports_downgrades() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "0.0.1").publish();
+    Package::new("bar", "0.0.2").publish();
+    assert_that(p.cargo("generate-lockfile"),
+                execs().with_status(0));
+
+    assert_that(p.cargo("update")
+                 .arg("-p").arg("bar").arg("--precise").arg("0.0.1"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry `[..]`
+[DOWNGRADING] bar v0.0.2 -> v0.0.1
+"));
+}
+
 #[test]
 fn dev_dependency_not_used() {
     let p = project("foo")
@@ -1408,3 +1566,131 @@ fn vv_prints_warnings() {
     assert_that(p.cargo("build").arg("-vv"),
                 execs().with_status(0));
 }
+
+#[test]
+fn resolve_conflict_prints_both_paths() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.1").publish();
+    Package::new("baz", "1.0.0").dep("bar", "=0.1.0").publish();
+    Package::new("qux", "1.0.0").dep("bar", "=0.1.1").publish();
+    Package::new("qux", "1.0.1").dep("bar", "=0.1.1").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "1"
+            qux = "1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr("\
+[UPDATING] registry `[..]`
+[ERROR] failed to select a version for `bar` (required by `qux`):
+all possible versions conflict with previously selected packages
+
+  required by package `qux v1.0.0 ([..])`
+    ... which satisfies dependency `qux = \"[..]\"` of package `foo v0.0.1 ([..])`
+
+  previously selected package `bar v0.1.0 ([..])`
+    ... which satisfies dependency `bar = \"[..]\"` of package `baz v1.0.0 ([..])`
+    ... which satisfies dependency `baz = \"[..]\"` of package `foo v0.0.1 ([..])`
+  which is semver-compatible with rejected versions of `bar`: 0.1.1
+"));
+}
+
+#[test]
+fn resolve_conflict_on_links() {
+    Package::new("a-sys", "1.0.0").links("z").publish();
+    Package::new("b-sys", "1.0.0").links("z").publish();
+    Package::new("b-sys", "1.1.0").links("z").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a-sys = "1"
+            b-sys = "1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr("\
+[UPDATING] registry `[..]`
+[ERROR] failed to select a version for `b-sys` (required by `foo`):
+all possible versions conflict with previously selected packages
+
+  required by package `foo v0.0.1 ([..])`
+
+  previously selected package `a-sys v1.0.0 ([..])`
+    ... which satisfies dependency `a-sys = \"[..]\"` of package `foo v0.0.1 ([..])`
+  which links to the native library `z`, as do rejected versions of `b-sys`: \
+1.1.0, 1.0.0
+"));
+
+    assert_that(p.cargo("build").arg("--message-format").arg("json"),
+                execs().with_status(101).with_json(r#"
+    {
+        "reason": "resolve-conflict",
+        "message": "{...}",
+        "conflict": {
+            "dependency": "b-sys",
+            "requirement": "[..]",
+            "required_by": "foo 0.0.1 ([..])",
+            "required_by_path": [],
+            "conflicts": [
+                {
+                    "package_id": "a-sys 1.0.0 ([..])",
+                    "reason": "links",
+                    "links": "z",
+                    "visible_to": null,
+                    "rejected": ["1.1.0", "1.0.0"],
+                    "path": [
+                        {
+                            "package_id": "foo 0.0.1 ([..])",
+                            "requirement": "a-sys = [..]"
+                        }
+                    ]
+                }
+            ]
+        }
+    }
+"#));
+}
+
+#[test]
+fn public_dependency_unifies_versions() {
+    Package::new("c", "1.0.0").publish();
+    Package::new("c", "2.0.0").publish();
+    Package::new("a", "1.0.0").public_dep("c", "1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "1"
+            c = ">= 1.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.cargo("generate-lockfile"), execs().with_status(0));
+    let lock = p.read_lockfile();
+    assert!(lock.contains("name = \"c\"\nversion = \"1.0.0\""), "{}", lock);
+    assert!(!lock.contains("version = \"2.0.0\""), "{}", lock);
+}
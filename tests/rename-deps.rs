@@ -0,0 +1,263 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::git;
+use cargotest::support::paths;
+use cargotest::support::registry::Package;
+use cargotest::support::{execs, project};
+use hamcrest::assert_that;
+
+#[test]
+fn rename_dependency() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.2.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { version = "0.1.0" }
+            baz = { version = "0.2.0", package = "bar" }
+        "#)
+        .file("src/lib.rs", "
+            extern crate bar;
+            extern crate baz;
+        ");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn rename_with_different_names() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { path = "bar", package = "bar" }
+        "#)
+        .file("src/lib.rs", "
+            extern crate baz;
+        ")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [lib]
+            name = "random_name"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc [..]--crate-name foo [..]--extern baz=[..]librandom_name-[..]`"));
+}
+
+#[test]
+fn rename_git_fork() {
+    let bar = git::repo(&paths::root().join("bar-fork"))
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn fork() {}");
+    bar.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = {{ path = "bar" }}
+            bar-fork = {{ git = '{}', package = "bar" }}
+        "#, bar.url()))
+        .file("src/lib.rs", "
+            extern crate bar;
+            extern crate bar_fork;
+
+            pub fn foo() {
+                bar::upstream();
+                bar_fork::fork();
+            }
+        ")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.2.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn upstream() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn rename_git_fork_of_registry_dep() {
+    Package::new("rand", "0.1.0")
+        .file("src/lib.rs", "pub fn upstream() {}")
+        .publish();
+
+    let rand = git::repo(&paths::root().join("rand-fork"))
+        .file("Cargo.toml", r#"
+            [project]
+            name = "rand"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn fork() {}");
+    rand.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            rand = "0.1.0"
+            rand_fork = {{ git = '{}', package = "rand" }}
+        "#, rand.url()))
+        .file("src/lib.rs", "
+            extern crate rand;
+            extern crate rand_fork;
+
+            pub fn foo() {
+                rand::upstream();
+                rand_fork::fork();
+            }
+        ");
+
+    // Both packages have the same name and version, so only their source
+    // tells which one each dependency refers to.
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn rename_patched_dep() {
+    Package::new("bar", "0.1.0")
+        .file("src/lib.rs", "pub fn upstream() {}")
+        .publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { version = "0.1.0", package = "bar" }
+
+            [patch.crates-io]
+            bar = { path = "bar" }
+        "#)
+        .file("src/lib.rs", "
+            extern crate baz;
+
+            pub fn foo() {
+                baz::patched();
+            }
+        ")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "pub fn patched() {}");
+
+    // The dependency resolves to the patch, which comes from a different
+    // source than the dependency itself, and is still linked as `baz`.
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc [..]--crate-name foo [..]--extern baz=[..]libbar-[..]`"));
+}
+
+#[test]
+fn rename_optional_feature() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { path = "bar", package = "bar", optional = true }
+
+            [features]
+            default = ["baz"]
+        "#)
+        .file("src/lib.rs", r#"
+            #[cfg(feature = "baz")]
+            extern crate baz;
+
+            #[cfg(not(feature = "baz"))]
+            pub fn foo() { not_enabled() }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+    assert_that(p.cargo("build").arg("--no-default-features"),
+                execs().with_status(101));
+}
+
+#[test]
+fn feature_must_use_local_name() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = { path = "bar", package = "bar", optional = true }
+
+            [features]
+            foo = ["bar"]
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  Feature `foo` includes `bar` which is neither a dependency nor another feature
+"));
+}
@@ -1103,4 +1103,53 @@ fn run_default_multiple_required_features() {
                 execs().with_status(101).with_stderr("\
 error: `cargo run` requires that a project only have one executable; \
 use the `--bin` option to specify which one to run"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn resolver2_dev_dep_feature_not_required_for_build() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            resolver = "2"
+
+            [dependencies]
+            common = { path = "common" }
+
+            [dev-dependencies]
+            common = { path = "common", features = ["std"] }
+
+            [[bin]]
+            name = "foo"
+            required-features = ["common/std"]
+        "#)
+        .file("src/main.rs", r#"
+            extern crate common;
+            fn main() { common::std(); }
+        "#)
+        .file("common/Cargo.toml", r#"
+            [project]
+            name = "common"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            std = []
+        "#)
+        .file("common/src/lib.rs", r#"
+            #[cfg(feature = "std")]
+            pub fn std() {}
+        "#);
+    p.build();
+
+    // `std` is only enabled through the dev-dependency, which a plain build
+    // doesn't see, so the binary is skipped.
+    assert_that(p.cargo("build"),
+                execs().with_status(0));
+    assert_that(&p.bin("foo"), not(existing_file()));
+
+    assert_that(p.cargo("test"),
+                execs().with_status(0));
+}
@@ -11,14 +11,15 @@ use cargo::core::source::{SourceId, GitReference};
 use cargo::core::dependency::Kind::{self, Development};
 use cargo::core::{Dependency, PackageId, Summary, Registry};
 use cargo::util::{CargoResult, ToUrl};
-use cargo::core::resolver::{self, Method};
+use cargo::core::resolver::{self, Method, ResolveBehavior};
 
 fn resolve<R: Registry>(pkg: PackageId, deps: Vec<Dependency>,
                         registry: &mut R)
                         -> CargoResult<Vec<PackageId>> {
     let summary = Summary::new(pkg.clone(), deps, HashMap::new()).unwrap();
     let method = Method::Everything;
-    Ok(resolver::resolve(&[(summary, method)], &[], registry)?.iter().cloned().collect())
+    Ok(resolver::resolve(&[(summary, method)], &[], registry, false,
+                         ResolveBehavior::V1, None)?.iter().cloned().collect())
 }
 
 trait ToDep {
@@ -109,6 +110,9 @@ fn dep_loc(name: &str, location: &str) -> Dependency {
 fn dep_kind(name: &str, kind: Kind) -> Dependency {
     dep(name).clone_inner().set_kind(kind).into_dependency()
 }
+fn dep_public(name: &str, req: &str) -> Dependency {
+    dep_req(name, req).clone_inner().set_public(true).into_dependency()
+}
 
 fn registry(pkgs: Vec<Summary>) -> Vec<Summary> {
     pkgs
@@ -393,3 +397,119 @@ fn hard_equality() {
                                        ("foo", "1.0.0"),
                                        ("bar", "1.0.0")])));
 }
+
+#[test]
+fn backjumping_over_unrelated_versions() {
+    // Every version of `z` depends on a `leaf` that doesn't exist, which used
+    // to make the resolver retry `z` for every combination of the `a*`
+    // packages' versions before giving up.
+    let mut pkgs = Vec::new();
+    let mut deps = Vec::new();
+    for a in 0..8 {
+        let name = format!("a{}", a);
+        for v in 0..10 {
+            let id = PackageId::new(&name, &format!("1.0.{}", v)[..],
+                                    &registry_loc()).unwrap();
+            pkgs.push(Summary::new(id, Vec::new(), HashMap::new()).unwrap());
+        }
+        deps.push(dep_req(&name, "1"));
+    }
+    for v in 0..11 {
+        let id = PackageId::new("z", &format!("1.0.{}", v)[..],
+                                &registry_loc()).unwrap();
+        let summary = Summary::new(id, vec![dep_req("leaf", "=2.0.0")],
+                                   HashMap::new()).unwrap();
+        pkgs.push(summary);
+    }
+    pkgs.push(pkg!(("leaf", "1.0.0")));
+    deps.push(dep_req("z", "1"));
+
+    let mut reg = registry(pkgs);
+    let res = resolve(pkg_id("root"), deps, &mut reg);
+    assert!(res.is_err());
+}
+
+#[test]
+fn known_conflicts_still_find_older_version() {
+    // Once one of the newer `foo`s fails on `bar` the rest of them are
+    // skipped without being activated, but the oldest still has to be found.
+    let mut pkgs = Vec::new();
+    for v in 0..20 {
+        let bar = if v == 0 { "=1.0.0" } else { "=1.0.1" };
+        let id = PackageId::new("foo", &format!("1.0.{}", v)[..],
+                                &registry_loc()).unwrap();
+        let summary = Summary::new(id, vec![dep_req("bar", bar)],
+                                   HashMap::new()).unwrap();
+        pkgs.push(summary);
+    }
+    pkgs.push(pkg!(("bar", "1.0.0")));
+    pkgs.push(pkg!(("bar", "1.0.1")));
+
+    let mut reg = registry(pkgs);
+    let res = resolve(pkg_id("root"), vec![
+        dep_req("foo", "1"),
+        dep_req("bar", "=1.0.0"),
+    ], &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("foo", "1.0.0"),
+                                       ("bar", "1.0.0")])));
+}
+
+#[test]
+fn public_dependency_picks_a_single_version() {
+    // `root` sees `c` both directly and through the public dependency of `a`,
+    // so both have to be the same version.
+    let mut reg = registry(vec![
+        pkg!(("c", "1.0.0")),
+        pkg!(("c", "2.0.0")),
+        pkg!("a" => [dep_public("c", "1")]),
+    ]);
+
+    let res = resolve(pkg_id("root"), vec![
+        dep("a"),
+        dep_req("c", ">= 1.0.0"),
+    ], &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("a", "1.0.0"),
+                                       ("c", "1.0.0")])).exactly());
+}
+
+#[test]
+fn private_dependency_allows_several_versions() {
+    let mut reg = registry(vec![
+        pkg!(("c", "1.0.0")),
+        pkg!(("c", "2.0.0")),
+        pkg!("a" => [dep_req("c", "1")]),
+    ]);
+
+    let res = resolve(pkg_id("root"), vec![
+        dep("a"),
+        dep_req("c", ">= 1.0.0"),
+    ], &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("a", "1.0.0"),
+                                       ("c", "1.0.0"),
+                                       ("c", "2.0.0")])).exactly());
+}
+
+#[test]
+fn public_dependency_seen_transitively() {
+    // `b` re-exports `a`, which re-exports `c`, so `root` sees `c` as well.
+    let mut reg = registry(vec![
+        pkg!(("c", "1.0.0")),
+        pkg!(("c", "2.0.0")),
+        pkg!("a" => [dep_public("c", "1")]),
+        pkg!("b" => [dep_public("a", "1")]),
+    ]);
+
+    let res = resolve(pkg_id("root"), vec![
+        dep("b"),
+        dep_req("c", "=2.0.0"),
+    ], &mut reg);
+
+    let err = res.unwrap_err().to_string();
+    assert!(err.contains("sees through public dependencies"), "{}", err);
+}
@@ -247,6 +247,60 @@ hello b.rs
 "));
 }
 
+#[test]
+fn default_run() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            default-run = "a"
+        "#)
+        .file("src/lib.rs", "")
+        .file("src/bin/a.rs", r#"
+            fn main() { println!("hello a.rs"); }
+        "#)
+        .file("src/bin/b.rs", r#"
+            fn main() { println!("hello b.rs"); }
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0)
+                       .with_stdout("\
+hello a.rs
+"));
+    assert_that(p.cargo("run").arg("--bin").arg("b"),
+                execs().with_status(0)
+                       .with_stdout("\
+hello b.rs
+"));
+}
+
+#[test]
+fn default_run_unknown_bin() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            default-run = "c"
+        "#)
+        .file("src/bin/a.rs", "fn main() {}")
+        .file("src/bin/b.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(101)
+                       .with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  default-run target `c` was not found, it must name one of the package's \
+binary targets
+"));
+}
+
 #[test]
 fn run_example() {
     let p = project("foo")
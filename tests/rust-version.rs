@@ -0,0 +1,208 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::{project, execs};
+use cargotest::support::registry::Package;
+use hamcrest::assert_that;
+
+#[test]
+fn rust_version_satisfied() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn rust_version_too_high() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.9876.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] package `foo v0.0.1 ([..])` cannot be built because it requires \
+rustc 1.9876.0 or newer, while the currently active rustc version is [..]
+Either upgrade rustc or pass `--ignore-rust-version` to build anyway
+"));
+    assert_that(p.cargo("build").arg("--ignore-rust-version"),
+                execs().with_status(0));
+}
+
+#[test]
+fn dependency_rust_version_too_high() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.9876"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] package `bar v0.0.1 ([..])` cannot be built because it requires \
+rustc 1.9876 or newer, [..]"));
+}
+
+#[test]
+fn rust_version_bad_format() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.17.0-beta"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  `rust-version` must be a version such as \"1.17\" or \"1.17.0\", but found `1.17.0-beta`
+"));
+}
+
+#[test]
+fn update_prefers_supported_versions() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.10"
+
+            [dependencies]
+            bar = "1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "1.0.0").publish();
+    assert_that(p.cargo("generate-lockfile"),
+                execs().with_status(0));
+
+    Package::new("bar", "1.0.1").rust_version("1.9").publish();
+    Package::new("bar", "1.1.0").rust_version("1.9876").publish();
+
+    assert_that(p.cargo("update"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry `[..]`
+[UPDATING] bar v1.0.0 -> v1.0.1
+[HOLDING] bar v1.0.1 ([..]), as v1.1.0 requires rust 1.9876
+"));
+
+    let lock = p.read_lockfile();
+    assert!(lock.contains("name = \"bar\"\nversion = \"1.0.1\""), "{}", lock);
+}
+
+#[test]
+fn update_holds_back_patched_dep() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("bar", "1.1.0").rust_version("1.9876").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            rust-version = "1.10"
+
+            [dependencies]
+            bar = "1"
+
+            [patch.crates-io]
+            bar = { path = "bar" }
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "1.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("update"),
+                execs().with_status(0).with_stderr_contains("\
+[HOLDING] bar v1.0.0 ([..]bar), as v1.1.0 requires rust 1.9876"));
+}
+
+#[test]
+fn rust_version_in_metadata() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            rust-version = "1.17"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata").arg("--no-deps"),
+                execs().with_json(r#"
+    {
+        "packages": [
+            {
+                "name": "foo",
+                "version": "0.5.0",
+                "id": "foo[..]",
+                "source": null,
+                "dependencies": [],
+                "license": null,
+                "license_file": null,
+                "description": null,
+                "targets": [
+                    {
+                        "kind": [
+                            "lib"
+                        ],
+                        "crate_types": [
+                            "lib"
+                        ],
+                        "name": "foo",
+                        "src_path": "[..][/]foo[/]src[/]lib.rs"
+                    }
+                ],
+                "features": {},
+                "manifest_path": "[..]Cargo.toml",
+                "rust_version": "1.17"
+            }
+        ],
+        "workspace_members": ["foo 0.5.0 (path+file:[..]foo)"],
+        "resolve": null,
+        "version": 1
+    }"#));
+}
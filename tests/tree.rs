@@ -0,0 +1,223 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::git;
+use cargotest::support::paths;
+use cargotest::support::registry::Package;
+use cargotest::support::{project, execs, ProjectBuilder};
+use hamcrest::assert_that;
+
+fn kinds_project() -> ProjectBuilder {
+    Package::new("b", "1.0.0").publish();
+    Package::new("a", "1.0.0").dep("b", "1.0.0").publish();
+    Package::new("c", "1.0.0").publish();
+    Package::new("d", "1.0.0").publish();
+
+    project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "1.0"
+
+            [build-dependencies]
+            c = "1.0"
+
+            [dev-dependencies]
+            d = "1.0"
+        "#)
+        .file("src/lib.rs", "")
+}
+
+#[test]
+fn simple() {
+    let p = kinds_project();
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+└── a v1.0.0
+    └── b v1.0.0
+[build-dependencies]
+└── c v1.0.0
+[dev-dependencies]
+└── d v1.0.0
+"));
+}
+
+#[test]
+fn filter_edge_kinds() {
+    let p = kinds_project();
+
+    assert_that(p.cargo_process("tree").arg("-e").arg("normal,dev"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+└── a v1.0.0
+    └── b v1.0.0
+[dev-dependencies]
+└── d v1.0.0
+"));
+}
+
+#[test]
+fn prefix_depth() {
+    let p = kinds_project();
+
+    assert_that(p.cargo_process("tree").arg("--prefix").arg("depth"),
+                execs().with_status(0).with_stdout("\
+0foo v0.0.1 ([..])
+1a v1.0.0
+2b v1.0.0
+1c v1.0.0
+1d v1.0.0
+"));
+}
+
+#[test]
+fn invert() {
+    let p = kinds_project();
+
+    assert_that(p.cargo_process("tree").arg("-i").arg("b"),
+                execs().with_status(0).with_stdout("\
+b v1.0.0
+└── a v1.0.0
+    └── foo v0.0.1 ([..])
+"));
+}
+
+#[test]
+fn duplicates() {
+    Package::new("b", "1.0.0").publish();
+    Package::new("b", "2.0.0").publish();
+    Package::new("a", "1.0.0").dep("b", "1.0.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "1.0"
+            b = "2.0"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree").arg("--duplicates"),
+                execs().with_status(0).with_stdout("\
+b v1.0.0
+└── a v1.0.0
+    └── foo v0.0.1 ([..])
+
+b v2.0.0
+└── foo v0.0.1 ([..])
+"));
+}
+
+#[test]
+fn features_on_edges() {
+    Package::new("a", "1.0.0")
+        .feature("x", &[])
+        .feature("y", &[])
+        .publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = { version = "1.0", features = ["x"] }
+
+            [features]
+            default = ["a/y"]
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree").arg("-e").arg("features"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+└── a v1.0.0 [features: x, y]
+"));
+}
+
+#[test]
+fn filter_target() {
+    Package::new("a", "1.0.0").publish();
+    Package::new("b", "1.0.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "1.0"
+
+            [target.not-a-real-target.dependencies]
+            b = "1.0"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+└── a v1.0.0
+"));
+    assert_that(p.cargo("tree").arg("--target").arg("all"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+├── a v1.0.0
+└── b v1.0.0
+"));
+}
+
+#[test]
+fn renamed_dep_from_two_sources() {
+    Package::new("a", "1.0.0").publish();
+    let git = git::repo(&paths::root().join("a-fork"))
+        .file("Cargo.toml", r#"
+            [project]
+            name = "a"
+            version = "1.0.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    git.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = "1.0"
+
+            [build-dependencies]
+            a2 = {{ package = "a", git = '{}', version = "1.0" }}
+        "#, git.url()))
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+└── a v1.0.0
+[build-dependencies]
+└── a v1.0.0 ([..]a-fork[..])
+"));
+    assert_that(p.cargo("tree").arg("-e").arg("normal"),
+                execs().with_status(0).with_stdout("\
+foo v0.0.1 ([..])
+└── a v1.0.0
+"));
+}
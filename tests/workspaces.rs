@@ -1378,3 +1378,177 @@ fn exclude_but_also_depend() {
                 execs().with_status(0));
     assert_that(&p.root().join("foo/bar/target"), existing_dir());
 }
+
+#[test]
+fn inherit_package_fields() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+
+            [workspace.package]
+            version = "1.2.3"
+            authors = ["Rustaceans"]
+            license = "MIT"
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = { workspace = true }
+            authors = { workspace = true }
+            license = { workspace = true }
+        "#)
+        .file("bar/src/main.rs", r#"
+            fn main() {
+                assert_eq!(env!("CARGO_PKG_VERSION"), "1.2.3");
+                assert_eq!(env!("CARGO_PKG_AUTHORS"), "Rustaceans");
+            }
+        "#);
+    p.build();
+
+    assert_that(p.cargo("run").cwd(p.root().join("bar")),
+                execs().with_status(0));
+}
+
+#[test]
+fn inherit_dependencies() {
+    Package::new("dep", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+
+            [workspace.dependencies]
+            dep = "0.1"
+            baz = { path = "baz", features = ["a"] }
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            dep = { workspace = true }
+            baz = { workspace = true, features = ["b"] }
+        "#)
+        .file("bar/src/lib.rs", r#"
+            extern crate dep;
+            extern crate baz;
+
+            pub fn bar() {
+                baz::a();
+                baz::b();
+            }
+        "#)
+        .file("baz/Cargo.toml", r#"
+            [project]
+            name = "baz"
+            version = "0.1.0"
+            authors = []
+
+            [features]
+            a = []
+            b = []
+        "#)
+        .file("baz/src/lib.rs", r#"
+            #[cfg(feature = "a")]
+            pub fn a() {}
+            #[cfg(feature = "b")]
+            pub fn b() {}
+        "#);
+    p.build();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0));
+}
+
+#[test]
+fn inherit_missing_field() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = { workspace = true }
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]`project.version` was inherited from the workspace, but \
+`workspace.package.version` is not defined in `[..]Cargo.toml`"));
+}
+
+#[test]
+fn inherit_missing_dependency() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            dep = { workspace = true }
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]dependency `dep` was inherited from the workspace, but it is not listed \
+in `[workspace.dependencies]`"));
+}
+
+#[test]
+fn inherit_without_workspace() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = { workspace = true }
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]inherits fields from the workspace, but no workspace root could be found"));
+}
+
+#[test]
+fn inherit_from_excluding_workspace() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [workspace]
+            exclude = ["bar"]
+
+            [workspace.package]
+            version = "1.2.3"
+        "#)
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = { workspace = true }
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    p.build();
+
+    // `bar` isn't a member of the workspace above it, so it has no workspace
+    // to inherit from.
+    assert_that(p.cargo("build").cwd(p.root().join("bar")),
+                execs().with_status(101).with_stderr_contains("\
+[..]inherits fields from the workspace, but no workspace root could be found"));
+}